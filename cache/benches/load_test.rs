@@ -61,7 +61,7 @@ async fn traverse_up(children: Vec<CacheItem>) {
 		loop {
 			item = match item.parent().await {
 				Ok(item) => item,
-				Err(OdiliaError::Cache(CacheError::NoItem)) => {
+				Err(OdiliaError::Cache(CacheError::NoItem { .. })) => {
 					// Missing item from cache; there's always exactly one.
 					// Perhaps an item pointing to a special root/null node gets
 					// through? Not super important.
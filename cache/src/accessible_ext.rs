@@ -2,6 +2,7 @@ use crate::convertable::Convertable;
 use crate::AccessiblePrimitive;
 use crate::CacheProperties;
 use crate::OdiliaError;
+use crate::{is_landmark, is_visible};
 use atspi_common::{ObjectRef, RelationType, Role};
 use atspi_proxies::accessible::AccessibleProxy;
 use std::collections::HashMap;
@@ -50,10 +51,13 @@ pub trait AccessibleExt {
 		Self: Sized;
 	/* TODO: not sure where these should go since it requires both Text as a self interface and
 	 * Hyperlink as children interfaces. */
+	/// Find the next accessible with the given role.
+	/// If `visible_only` is set, items which are not on screen (see [`crate::is_visible`]) are skipped.
 	fn get_next<'a>(
 		&self,
 		role: Role,
 		backward: bool,
+		visible_only: bool,
 	) -> impl Future<Output = Result<Option<AccessibleProxy<'a>>, Self::Error>> + Send
 	where
 		Self: Sized;
@@ -228,6 +232,7 @@ impl AccessibleExt for AccessibleProxy<'_> {
 		&self,
 		role: Role,
 		backward: bool,
+		visible_only: bool,
 	) -> Result<Option<AccessibleProxy<'a>>, Self::Error>
 	where
 		Self: Sized,
@@ -250,7 +255,9 @@ impl AccessibleExt for AccessibleProxy<'_> {
 				return Ok(None);
 			}
 			// if it matches, then return it
-			if item.match_(role).await? {
+			if item.match_(role).await?
+				&& (!visible_only || is_visible(&item.get_state().await?))
+			{
 				return Ok(Some(item));
 			}
 			// if it doesnt match, add all edges
@@ -284,6 +291,11 @@ impl AccessibleExt for AccessibleProxy<'_> {
 	}
 	// TODO: make match more broad, allow use of other parameters; also, support multiple roles, since right now, multiple will just exit immediately with false
 	async fn match_(&self, role: Role) -> Result<bool, OdiliaError> {
-		Ok(self.get_role().await? == role)
+		let own_role = self.get_role().await?;
+		if role == Role::Landmark && own_role != Role::Landmark {
+			// only asked for when looking for landmarks, since it is another round trip for every item
+			return Ok(is_landmark(own_role, &self.get_attributes().await?));
+		}
+		Ok(own_role == role)
 	}
 }
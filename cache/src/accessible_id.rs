@@ -0,0 +1,158 @@
+use odilia_common::errors::AccessiblePrimitiveConversionError;
+use std::{fmt, str::FromStr};
+use zbus::zvariant::ObjectPath;
+
+use crate::AccessiblePrimitive;
+
+/// The object path prefix which AT-SPI puts in front of the ID of most accessibles.
+pub const ACCESSIBLE_PATH_PREFIX: &str = "/org/a11y/atspi/accessible/";
+
+/// The ID of an accessible which follows the usual AT-SPI path scheme.
+/// Applications are free to use other paths (GTK 4 does), which do not parse into this.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum AccessibleId {
+	/// `/org/a11y/atspi/accessible/root`, the application itself.
+	Root,
+	/// `/org/a11y/atspi/accessible/null`, which stands for "no accessible", like a missing parent.
+	Null,
+	/// `/org/a11y/atspi/accessible/1234`
+	Number(u64),
+}
+
+/// Parse an accessible's ID from its full object path, like `/org/a11y/atspi/accessible/12`, or from the last part of it alone, like `12`, `root` or `null`.
+/// # Errors
+/// - [`AccessiblePrimitiveConversionError::InvalidPath`] for an object path outside of the AT-SPI accessible scheme.
+/// - [`AccessiblePrimitiveConversionError::NoPathId`] if there is nothing after the prefix.
+/// - [`AccessiblePrimitiveConversionError::ParseError`] if the ID is neither `root`, `null` nor a number.
+pub fn parse_accessible_id(path: &str) -> Result<AccessibleId, AccessiblePrimitiveConversionError> {
+	let id = match path.strip_prefix(ACCESSIBLE_PATH_PREFIX) {
+		Some(id) => id,
+		None if path.starts_with('/') => {
+			return Err(AccessiblePrimitiveConversionError::InvalidPath)
+		}
+		None => path,
+	};
+	match id {
+		"" => Err(AccessiblePrimitiveConversionError::NoPathId),
+		"root" => Ok(AccessibleId::Root),
+		"null" => Ok(AccessibleId::Null),
+		// a sign is accepted by `parse`, but is never part of an ID
+		number if number.starts_with('+') => {
+			Err(AccessiblePrimitiveConversionError::InvalidPath)
+		}
+		number => number
+			.parse()
+			.map(AccessibleId::Number)
+			.map_err(AccessiblePrimitiveConversionError::ParseError),
+	}
+}
+
+impl fmt::Display for AccessibleId {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.write_str(ACCESSIBLE_PATH_PREFIX)?;
+		match self {
+			Self::Root => f.write_str("root"),
+			Self::Null => f.write_str("null"),
+			Self::Number(number) => write!(f, "{number}"),
+		}
+	}
+}
+impl FromStr for AccessibleId {
+	type Err = AccessiblePrimitiveConversionError;
+
+	fn from_str(path: &str) -> Result<Self, Self::Err> {
+		parse_accessible_id(path)
+	}
+}
+impl TryFrom<&ObjectPath<'_>> for AccessibleId {
+	type Error = AccessiblePrimitiveConversionError;
+
+	fn try_from(path: &ObjectPath<'_>) -> Result<Self, Self::Error> {
+		parse_accessible_id(path.as_str())
+	}
+}
+impl TryFrom<&AccessiblePrimitive> for AccessibleId {
+	type Error = AccessiblePrimitiveConversionError;
+
+	fn try_from(accessible: &AccessiblePrimitive) -> Result<Self, Self::Error> {
+		parse_accessible_id(&accessible.id)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{parse_accessible_id, AccessibleId};
+	use odilia_common::errors::AccessiblePrimitiveConversionError;
+	use zbus::zvariant::ObjectPath;
+
+	#[test]
+	fn every_form_of_id_parses() {
+		for (path, id) in [
+			("/org/a11y/atspi/accessible/root", AccessibleId::Root),
+			("/org/a11y/atspi/accessible/null", AccessibleId::Null),
+			("/org/a11y/atspi/accessible/1234", AccessibleId::Number(1234)),
+			("root", AccessibleId::Root),
+			("null", AccessibleId::Null),
+			("0", AccessibleId::Number(0)),
+		] {
+			assert_eq!(parse_accessible_id(path).ok(), Some(id), "{path}");
+		}
+		let path =
+			ObjectPath::try_from("/org/a11y/atspi/accessible/7").expect("valid path");
+		assert_eq!(AccessibleId::try_from(&path).ok(), Some(AccessibleId::Number(7)));
+	}
+	#[test]
+	fn odd_ids_are_rejected_with_the_reason() {
+		for path in [
+			"/org/Gnome/GTK/abab22",
+			"/org/a11y/atspi/accessible",
+			"/",
+			"+5",
+			"/org/a11y/atspi/accessible/+5",
+		] {
+			assert!(
+				matches!(
+					parse_accessible_id(path),
+					Err(AccessiblePrimitiveConversionError::InvalidPath)
+				),
+				"{path}"
+			);
+		}
+		for path in ["", "/org/a11y/atspi/accessible/"] {
+			assert!(
+				matches!(
+					parse_accessible_id(path),
+					Err(AccessiblePrimitiveConversionError::NoPathId)
+				),
+				"{path}"
+			);
+		}
+		for path in [
+			"-1",
+			"12a",
+			"Root",
+			"/org/a11y/atspi/accessible/1/2",
+			"99999999999999999999",
+			" 1",
+		] {
+			assert!(
+				matches!(
+					parse_accessible_id(path),
+					Err(AccessiblePrimitiveConversionError::ParseError(_))
+				),
+				"{path}"
+			);
+		}
+	}
+	#[test]
+	fn ids_display_as_their_full_path() {
+		for path in [
+			"/org/a11y/atspi/accessible/root",
+			"/org/a11y/atspi/accessible/null",
+			"/org/a11y/atspi/accessible/42",
+		] {
+			let id: AccessibleId = path.parse().expect("valid id");
+			assert_eq!(id.to_string(), path);
+		}
+	}
+}
@@ -0,0 +1,89 @@
+use std::{
+	collections::HashSet,
+	sync::{Arc, Mutex, PoisonError},
+};
+
+use crate::AccessiblePrimitive;
+
+/// The items which are being fetched in the background right now, so the same item is not fetched twice at once.
+#[derive(Debug, Default)]
+pub struct InFlight {
+	keys: Mutex<HashSet<AccessiblePrimitive>>,
+}
+
+impl InFlight {
+	#[must_use]
+	pub fn new() -> Self {
+		Self::default()
+	}
+	/// Start fetching `key`, unless it is already being fetched.
+	/// Returns a guard which marks the fetch as finished when it is dropped, or `None` if another fetch of `key` is still running.
+	#[must_use]
+	pub fn claim(self: &Arc<Self>, key: &AccessiblePrimitive) -> Option<InFlightGuard> {
+		self.lock()
+			.insert(key.clone())
+			.then(|| InFlightGuard { in_flight: Arc::clone(self), key: key.clone() })
+	}
+	/// Whether `key` is being fetched right now.
+	#[must_use]
+	pub fn contains(&self, key: &AccessiblePrimitive) -> bool {
+		self.lock().contains(key)
+	}
+	/// How many fetches are running right now.
+	#[must_use]
+	pub fn len(&self) -> usize {
+		self.lock().len()
+	}
+	#[must_use]
+	pub fn is_empty(&self) -> bool {
+		self.len() == 0
+	}
+	/// The set holds no invariants across a panic, so a poisoned lock is still safe to use.
+	fn lock(&self) -> std::sync::MutexGuard<'_, HashSet<AccessiblePrimitive>> {
+		self.keys.lock().unwrap_or_else(PoisonError::into_inner)
+	}
+}
+
+/// Held for as long as an item is being fetched; see [`InFlight::claim`].
+#[derive(Debug)]
+pub struct InFlightGuard {
+	in_flight: Arc<InFlight>,
+	key: AccessiblePrimitive,
+}
+
+impl Drop for InFlightGuard {
+	fn drop(&mut self) {
+		self.in_flight.lock().remove(&self.key);
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::InFlight;
+	use crate::AccessiblePrimitive;
+	use std::sync::Arc;
+
+	fn prim(id: &str) -> AccessiblePrimitive {
+		AccessiblePrimitive {
+			id: format!("/org/a11y/atspi/accessible/{id}"),
+			sender: ":1.2".into(),
+		}
+	}
+
+	#[test]
+	fn an_item_can_only_be_claimed_once_at_a_time() {
+		let in_flight = Arc::new(InFlight::new());
+		let first = in_flight.claim(&prim("1"));
+		assert!(first.is_some());
+		assert!(in_flight.claim(&prim("1")).is_none());
+		assert!(in_flight.contains(&prim("1")));
+		let other = in_flight.claim(&prim("2"));
+		assert!(other.is_some());
+		assert_eq!(in_flight.len(), 2);
+		drop(first);
+		assert!(!in_flight.contains(&prim("1")));
+		assert!(in_flight.claim(&prim("1")).is_some());
+		drop(other);
+		assert!(in_flight.is_empty());
+	}
+}
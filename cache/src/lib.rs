@@ -15,15 +15,22 @@ mod convertable;
 pub use convertable::Convertable;
 mod accessible_ext;
 pub use accessible_ext::AccessibleExt;
+mod accessible_id;
+pub use accessible_id::{parse_accessible_id, AccessibleId, ACCESSIBLE_PATH_PREFIX};
+mod in_flight;
+pub use in_flight::{InFlight, InFlightGuard};
 
 use std::{
-	collections::HashMap,
-	sync::{Arc, RwLock, Weak},
+	collections::{HashMap, HashSet},
+	future::Future,
+	hash::BuildHasher,
+	sync::{Arc, PoisonError, RwLock, RwLockReadGuard, Weak},
+	time::Duration,
 };
 
 use atspi_common::{
 	object_ref::ObjectRef, ClipType, CoordType, EventProperties, Granularity, InterfaceSet,
-	RelationType, Role, StateSet,
+	RelationType, Role, State, StateSet,
 };
 use atspi_proxies::{accessible::AccessibleProxy, text::TextProxy};
 use dashmap::DashMap;
@@ -33,6 +40,7 @@ use odilia_common::{
 	result::OdiliaResult,
 };
 use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast;
 use zbus::{
 	names::OwnedUniqueName,
 	zvariant::{ObjectPath, OwnedObjectPath},
@@ -53,7 +61,7 @@ type CacheKey = AccessiblePrimitive;
 type InnerCache = DashMap<CacheKey, Arc<RwLock<CacheItem>>, FxBuildHasher>;
 type ThreadSafeCache = Arc<InnerCache>;
 
-#[derive(Clone, Debug, PartialEq, Eq, Hash, Deserialize, Serialize)]
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Deserialize, Serialize)]
 /// A struct which represents the bare minimum of an accessible for purposes of caching.
 /// This makes some *possibly eronious* assumptions about what the sender is.
 pub struct AccessiblePrimitive {
@@ -73,6 +81,12 @@ pub struct AccessiblePrimitive {
 	pub sender: smartstring::alias::String,
 }
 impl AccessiblePrimitive {
+	/// The D-Bus object path of this accessible, for example `/org/a11y/atspi/accessible/1234`.
+	/// # Errors
+	/// If the ID is not a valid object path.
+	pub fn object_path(&self) -> zbus::zvariant::Result<OwnedObjectPath> {
+		OwnedObjectPath::try_from(self.id.as_str())
+	}
 	/// Convert into an [`atspi_proxies::accessible::AccessibleProxy`]. Must be async because the creation of an async proxy requires async itself.
 	/// # Errors
 	/// Will return a [`zbus::Error`] in the case of an invalid destination, path, or failure to create a `Proxy` from those properties.
@@ -81,9 +95,8 @@ impl AccessiblePrimitive {
 		self,
 		conn: &zbus::Connection,
 	) -> zbus::Result<AccessibleProxy<'a>> {
-		let id = self.id;
+		let path = self.object_path()?;
 		let sender = self.sender.clone();
-		let path: ObjectPath<'a> = id.try_into()?;
 		ProxyBuilder::new(conn)
 			.path(path)?
 			.destination(sender.as_str().to_owned())?
@@ -96,9 +109,8 @@ impl AccessiblePrimitive {
 	/// Will return a [`zbus::Error`] in the case of an invalid destination, path, or failure to create a `Proxy` from those properties.
 	#[tracing::instrument(skip_all, level = "trace", ret, err)]
 	pub async fn into_text<'a>(self, conn: &zbus::Connection) -> zbus::Result<TextProxy<'a>> {
-		let id = self.id;
+		let path = self.object_path()?;
 		let sender = self.sender.clone();
-		let path: ObjectPath<'a> = id.try_into()?;
 		ProxyBuilder::new(conn)
 			.path(path)?
 			.destination(sender.as_str().to_owned())?
@@ -107,6 +119,7 @@ impl AccessiblePrimitive {
 			.await
 	}
 	/// Turns any `atspi::event` type into an `AccessiblePrimitive`, the basic type which is used for keys in the cache.
+	/// Every event carries the unique bus name of its sender (see [`EventProperties::sender`]), so there is no need to guess a sender from context.
 	/// # Errors
 	/// The errors are self-explanitory variants of the [`odilia_common::errors::AccessiblePrimitiveConversionError`].
 	#[tracing::instrument(skip_all, level = "trace", ret, err)]
@@ -167,6 +180,11 @@ impl<'a> TryFrom<AccessibleProxy<'a>> for AccessiblePrimitive {
 		Ok(AccessiblePrimitive { id, sender })
 	}
 }
+impl From<&CacheItem> for AccessiblePrimitive {
+	fn from(cache_item: &CacheItem) -> AccessiblePrimitive {
+		cache_item.object.clone()
+	}
+}
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
 /// A struct representing an accessible. To get any information from the cache other than the stored information like role, interfaces, and states, you will need to instantiate an [`atspi_proxies::accessible::AccessibleProxy`] or other `*Proxy` type from atspi to query further info.
@@ -208,7 +226,7 @@ impl CacheItem {
 			let cache = strong_cache(&self.cache)?;
 			let arc_mut_parent = cache
 				.get_ref(&self.parent.key.clone())
-				.ok_or(CacheError::NoItem)?;
+				.ok_or_else(|| no_item(&self.parent.key))?;
 			self.parent.item = Arc::downgrade(&arc_mut_parent);
 			Ok(arc_mut_parent)
 		}
@@ -307,6 +325,52 @@ impl CacheItem {
 				.collect(),
 		})
 	}
+	/// The D-Bus object path of this item; see [`AccessiblePrimitive::object_path`].
+	/// # Errors
+	/// If the ID is not a valid object path.
+	pub fn object_path(&self) -> zbus::zvariant::Result<OwnedObjectPath> {
+		self.object.object_path()
+	}
+	/// The primitive of this item's parent, without looking the parent up in the cache.
+	#[must_use]
+	pub fn parent_primitive(&self) -> AccessiblePrimitive {
+		self.parent.key.clone()
+	}
+	/// The primitive of the application this item belongs to.
+	#[must_use]
+	pub fn app_primitive(&self) -> AccessiblePrimitive {
+		self.app.clone()
+	}
+	/// Whether `other` holds the same information as this item.
+	/// The handle to the cache, and the handles to the cached parent and children, are not compared: they say where an item is kept, not what it is.
+	/// `CacheItem` can not implement `PartialEq` because of those handles, so this is how to tell whether an item has really changed.
+	#[must_use]
+	pub fn same_content(&self, other: &CacheItem) -> bool {
+		// destructured, so a field added later can not be forgotten here
+		let CacheItem {
+			object,
+			app,
+			parent,
+			index,
+			children_num,
+			interfaces,
+			role,
+			states,
+			text,
+			children,
+			cache: _,
+		} = self;
+		*object == other.object
+			&& *app == other.app && parent.key == other.parent.key
+			&& *index == other.index
+			&& *children_num == other.children_num
+			&& *interfaces == other.interfaces
+			&& *role == other.role
+			&& *states == other.states
+			&& *text == other.text
+			&& children.len() == other.children.len()
+			&& children.iter().zip(&other.children).all(|(a, b)| a.key == b.key)
+	}
 	// Same as [`AccessibleProxy::get_children`], just offered as a non-async version.
 	/// Get a `Vec` of children with the same type as `Self`.
 	/// # Errors
@@ -322,11 +386,87 @@ impl CacheItem {
 				child_ref
 					.clone_inner()
 					.or_else(|| derefed_cache.get(&child_ref.key))
-					.ok_or(CacheError::NoItem)
+					.ok_or_else(|| no_item(&child_ref.key))
 			})
 			.collect::<Result<Vec<_>, _>>()?;
 		Ok(children)
 	}
+	/// Whether the item is currently shown on screen, according to its cached states.
+	/// See [`is_visible`] for the exact rules.
+	#[must_use]
+	pub fn is_visible(&self) -> bool {
+		is_visible(&self.states)
+	}
+}
+
+/// Whether an accessible with the given states is shown on screen.
+/// It must be both [`State::Showing`] and [`State::Visible`], and not [`State::Defunct`].
+/// Bounds are not cached, so an item with a zero-sized extent is still considered visible.
+#[must_use]
+pub fn is_visible(states: &StateSet) -> bool {
+	states.contains(State::Showing)
+		&& states.contains(State::Visible)
+		&& !states.contains(State::Defunct)
+}
+
+/// The kinds of landmark a page can mark a region with, as written in the `xml-roles` attribute.
+pub const LANDMARK_TYPES: [&str; 8] = [
+	"banner",
+	"complementary",
+	"contentinfo",
+	"form",
+	"main",
+	"navigation",
+	"region",
+	"search",
+];
+
+/// The kind of landmark an item is, like "navigation", from its `xml-roles` attribute, or from `landmark` where that is used instead.
+/// `xml-roles` may hold several roles separated by spaces; the first one which is a landmark is used.
+#[must_use]
+pub fn landmark_type<S: BuildHasher>(
+	attributes: &HashMap<String, String, S>,
+) -> Option<&'static str> {
+	["xml-roles", "landmark"]
+		.iter()
+		.filter_map(|key| attributes.get(*key))
+		.flat_map(|roles| roles.split_whitespace())
+		.find_map(|role| {
+			LANDMARK_TYPES
+				.iter()
+				.find(|landmark| landmark.eq_ignore_ascii_case(role))
+		})
+		.copied()
+}
+
+/// Whether an item is a landmark which structural navigation stops at.
+/// Browsers give most landmarks the [`Role::Landmark`] role, but some, like forms and search boxes, keep a role of their own and are only marked by their attributes.
+#[must_use]
+pub fn is_landmark<S: BuildHasher>(role: Role, attributes: &HashMap<String, String, S>) -> bool {
+	role == Role::Landmark || landmark_type(attributes).is_some()
+}
+
+/// How many levels [`Cache::nearest_cached_ancestor`] walks up through uncached items before giving up.
+/// Real trees are rarely more than a few dozen levels deep, so this mostly guards against applications which report a loop of parents.
+pub const ANCESTOR_WALK_LIMIT: usize = 64;
+
+/// Roles which hold text the user edits or reads through, so their text is kept even when it is empty.
+const TEXT_ROLES: [Role; 7] = [
+	Role::Entry,
+	Role::PasswordText,
+	Role::Text,
+	Role::Terminal,
+	Role::Paragraph,
+	Role::DocumentText,
+	Role::Editbar,
+];
+
+/// Whether the name of an accessible should be cached in place of its text.
+/// Buttons and links often implement `Text` with nothing in it, while their name says what they are.
+/// Empty text fields keep their empty text, so they are not read as their label.
+#[must_use]
+pub fn text_falls_back_to_name(role: Role, text: &str) -> bool {
+	text.is_empty() && !TEXT_ROLES.contains(&role)
 }
 
 /// A composition of an accessible ID and (possibly) a reference
@@ -362,6 +502,33 @@ impl From<AccessiblePrimitive> for CacheRef {
 	}
 }
 
+/// How long [`CacheItem`] queries wait for an application to answer, unless the cache is given another limit with [`Cache::with_query_timeout`].
+pub const DEFAULT_QUERY_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Wait for `query` for at most `limit`, so an application which has stopped responding can not stall the caller.
+/// A zero `limit` waits for as long as the query takes.
+/// # Errors
+/// [`OdiliaError::Timeout`] if the query did not finish in time, or whatever error the query itself returned.
+pub async fn with_timeout<T, F>(limit: Duration, query: F) -> OdiliaResult<T>
+where
+	F: Future<Output = OdiliaResult<T>>,
+{
+	if limit.is_zero() {
+		return query.await;
+	}
+	tokio::time::timeout(limit, query)
+		.await
+		.map_err(|_| OdiliaError::Timeout(limit))?
+}
+
+/// How long queries about `cache_item` may take, from the cache it belongs to.
+fn query_timeout(cache_item: &CacheItem) -> Duration {
+	cache_item
+		.cache
+		.upgrade()
+		.map_or(DEFAULT_QUERY_TIMEOUT, |cache| cache.query_timeout)
+}
+
 #[inline]
 #[tracing::instrument(level = "trace", ret, err)]
 async fn as_accessible(cache_item: &CacheItem) -> OdiliaResult<AccessibleProxy<'_>> {
@@ -375,6 +542,88 @@ async fn as_text(cache_item: &CacheItem) -> OdiliaResult<TextProxy<'_>> {
 	Ok(cache_item.object.clone().into_text(&cache.connection).await?)
 }
 
+/// Look up the targets of each relation with `lookup`, dropping any that are not available.
+/// A relation is only returned if at least one of its targets could be found.
+fn resolve_relations<F>(
+	relations: Vec<(RelationType, Vec<ObjectRef>)>,
+	lookup: F,
+) -> Vec<(RelationType, Vec<CacheItem>)>
+where
+	F: Fn(&AccessiblePrimitive) -> Option<CacheItem>,
+{
+	relations
+		.into_iter()
+		.filter_map(|(relation, object_refs)| {
+			let targets: Vec<CacheItem> =
+				object_refs
+					.into_iter()
+					.filter_map(|object_ref| {
+						let key = object_ref.into();
+						let target = lookup(&key);
+						if target.is_none() {
+							tracing::debug!(?key, ?relation, "Relation target is not cached; skipping");
+						}
+						target
+					})
+					.collect();
+			(!targets.is_empty()).then_some((relation, targets))
+		})
+		.collect()
+}
+
+/// Which of `ids` to evict to bring their number down to `target_len`, leaving out any in `protected`.
+/// Items are chosen in the order of `ids`; when too many are protected, every unprotected item is chosen, which still leaves more than `target_len`.
+#[must_use]
+pub fn select_evictions<S: BuildHasher>(
+	ids: Vec<CacheKey>,
+	protected: &HashSet<CacheKey, S>,
+	target_len: usize,
+) -> Vec<CacheKey> {
+	let excess = ids.len().saturating_sub(target_len);
+	ids.into_iter()
+		.filter(|id| !protected.contains(id))
+		.take(excess)
+		.collect()
+}
+
+/// The children of `item`, followed by the roots of any trees it embeds from other applications.
+/// Embedded trees (like a browser plugin's) are only linked to their embedder through a [`RelationType::Embeds`] relation in `relations`, so they are not in `item.children`.
+#[must_use]
+pub fn children_with_embeds(
+	item: &CacheItem,
+	relations: &[(RelationType, Vec<ObjectRef>)],
+) -> Vec<AccessiblePrimitive> {
+	let mut children: Vec<AccessiblePrimitive> =
+		item.children.iter().map(|child| child.key.clone()).collect();
+	let embedded = relations
+		.iter()
+		.filter(|(relation, _)| *relation == RelationType::Embeds)
+		.flat_map(|(_, targets)| targets.iter().cloned().map(AccessiblePrimitive::from));
+	for root in embedded {
+		if !children.contains(&root) {
+			children.push(root);
+		}
+	}
+	children
+}
+
+/// The item which embeds this one into another application's tree, from a [`RelationType::EmbeddedBy`] relation in `relations`.
+/// This takes the place of the parent for the root of an embedded tree.
+#[must_use]
+pub fn embedder(relations: &[(RelationType, Vec<ObjectRef>)]) -> Option<AccessiblePrimitive> {
+	relations
+		.iter()
+		.find(|(relation, _)| *relation == RelationType::EmbeddedBy)
+		.and_then(|(_, targets)| targets.first())
+		.cloned()
+		.map(AccessiblePrimitive::from)
+}
+
+/// The error for an item which is not in the cache, saying which item it was.
+fn no_item(key: &CacheKey) -> CacheError {
+	CacheError::NoItem { sender: key.sender.to_string(), id: key.id.clone() }
+}
+
 #[inline]
 #[tracing::instrument(level = "trace", ret, err)]
 fn strong_cache(weak_cache: &Weak<Cache>) -> OdiliaResult<Arc<Cache>> {
@@ -387,7 +636,7 @@ impl CacheItem {
 	/// - [`CacheError::NoItem`] if application is not in cache
 	pub fn get_application(&self) -> Result<Self, OdiliaError> {
 		let derefed_cache: Arc<Cache> = strong_cache(&self.cache)?;
-		derefed_cache.get(&self.app).ok_or(CacheError::NoItem.into())
+		derefed_cache.get(&self.app).ok_or_else(|| no_item(&self.app).into())
 	}
 	/// See [`atspi_proxies::accessible::AccessibleProxy::parent`]
 	/// # Errors
@@ -397,71 +646,77 @@ impl CacheItem {
 			.parent
 			.clone_inner()
 			.or_else(|| self.cache.upgrade()?.get(&self.parent.key));
-		parent_item.ok_or(CacheError::NoItem.into())
+		parent_item.ok_or_else(|| no_item(&self.parent.key).into())
 	}
 	/// See [`atspi_proxies::accessible::AccessibleProxy::get_attributes`]
 	/// # Errors
 	/// - If the item is no longer available over the AT-SPI connection.
+	/// - [`OdiliaError::Timeout`] if the application does not answer in time.
 	pub async fn get_attributes(&self) -> Result<HashMap<String, String>, OdiliaError> {
-		Ok(as_accessible(self).await?.get_attributes().await?)
+		with_timeout(query_timeout(self), async {
+			Ok(as_accessible(self).await?.get_attributes().await?)
+		})
+		.await
 	}
 	/// See [`atspi_proxies::accessible::AccessibleProxy::name`]
 	/// # Errors
 	/// - If the item is no longer available over the AT-SPI connection.
+	/// - [`OdiliaError::Timeout`] if the application does not answer in time.
 	pub async fn name(&self) -> Result<String, OdiliaError> {
-		Ok(as_accessible(self).await?.name().await?)
+		with_timeout(query_timeout(self), async {
+			Ok(as_accessible(self).await?.name().await?)
+		})
+		.await
 	}
 	/// See [`atspi_proxies::accessible::AccessibleProxy::locale`]
 	/// # Errors
 	/// - If the item is no longer available over the AT-SPI connection.
+	/// - [`OdiliaError::Timeout`] if the application does not answer in time.
 	pub async fn locale(&self) -> Result<String, OdiliaError> {
-		Ok(as_accessible(self).await?.locale().await?)
+		with_timeout(query_timeout(self), async {
+			Ok(as_accessible(self).await?.locale().await?)
+		})
+		.await
 	}
 	/// See [`atspi_proxies::accessible::AccessibleProxy::description`]
 	/// # Errors
 	/// - If the item is no longer available over the AT-SPI connection.
+	/// - [`OdiliaError::Timeout`] if the application does not answer in time.
 	pub async fn description(&self) -> Result<String, OdiliaError> {
-		Ok(as_accessible(self).await?.description().await?)
+		with_timeout(query_timeout(self), async {
+			Ok(as_accessible(self).await?.description().await?)
+		})
+		.await
 	}
 	/// See [`atspi_proxies::accessible::AccessibleProxy::get_relation_set`]
+	/// Targets which are not in the cache are skipped, and relations with no cached targets are left out entirely.
 	/// # Errors
 	/// - If the item is no longer available over the AT-SPI connection.
-	/// - The items mentioned are not in the cache.
+	/// - [`OdiliaError::Timeout`] if the application does not answer in time.
 	pub async fn get_relation_set(
 		&self,
 	) -> Result<Vec<(RelationType, Vec<Self>)>, OdiliaError> {
 		let cache = strong_cache(&self.cache)?;
-		as_accessible(self)
-			.await?
-			.get_relation_set()
-			.await?
-			.into_iter()
-			.map(|(relation, object_pairs)| {
-				(
-					relation,
-					object_pairs
-						.into_iter()
-						.map(|object_pair| {
-							cache.get(&object_pair.into()).ok_or(
-								OdiliaError::Cache(
-									CacheError::NoItem,
-								),
-							)
-						})
-						.collect::<Result<Vec<Self>, OdiliaError>>(),
-				)
-			})
-			.map(|(relation, result_selfs)| Ok((relation, result_selfs?)))
-			.collect::<Result<Vec<(RelationType, Vec<Self>)>, OdiliaError>>()
+		let relations = with_timeout(query_timeout(self), async {
+			Ok(as_accessible(self).await?.get_relation_set().await?)
+		})
+		.await?;
+		Ok(resolve_relations(relations, |key| cache.get(key)))
 	}
 	/// See [`atspi_proxies::accessible::AccessibleProxy::get_child_at_index`]
 	/// # Errors
 	/// - The items mentioned are not in the cache.
+	/// - [`CacheError::NoChild`] if the item has no child at `idx`.
 	pub fn get_child_at_index(&self, idx: i32) -> Result<Self, OdiliaError> {
-		self.get_children()?
-			.get(usize::try_from(idx)?)
-			.ok_or(CacheError::NoItem.into())
-			.cloned()
+		let index = usize::try_from(idx)?;
+		self.get_children()?.get(index).cloned().ok_or_else(|| {
+			CacheError::NoChild {
+				sender: self.object.sender.to_string(),
+				id: self.object.id.clone(),
+				index,
+			}
+			.into()
+		})
 	}
 }
 
@@ -726,8 +981,30 @@ impl CacheItem {
 pub struct Cache {
 	pub by_id: ThreadSafeCache,
 	pub connection: zbus::Connection,
+	/// Held for writing while [`Cache::replace_all`] swaps out the contents of the cache, and for reading by everything else.
+	/// Accessing `by_id` directly bypasses this guard. Like `by_id`, it is shared between clones of the cache.
+	replacing: Arc<RwLock<()>>,
+	/// When false, nothing is ever stored; see [`Cache::uncached`].
+	caching_enabled: bool,
+	/// Where changes to the cache are announced; see [`Cache::subscribe`].
+	changes: broadcast::Sender<CacheEvent>,
+	/// The items whose surroundings are being fetched by [`Cache::warm_ancestors_async`] right now.
+	warming: Arc<InFlight>,
+	/// How long queries made through a [`CacheItem`] wait for its application; see [`with_timeout`].
+	query_timeout: Duration,
 }
 
+/// A change made to the cache, as seen by subscribers from [`Cache::subscribe`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum CacheEvent {
+	Added(AccessiblePrimitive),
+	Removed(AccessiblePrimitive),
+	Modified(AccessiblePrimitive),
+}
+
+/// How many changes a subscriber can fall behind by before it starts missing the oldest ones.
+pub const CACHE_EVENT_CAPACITY: usize = 1024;
+
 // N.B.: we are using std RwLockes internally here, within the cache hashmap
 // entries. When adding async methods, take care not to hold these mutexes
 // across .await points.
@@ -742,8 +1019,48 @@ impl Cache {
 				FxBuildHasher::default(),
 			)),
 			connection: conn,
+			replacing: Arc::new(RwLock::new(())),
+			caching_enabled: true,
+			changes: broadcast::channel(CACHE_EVENT_CAPACITY).0,
+			warming: Arc::new(InFlight::new()),
+			query_timeout: DEFAULT_QUERY_TIMEOUT,
 		}
 	}
+	/// Create a cache which never stores anything, so every lookup misses and every item is built fresh from the application.
+	/// This is much slower, since each item costs several dbus calls every time it is needed; it is only meant to rule the cache in or out when looking for a bug.
+	/// Adding and modifying items does nothing.
+	#[must_use]
+	#[tracing::instrument(level = "debug", ret)]
+	pub fn uncached(conn: zbus::Connection) -> Self {
+		Self { caching_enabled: false, ..Self::new(conn) }
+	}
+	/// Give up on queries made through the cached items after `limit`, instead of [`DEFAULT_QUERY_TIMEOUT`].
+	/// A zero `limit` waits forever.
+	#[must_use]
+	pub fn with_query_timeout(self, limit: Duration) -> Self {
+		Self { query_timeout: limit, ..self }
+	}
+	/// Whether items are kept in the cache at all; false for a cache made with [`Self::uncached`].
+	#[must_use]
+	pub fn caching_enabled(&self) -> bool {
+		self.caching_enabled
+	}
+	/// Get told about every item which is added to, removed from or modified in the cache from now on.
+	/// Changes are never held back for a slow subscriber: once it falls [`CACHE_EVENT_CAPACITY`] changes behind, it misses the oldest ones and gets [`broadcast::error::RecvError::Lagged`] instead.
+	#[must_use]
+	pub fn subscribe(&self) -> broadcast::Receiver<CacheEvent> {
+		self.changes.subscribe()
+	}
+	/// Tell subscribers about a change; this does nothing when there are none.
+	fn notify(&self, event: CacheEvent) {
+		// sending only fails when nobody is subscribed, which is fine
+		let _ = self.changes.send(event);
+	}
+	/// Block replacement of the whole cache for as long as the guard is held.
+	/// The guard protects no data, so a poisoned lock is still safe to use.
+	fn read_guard(&self) -> RwLockReadGuard<'_, ()> {
+		self.replacing.read().unwrap_or_else(PoisonError::into_inner)
+	}
 	/// add a single new item to the cache. Note that this will empty the bucket
 	/// before inserting the `CacheItem` into the cache (this is so there is
 	/// never two items with the same ID stored in the cache at the same time).
@@ -755,6 +1072,20 @@ impl Cache {
 		self.add_ref(id, &Arc::new(RwLock::new(cache_item)))
 	}
 
+	/// Add a freshly built item, then get back the stored copy, which has its references to the rest of the cache filled in.
+	/// When caching is disabled nothing is stored, so the item is handed back as it is.
+	/// # Errors
+	/// Fails if [`Self::add`] fails, or if the item was removed again before it could be read back.
+	#[tracing::instrument(level = "trace", ret, err)]
+	pub fn add_and_get(&self, cache_item: CacheItem) -> OdiliaResult<CacheItem> {
+		if !self.caching_enabled {
+			return Ok(cache_item);
+		}
+		let id = cache_item.object.clone();
+		self.add(cache_item)?;
+		self.get(&id).ok_or_else(|| no_item(&id).into())
+	}
+
 	/// Add an item via a reference instead of creating the reference.
 	/// # Errors
 	/// Can error if [`Cache::populate_references`] errors. The insertion is guarenteed to succeed.
@@ -764,14 +1095,23 @@ impl Cache {
 		id: CacheKey,
 		cache_item: &Arc<RwLock<CacheItem>>,
 	) -> OdiliaResult<()> {
-		self.by_id.insert(id, Arc::clone(cache_item));
-		Self::populate_references(&self.by_id, cache_item)
+		if !self.caching_enabled {
+			return Ok(());
+		}
+		let _guard = self.read_guard();
+		self.by_id.insert(id.clone(), Arc::clone(cache_item));
+		Self::populate_references(&self.by_id, cache_item)?;
+		self.notify(CacheEvent::Added(id));
+		Ok(())
 	}
 
 	/// Remove a single cache item. This function can not fail.
 	#[tracing::instrument(level = "trace", ret)]
 	pub fn remove(&self, id: &CacheKey) {
-		self.by_id.remove(id);
+		let _guard = self.read_guard();
+		if self.by_id.remove(id).is_some() {
+			self.notify(CacheEvent::Removed(id.clone()));
+		}
 	}
 
 	/// Get a single item from the cache, this only gets a reference to an item, not the item itself.
@@ -780,6 +1120,7 @@ impl Cache {
 	#[must_use]
 	#[tracing::instrument(level = "trace", ret)]
 	pub fn get_ref(&self, id: &CacheKey) -> Option<Arc<RwLock<CacheItem>>> {
+		let _guard = self.read_guard();
 		self.by_id.get(id).as_deref().cloned()
 	}
 
@@ -790,6 +1131,7 @@ impl Cache {
 	#[must_use]
 	#[tracing::instrument(level = "trace", ret)]
 	pub fn get(&self, id: &CacheKey) -> Option<CacheItem> {
+		let _guard = self.read_guard();
 		Some(self.by_id.get(id).as_deref()?.read().ok()?.clone())
 	}
 
@@ -797,7 +1139,189 @@ impl Cache {
 	#[must_use]
 	#[tracing::instrument(level = "trace", ret)]
 	pub fn get_all(&self, ids: &[CacheKey]) -> Vec<Option<CacheItem>> {
-		ids.iter().map(|id| self.get(id)).collect()
+		let _guard = self.read_guard();
+		ids.iter()
+			.map(|id| Some(self.by_id.get(id).as_deref()?.read().ok()?.clone()))
+			.collect()
+	}
+
+	/// Get the cached ancestors of an item, starting with its parent and ending with the furthest ancestor which is still in the cache.
+	/// The item itself is not included.
+	#[must_use]
+	#[tracing::instrument(level = "trace", ret)]
+	pub fn ancestors(&self, id: &CacheKey) -> Vec<CacheItem> {
+		let mut ancestors: Vec<CacheItem> = Vec::new();
+		let mut key = self.get(id).map(|item| item.parent.key);
+		while let Some(parent_key) = key {
+			// stop at the root (which is its own parent), and on any other loop in the tree
+			if &parent_key == id
+				|| ancestors.iter().any(|item| item.object == parent_key)
+			{
+				break;
+			}
+			let Some(parent) = self.get(&parent_key) else {
+				break;
+			};
+			key = Some(parent.parent.key.clone());
+			ancestors.push(parent);
+		}
+		ancestors
+	}
+
+	/// Get an item and all of its cached descendants, in reading order: each item comes before its children, and children in order.
+	/// Children which are not cached are skipped, along with everything below them.
+	#[must_use]
+	#[tracing::instrument(level = "trace", skip(self))]
+	pub fn descendants(&self, id: &CacheKey) -> Vec<CacheItem> {
+		let mut descendants: Vec<CacheItem> = Vec::new();
+		let mut stack = vec![id.clone()];
+		while let Some(key) = stack.pop() {
+			// a child listed twice, or a loop in the tree, would otherwise be walked forever
+			if descendants.iter().any(|item| item.object == key) {
+				continue;
+			}
+			let Some(item) = self.get(&key) else {
+				continue;
+			};
+			stack.extend(item.children.iter().rev().map(|child| child.key.clone()));
+			descendants.push(item);
+		}
+		descendants
+	}
+
+	/// Get the cached parent of an item.
+	/// Returns `None` for the root (which is its own parent), and when the parent is not in the cache.
+	#[must_use]
+	pub fn parent_of(&self, id: &CacheKey) -> Option<CacheItem> {
+		let parent_key = self.get(id)?.parent.key;
+		if &parent_key == id {
+			return None;
+		}
+		self.get(&parent_key)
+	}
+
+	/// Get the closest ancestor of an item which is in the cache, even when the item itself is not.
+	/// The parents of uncached items are asked for over dbus, one level at a time, for at most [`ANCESTOR_WALK_LIMIT`] levels.
+	/// Returns `None` when the walk reaches the root, gives up, or the application can not be asked.
+	pub async fn nearest_cached_ancestor(&self, id: &CacheKey) -> Option<CacheItem> {
+		self.nearest_cached_ancestor_by(id, |key| async move {
+			let accessible = key.into_accessible(&self.connection).await.ok()?;
+			Some(AccessiblePrimitive::from(accessible.parent().await.ok()?))
+		})
+		.await
+	}
+
+	/// [`Self::nearest_cached_ancestor`], finding the parent of each uncached item with `uncached_parent`.
+	async fn nearest_cached_ancestor_by<F, Fut>(
+		&self,
+		id: &CacheKey,
+		mut uncached_parent: F,
+	) -> Option<CacheItem>
+	where
+		F: FnMut(CacheKey) -> Fut,
+		Fut: Future<Output = Option<CacheKey>>,
+	{
+		let mut key = id.clone();
+		for _ in 0..ANCESTOR_WALK_LIMIT {
+			let parent_key = match self.get(&key) {
+				Some(item) => item.parent.key,
+				None => uncached_parent(key.clone()).await?,
+			};
+			// the root is its own parent
+			if parent_key == key {
+				return None;
+			}
+			if let Some(parent) = self.get(&parent_key) {
+				return Some(parent);
+			}
+			key = parent_key;
+		}
+		None
+	}
+
+	/// Get the position of an item among its parent's children.
+	/// The index the application gave the item is used when it is known; otherwise the item is looked for in its cached parent's children.
+	/// Returns `None` when the item is not cached, or neither is known.
+	#[must_use]
+	pub fn index_of(&self, id: &CacheKey) -> Option<usize> {
+		let item = self.get(id)?;
+		if item.index.is_some() {
+			return item.index;
+		}
+		self.parent_of(id)?.children.iter().position(|child| &child.key == id)
+	}
+
+	/// Get the first child of an item, if the item has any children and the first one is cached.
+	#[must_use]
+	pub fn first_child_of(&self, id: &CacheKey) -> Option<CacheItem> {
+		let item = self.get(id)?;
+		self.get(&item.children.first()?.key)
+	}
+
+	/// Get the sibling directly after an item in its parent's children, if it is cached.
+	#[must_use]
+	pub fn next_sibling_of(&self, id: &CacheKey) -> Option<CacheItem> {
+		self.sibling_of(id, true)
+	}
+
+	/// Get the sibling directly before an item in its parent's children, if it is cached.
+	#[must_use]
+	pub fn previous_sibling_of(&self, id: &CacheKey) -> Option<CacheItem> {
+		self.sibling_of(id, false)
+	}
+
+	fn sibling_of(&self, id: &CacheKey, forward: bool) -> Option<CacheItem> {
+		let siblings = self.parent_of(id)?.children;
+		let position = siblings.iter().position(|sibling| &sibling.key == id)?;
+		let sibling_position =
+			if forward { position + 1 } else { position.checked_sub(1)? };
+		self.get(&siblings.get(sibling_position)?.key)
+	}
+
+	/// Get up to `count` children of `parent`, starting at child `start`, in order, under a single read handle.
+	/// Children which are not cached are `None`, so the position of each item in the window is kept.
+	/// The window ends early at the last child, and is empty when `parent` is not cached.
+	#[must_use]
+	#[tracing::instrument(level = "trace", ret)]
+	pub fn get_many_present(
+		&self,
+		parent: &CacheKey,
+		start: usize,
+		count: usize,
+	) -> Vec<Option<CacheItem>> {
+		let _guard = self.read_guard();
+		let get = |id: &CacheKey| Some(self.by_id.get(id).as_deref()?.read().ok()?.clone());
+		let Some(parent) = get(parent) else {
+			return Vec::new();
+		};
+		parent.children
+			.iter()
+			.skip(start)
+			.take(count)
+			.map(|child| get(&child.key))
+			.collect()
+	}
+
+	/// Whether `id` is `container` itself, or one of its cached descendants.
+	/// An item whose chain of parents leaves the cache before reaching `container` is treated as outside of it.
+	#[must_use]
+	pub fn is_within(&self, id: &CacheKey, container: &CacheKey) -> bool {
+		id == container || self.ancestors(id).iter().any(|item| &item.object == container)
+	}
+
+	/// Get every item in the cache which has one of the given roles.
+	/// Like [`Self::get`], this clones each matching item; the order of the returned items is not specified.
+	#[must_use]
+	#[tracing::instrument(level = "trace", ret)]
+	pub fn find_by_role(&self, roles: &[Role]) -> Vec<CacheItem> {
+		let _guard = self.read_guard();
+		self.by_id
+			.iter()
+			.filter_map(|entry| {
+				let item = entry.value().read().ok()?;
+				roles.contains(&item.role).then(|| item.clone())
+			})
+			.collect()
 	}
 
 	/// Bulk add many items to the cache; only one accessible should ever be
@@ -806,27 +1330,101 @@ impl Cache {
 	/// An `Err(_)` variant may be returned if the [`Cache::populate_references`] function fails.
 	#[tracing::instrument(level = "trace", ret, err)]
 	pub fn add_all(&self, cache_items: Vec<CacheItem>) -> OdiliaResult<()> {
+		if !self.caching_enabled {
+			return Ok(());
+		}
+		let _guard = self.read_guard();
+		self.insert_all(cache_items)
+	}
+	/// Replace the entire contents of the cache with `cache_items`, for example after reconnecting to the accessibility bus.
+	/// Anyone reading through the methods on [`Cache`] will either see all of the old items or all of the new ones, never a mix of the two.
+	/// # Errors
+	/// An `Err(_)` variant may be returned if the [`Cache::populate_references`] function fails.
+	#[tracing::instrument(level = "debug", skip_all, err)]
+	pub fn replace_all(&self, cache_items: Vec<CacheItem>) -> OdiliaResult<()> {
+		if !self.caching_enabled {
+			return Ok(());
+		}
+		let _guard = self.replacing.write().unwrap_or_else(PoisonError::into_inner);
+		if self.changes.receiver_count() > 0 {
+			for entry in self.by_id.iter() {
+				self.notify(CacheEvent::Removed(entry.key().clone()));
+			}
+		}
+		self.by_id.clear();
+		self.insert_all(cache_items)
+	}
+	/// Insert many items, then link them all together once they are all present.
+	fn insert_all(&self, cache_items: Vec<CacheItem>) -> OdiliaResult<()> {
+		let cache = &self.by_id;
+		let added: Vec<CacheKey> = if self.changes.receiver_count() > 0 {
+			cache_items
+				.iter()
+				.map(|cache_item| cache_item.object.clone())
+				.collect()
+		} else {
+			Vec::new()
+		};
 		cache_items
 			.into_iter()
 			.map(|cache_item| {
 				let id = cache_item.object.clone();
 				let arc = Arc::new(RwLock::new(cache_item));
-				self.by_id.insert(id, Arc::clone(&arc));
+				cache.insert(id, Arc::clone(&arc));
 				arc
 			})
 			.collect::<Vec<_>>() // Insert all items before populating
 			.into_iter()
-			.try_for_each(|item| Self::populate_references(&self.by_id, &item))
+			.try_for_each(|item| Self::populate_references(cache, &item))?;
+		for id in added {
+			self.notify(CacheEvent::Added(id));
+		}
+		Ok(())
 	}
 	/// Bulk remove all ids in the cache; this only refreshes the cache after removing all items.
 	#[tracing::instrument(level = "trace", ret)]
 	pub fn remove_all(&self, ids: &Vec<CacheKey>) {
+		let _guard = self.read_guard();
 		for id in ids {
-			self.by_id.remove(id);
+			if self.by_id.remove(id).is_some() {
+				self.notify(CacheEvent::Removed(id.clone()));
+			}
 		}
 	}
+	/// Remove every item which belongs to the application with the given unique bus name (like `:1.42`), for example because the application has closed.
+	/// Returns how many items were removed.
+	#[tracing::instrument(level = "debug", ret)]
+	pub fn prune_by_sender(&self, sender: &str) -> usize {
+		let _guard = self.read_guard();
+		let before = self.by_id.len();
+		self.by_id.retain(|key, _| {
+			let keep = key.sender.as_str() != sender;
+			if !keep {
+				self.notify(CacheEvent::Removed(key.clone()));
+			}
+			keep
+		});
+		before.saturating_sub(self.by_id.len())
+	}
+
+	/// Evict items until at most `target_len` are left, for when memory is short.
+	/// The items in `keep` (like the focused item and the recently visited ones) and all of their cached ancestors are never evicted, so fewer items may be evicted than asked for.
+	/// Returns how many items were evicted.
+	#[tracing::instrument(level = "debug", skip(self, keep), ret)]
+	pub fn trim(&self, target_len: usize, keep: &[CacheKey]) -> usize {
+		let mut protected: HashSet<CacheKey> = HashSet::new();
+		for key in keep {
+			protected.extend(self.ancestors(key).into_iter().map(|item| item.object));
+			protected.insert(key.clone());
+		}
+		let ids = self.by_id.iter().map(|entry| entry.key().clone()).collect();
+		let evicted = select_evictions(ids, &protected, target_len);
+		self.remove_all(&evicted);
+		evicted.len()
+	}
 
 	/// Edit a mutable `CacheItem`. Returns true if the update was successful.
+	/// Nothing is edited when caching is disabled, since there is nothing stored to edit.
 	///
 	/// Note: an exclusive lock for the given cache item will be placed for the
 	/// entire length of the passed function, so try to avoid any compute in it.
@@ -839,9 +1437,13 @@ impl Cache {
 	where
 		F: FnOnce(&mut CacheItem),
 	{
+		if !self.caching_enabled {
+			return Ok(false);
+		}
 		// I wonder if `get_mut` vs `get` makes any difference here? I suppose
 		// it will just rely on the dashmap write access vs mutex lock access.
 		// Let's default to the fairness of the mutex.
+		let _guard = self.read_guard();
 		let entry = if let Some(i) = self.by_id.get(id) {
 			// Drop the dashmap reference immediately, at the expense of an Arc clone.
 			(*i).clone()
@@ -851,6 +1453,86 @@ impl Cache {
 		};
 		let mut cache_item = entry.write()?;
 		modify(&mut cache_item);
+		self.notify(CacheEvent::Modified(id.clone()));
+		Ok(true)
+	}
+
+	/// Edit several `CacheItem`s together, for changes which must not be seen half done, like re-indexing siblings.
+	/// The items are passed to `modify` in the order of `ids`, with repeated IDs only passed once.
+	/// Returns true if the update was successful; if any of the items are not in the cache, nothing is modified and false is returned.
+	///
+	/// Note: an exclusive lock on every item is held for the entire length of the passed function, so keep it short, and do not use the cache from inside of it.
+	/// To avoid deadlocks between two batches which share items, the locks are always taken in the order of the items' keys, no matter the order of `ids`.
+	///
+	/// # Errors
+	///
+	/// An [`odilia_common::errors::OdiliaError::PoisoningError`] may be returned if a write lock can not be acquired on any of the `CacheItem`s being modified.
+	#[tracing::instrument(level = "trace", skip(modify), ret, err)]
+	pub fn with_items_mut<F>(&self, ids: &[CacheKey], modify: F) -> OdiliaResult<bool>
+	where
+		F: FnOnce(&mut [&mut CacheItem]),
+	{
+		let _guard = self.read_guard();
+		let mut unique: Vec<&CacheKey> = Vec::with_capacity(ids.len());
+		for id in ids {
+			if !unique.contains(&id) {
+				unique.push(id);
+			}
+		}
+		// each entry remembers its position in `ids`, so it can be handed back in that order once locked
+		let mut entries = Vec::with_capacity(unique.len());
+		for (position, id) in unique.into_iter().enumerate() {
+			let Some(entry) = self.by_id.get(id) else {
+				tracing::trace!(
+					"The cache does not contain the requested item: {:?}",
+					id
+				);
+				return Ok(false);
+			};
+			entries.push((position, id, (*entry).clone()));
+		}
+		entries.sort_by_key(|(_, id, _)| *id);
+		let mut locked = Vec::with_capacity(entries.len());
+		for (position, _, entry) in &entries {
+			locked.push((*position, entry.write()?));
+		}
+		locked.sort_by_key(|(position, _)| *position);
+		let mut items: Vec<&mut CacheItem> =
+			locked.iter_mut().map(|(_, cache_item)| &mut **cache_item).collect();
+		modify(&mut items);
+		for (_, id, _) in &entries {
+			self.notify(CacheEvent::Modified((*id).clone()));
+		}
+		Ok(true)
+	}
+
+	/// Replace the children of `parent` with `children`, in their new order, and give each cached child its new index.
+	/// Used when an application reorders the rows or columns of a table, which would otherwise leave sibling navigation following the old order.
+	/// Returns `false`, changing nothing, when `parent` is not cached.
+	///
+	/// # Errors
+	///
+	/// An [`odilia_common::errors::OdiliaError::PoisoningError`] may be returned if a write lock can not be acquired on any of the `CacheItem`s being modified.
+	#[tracing::instrument(level = "trace", skip(self), ret, err)]
+	pub fn reorder_children(
+		&self,
+		parent: &CacheKey,
+		children: Vec<CacheKey>,
+	) -> OdiliaResult<bool> {
+		let modified = self.modify_item(parent, |item| {
+			item.children_num = Some(children.len());
+			item.children = children.iter().cloned().map(CacheRef::new).collect();
+		})?;
+		if !modified {
+			return Ok(false);
+		}
+		for (index, child) in children.iter().enumerate() {
+			self.modify_item(child, |item| item.index = Some(index))?;
+		}
+		// the new references do not point at the cached children yet
+		if let Some(parent) = self.get_ref(parent) {
+			Self::populate_references(&self.by_id, &parent)?;
+		}
 		Ok(true)
 	}
 
@@ -884,6 +1566,66 @@ impl Cache {
 		Ok(cache_item)
 	}
 
+	/// Make sure the ancestors and immediate children of an item are cached, fetching whatever is missing in the background.
+	/// This is meant for a newly focused item, so its focus can be announced without waiting for the items around it.
+	/// Nothing is started when caching is off, or when the items around the same item are still being fetched from an earlier call.
+	pub fn warm_ancestors_async(self: &Arc<Self>, id: CacheKey) {
+		if !self.caching_enabled {
+			return;
+		}
+		let Some(guard) = self.warming.claim(&id) else {
+			tracing::trace!("Already warming the items around {id:?}");
+			return;
+		};
+		let cache = Arc::clone(self);
+		tokio::spawn(async move {
+			let _guard = guard;
+			if let Err(e) = cache.warm_around(&id).await {
+				tracing::debug!(error = %e, "Could not warm the items around {id:?}");
+			}
+		});
+	}
+
+	/// Fetch and cache whatever is missing of the immediate children of `id` and its ancestors, up to [`ANCESTOR_WALK_LIMIT`] levels.
+	async fn warm_around(self: &Arc<Self>, id: &CacheKey) -> OdiliaResult<()> {
+		let item = match self.get(id) {
+			Some(item) => item,
+			None => self.fetch(id).await?,
+		};
+		for child in &item.children {
+			if self.get(&child.key).is_none() {
+				// children come and go, so one which has already gone away should not stop the rest
+				if let Err(e) = self.fetch(&child.key).await {
+					tracing::trace!(error = %e, "Could not fetch a child while warming");
+				}
+			}
+		}
+		let mut key = item.object;
+		let mut parent = item.parent.key;
+		for _ in 0..ANCESTOR_WALK_LIMIT {
+			// the root is its own parent, and the desktop's parent is null
+			if parent == key
+				|| matches!(AccessibleId::try_from(&parent), Ok(AccessibleId::Null))
+			{
+				break;
+			}
+			let ancestor = match self.get(&parent) {
+				Some(ancestor) => ancestor,
+				None => self.fetch(&parent).await?,
+			};
+			key = parent;
+			parent = ancestor.parent.key;
+		}
+		Ok(())
+	}
+
+	/// Build an item from its application and add it to the cache.
+	async fn fetch(self: &Arc<Self>, id: &CacheKey) -> OdiliaResult<CacheItem> {
+		let accessible = id.clone().into_accessible(&self.connection).await?;
+		let item = accessible_to_cache_item(&accessible, Arc::downgrade(self)).await?;
+		self.add_and_get(item)
+	}
+
 	/// Populate children and parent references given a cache and an `Arc<RwLock<CacheItem>>`.
 	/// This will unlock the `RwLock<_>`, update the references for children and parents, then go to the parent and children and do the same: update the parent for the children, then update the children referneces for the parent.
 	/// # Errors
@@ -956,10 +1698,12 @@ pub async fn accessible_to_cache_item(
 	// if it implements the Text interface
 	let text = match accessible.to_text().await {
 		// get *all* the text
-		Ok(text_iface) => text_iface.get_all_text().await,
+		Ok(text_iface) => text_iface.get_all_text().await?,
 		// otherwise, use the name instaed
-		Err(_) => Ok(accessible.name().await?),
-	}?;
+		Err(_) => accessible.name().await?,
+	};
+	let text =
+		if text_falls_back_to_name(role, &text) { accessible.name().await? } else { text };
 	Ok(CacheItem {
 		object: accessible.try_into()?,
 		app: app.into(),
@@ -974,3 +1718,704 @@ pub async fn accessible_to_cache_item(
 		cache,
 	})
 }
+
+#[cfg(test)]
+mod tests {
+	use super::{
+		children_with_embeds, embedder, is_landmark, is_visible, landmark_type,
+		resolve_relations, select_evictions, text_falls_back_to_name, with_timeout,
+		AccessiblePrimitive, Cache, CacheEvent, CacheItem, CacheKey, CacheRef,
+		ANCESTOR_WALK_LIMIT, CACHE_EVENT_CAPACITY,
+	};
+	use atspi_common::{
+		object_ref::ObjectRef, InterfaceSet, RelationType, Role, State, StateSet,
+	};
+	use odilia_common::{errors::OdiliaError, result::OdiliaResult};
+	use std::{
+		collections::{HashMap, HashSet},
+		sync::{
+			atomic::{AtomicBool, Ordering},
+			Arc, Weak,
+		},
+		time::Duration,
+	};
+	use tokio::{net::UnixStream, sync::broadcast::error::TryRecvError};
+	use zbus::{connection::Builder, Guid};
+
+	/// A connection to ourselves, so a [`Cache`] can be made without an accessibility bus.
+	async fn peer_connection() -> zbus::Connection {
+		let (server_stream, client_stream) = UnixStream::pair().expect("socket pair");
+		let server = Builder::unix_stream(server_stream)
+			.server(Guid::generate())
+			.expect("valid guid")
+			.p2p()
+			.build();
+		let client = Builder::unix_stream(client_stream).p2p().build();
+		let (server, _client) = tokio::try_join!(server, client).expect("peer connection");
+		server
+	}
+
+	fn object_ref(id: &str) -> ObjectRef {
+		ObjectRef {
+			name: ":1.2".try_into().expect("valid unique name"),
+			path: id.try_into().expect("valid object path"),
+		}
+	}
+
+	fn item(id: &str) -> CacheItem {
+		let key = AccessiblePrimitive::from(object_ref(id));
+		CacheItem {
+			object: key.clone(),
+			app: key.clone(),
+			parent: CacheRef::new(key),
+			index: None,
+			children_num: Some(0),
+			interfaces: InterfaceSet::empty(),
+			role: Role::Label,
+			states: StateSet::empty(),
+			text: String::new(),
+			children: Vec::new(),
+			cache: Weak::new(),
+		}
+	}
+
+	#[test]
+	fn uncached_relation_targets_are_skipped() {
+		let cached = item("/org/a11y/atspi/accessible/1");
+		let lookup: HashMap<AccessiblePrimitive, CacheItem> =
+			[(cached.object.clone(), cached)].into_iter().collect();
+		let relations = vec![
+			(
+				RelationType::LabelledBy,
+				vec![
+					object_ref("/org/a11y/atspi/accessible/1"),
+					object_ref("/org/a11y/atspi/accessible/2"),
+				],
+			),
+			(
+				RelationType::DescribedBy,
+				vec![object_ref("/org/a11y/atspi/accessible/3")],
+			),
+		];
+		let resolved = resolve_relations(relations, |key| lookup.get(key).cloned());
+		assert_eq!(resolved.len(), 1);
+		assert_eq!(resolved[0].0, RelationType::LabelledBy);
+		assert_eq!(resolved[0].1.len(), 1);
+		assert_eq!(resolved[0].1[0].object.id, "/org/a11y/atspi/accessible/1");
+	}
+
+	#[test]
+	fn embedded_roots_follow_the_children() {
+		let mut frame = item("/org/a11y/atspi/accessible/1");
+		frame.children = vec![
+			CacheRef::new(item("/org/a11y/atspi/accessible/2").object),
+			CacheRef::new(item("/org/a11y/atspi/accessible/3").object),
+		];
+		let plugin_root = ObjectRef {
+			name: ":1.9".try_into().expect("valid unique name"),
+			path: "/org/a11y/atspi/accessible/root"
+				.try_into()
+				.expect("valid object path"),
+		};
+		let relations = vec![
+			(
+				RelationType::LabelledBy,
+				vec![object_ref("/org/a11y/atspi/accessible/4")],
+			),
+			(RelationType::Embeds, vec![plugin_root]),
+		];
+		let children: Vec<(String, String)> = children_with_embeds(&frame, &relations)
+			.into_iter()
+			.map(|child| (child.sender.to_string(), child.id))
+			.collect();
+		assert_eq!(
+			children,
+			[
+				(":1.2".to_string(), "/org/a11y/atspi/accessible/2".to_string()),
+				(":1.2".to_string(), "/org/a11y/atspi/accessible/3".to_string()),
+				(":1.9".to_string(), "/org/a11y/atspi/accessible/root".to_string()),
+			]
+		);
+		assert_eq!(children_with_embeds(&frame, &[]).len(), 2);
+		let embedded_by = [(
+			RelationType::EmbeddedBy,
+			vec![object_ref("/org/a11y/atspi/accessible/1")],
+		)];
+		assert_eq!(embedder(&embedded_by), Some(frame.object));
+		assert_eq!(embedder(&relations), None);
+	}
+
+	#[test]
+	fn embedded_roots_already_among_the_children_are_not_repeated() {
+		let mut frame = item("/org/a11y/atspi/accessible/1");
+		frame.children = vec![CacheRef::new(item("/org/a11y/atspi/accessible/2").object)];
+		let relations =
+			[(RelationType::Embeds, vec![object_ref("/org/a11y/atspi/accessible/2")])];
+		assert_eq!(children_with_embeds(&frame, &relations).len(), 1);
+	}
+
+	#[tokio::test]
+	async fn slow_queries_time_out() {
+		let limit = Duration::from_millis(10);
+		let slow = with_timeout(limit, std::future::pending::<OdiliaResult<()>>()).await;
+		assert!(matches!(slow, Err(OdiliaError::Timeout(waited)) if waited == limit));
+		let quick = with_timeout(limit, async { Ok(5) }).await;
+		assert!(matches!(quick, Ok(5)));
+		let failed = with_timeout(limit, async {
+			Err::<(), _>(OdiliaError::Generic("gone".to_string()))
+		})
+		.await;
+		assert!(matches!(failed, Err(OdiliaError::Generic(_))));
+		// without a limit, the query is waited for however long it takes
+		let unlimited = with_timeout(Duration::ZERO, async {
+			tokio::time::sleep(Duration::from_millis(20)).await;
+			Ok(())
+		})
+		.await;
+		assert!(unlimited.is_ok());
+	}
+	#[test]
+	fn visibility_requires_showing_and_visible() {
+		assert!(is_visible(&StateSet::new(State::Showing | State::Visible)));
+		assert!(is_visible(&StateSet::new(
+			State::Showing | State::Visible | State::Focusable
+		)));
+		assert!(!is_visible(&StateSet::new(State::Showing)));
+		assert!(!is_visible(&StateSet::new(State::Visible)));
+		assert!(!is_visible(&StateSet::empty()));
+	}
+	#[test]
+	fn defunct_items_are_not_visible() {
+		assert!(!is_visible(&StateSet::new(
+			State::Showing | State::Visible | State::Defunct
+		)));
+	}
+	#[test]
+	fn empty_text_falls_back_to_name_outside_of_text_roles() {
+		assert!(text_falls_back_to_name(Role::PushButton, ""));
+		assert!(text_falls_back_to_name(Role::Link, ""));
+		assert!(!text_falls_back_to_name(Role::PushButton, "OK"));
+		assert!(!text_falls_back_to_name(Role::Entry, ""));
+		assert!(!text_falls_back_to_name(Role::PasswordText, ""));
+		assert!(!text_falls_back_to_name(Role::Paragraph, "Some text"));
+	}
+	#[test]
+	fn cache_item_visibility_uses_cached_states() {
+		let mut hidden_menu_item = item("/org/a11y/atspi/accessible/4");
+		hidden_menu_item.role = Role::MenuItem;
+		hidden_menu_item.states = StateSet::new(State::Visible);
+		assert!(!hidden_menu_item.is_visible());
+		hidden_menu_item.states.insert(State::Showing);
+		assert!(hidden_menu_item.is_visible());
+	}
+	#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+	async fn readers_never_see_a_half_replaced_cache() {
+		const ITEMS: usize = 500;
+		let items_with_role = |role: Role| {
+			(0..ITEMS)
+				.map(|idx| {
+					let mut item =
+						item(&format!("/org/a11y/atspi/accessible/{idx}"));
+					item.role = role;
+					item
+				})
+				.collect::<Vec<_>>()
+		};
+		let cache = Arc::new(Cache::new(peer_connection().await));
+		cache.add_all(items_with_role(Role::Label)).expect("initial items");
+		let done = Arc::new(AtomicBool::new(false));
+		let reader = {
+			let cache = Arc::clone(&cache);
+			let done = Arc::clone(&done);
+			std::thread::spawn(move || {
+				while !done.load(Ordering::Relaxed) {
+					let seen = cache
+						.find_by_role(&[Role::Label, Role::PushButton]);
+					assert_eq!(seen.len(), ITEMS);
+					assert!(seen.iter().all(|item| item.role == seen[0].role));
+				}
+			})
+		};
+		for round in 0..20 {
+			let role = if round % 2 == 0 { Role::PushButton } else { Role::Label };
+			cache.replace_all(items_with_role(role)).expect("replacement");
+		}
+		done.store(true, Ordering::Relaxed);
+		reader.join().expect("reader saw a partially replaced cache");
+		assert_eq!(cache.by_id.len(), ITEMS);
+	}
+	#[tokio::test]
+	async fn prune_by_sender_only_removes_that_application() {
+		let cache = Cache::new(peer_connection().await);
+		let mut items: Vec<CacheItem> = (0..3)
+			.map(|idx| item(&format!("/org/a11y/atspi/accessible/{idx}")))
+			.collect();
+		let mut other_app = item("/org/a11y/atspi/accessible/0");
+		other_app.object.sender = ":1.9".into();
+		items.push(other_app.clone());
+		cache.add_all(items).expect("items are added");
+
+		assert_eq!(cache.prune_by_sender(":1.2"), 3);
+		assert_eq!(cache.by_id.len(), 1);
+		assert!(cache.get(&other_app.object).is_some());
+		assert_eq!(cache.prune_by_sender(":1.2"), 0);
+	}
+	#[test]
+	fn object_paths_are_rebuilt_from_ids() {
+		for id in [
+			"/org/a11y/atspi/accessible/1234",
+			"/org/a11y/atspi/accessible/root",
+			"/org/a11y/atspi/accessible/null",
+		] {
+			let cache_item = item(id);
+			assert_eq!(cache_item.object_path().expect("valid path").as_str(), id);
+			assert_eq!(
+				cache_item.object.object_path().expect("valid path").as_str(),
+				id
+			);
+		}
+	}
+	#[test]
+	fn primitives_are_taken_from_cache_items() {
+		let mut cache_item = item("/org/a11y/atspi/accessible/2");
+		cache_item.parent = CacheRef::new(item("/org/a11y/atspi/accessible/1").object);
+		cache_item.app = item("/org/a11y/atspi/accessible/root").object;
+		assert_eq!(AccessiblePrimitive::from(&cache_item), cache_item.object);
+		assert_eq!(cache_item.parent_primitive(), cache_item.parent.key);
+		assert_eq!(cache_item.app_primitive().id, "/org/a11y/atspi/accessible/root");
+	}
+	#[tokio::test]
+	async fn items_are_compared_without_their_cache_handles() {
+		let cache = Arc::new(Cache::new(peer_connection().await));
+		let mut uncached = item("/org/a11y/atspi/accessible/1");
+		uncached.children =
+			vec![CacheRef::new(item("/org/a11y/atspi/accessible/2").object)];
+		let mut cached = uncached.clone();
+		cached.cache = Arc::downgrade(&cache);
+		assert!(uncached.same_content(&cached));
+		assert!(cached.same_content(&uncached));
+
+		let mut button = cached.clone();
+		button.role = Role::PushButton;
+		assert!(!cached.same_content(&button));
+		let mut renamed = cached.clone();
+		renamed.text = "OK".to_string();
+		assert!(!cached.same_content(&renamed));
+		let mut childless = cached.clone();
+		childless.children.clear();
+		assert!(!cached.same_content(&childless));
+	}
+	#[test]
+	fn landmarks_are_read_from_their_attributes() {
+		let attributes = |pairs: &[(&str, &str)]| -> HashMap<String, String> {
+			pairs.iter()
+				.map(|(key, value)| ((*key).to_string(), (*value).to_string()))
+				.collect()
+		};
+		// as Firefox exposes a <nav> element
+		let nav = attributes(&[
+			("xml-roles", "navigation"),
+			("tag", "nav"),
+			("display", "block"),
+		]);
+		assert_eq!(landmark_type(&nav), Some("navigation"));
+		assert!(is_landmark(Role::Landmark, &nav));
+		// a search form keeps the form role, and only its attributes say it is a landmark
+		let search = attributes(&[("xml-roles", "search"), ("tag", "form")]);
+		assert_eq!(landmark_type(&search), Some("search"));
+		assert!(is_landmark(Role::Form, &search));
+		let multiple = attributes(&[("xml-roles", "presentation Main")]);
+		assert_eq!(landmark_type(&multiple), Some("main"));
+		let gtk = attributes(&[("landmark", "banner")]);
+		assert_eq!(landmark_type(&gtk), Some("banner"));
+
+		let list = attributes(&[("xml-roles", "list"), ("tag", "ul")]);
+		assert_eq!(landmark_type(&list), None);
+		assert!(!is_landmark(Role::List, &list));
+		assert!(!is_landmark(Role::Section, &HashMap::new()));
+		// landmarks which do not say what kind they are are still landmarks
+		assert!(is_landmark(Role::Landmark, &HashMap::new()));
+	}
+	#[test]
+	fn invalid_ids_are_not_object_paths() {
+		let primitive =
+			AccessiblePrimitive { id: "not a path".to_string(), sender: ":1.2".into() };
+		assert!(primitive.object_path().is_err());
+	}
+	#[tokio::test]
+	async fn subscribers_are_told_about_changes() {
+		let cache = Cache::new(peer_connection().await);
+		let mut changes = cache.subscribe();
+		let key = item("/org/a11y/atspi/accessible/1").object;
+		cache.add(item("/org/a11y/atspi/accessible/1"))
+			.expect("item is added");
+		let modified = cache.modify_item(&key, |item| item.text = "changed".to_string());
+		assert!(modified.expect("item is modified"));
+		cache.remove(&key);
+		// nothing is announced for items which are not in the cache
+		cache.remove(&key);
+		assert!(!cache
+			.modify_item(&key, |_| {})
+			.expect("missing items are not an error"));
+
+		assert_eq!(changes.try_recv(), Ok(CacheEvent::Added(key.clone())));
+		assert_eq!(changes.try_recv(), Ok(CacheEvent::Modified(key.clone())));
+		assert_eq!(changes.try_recv(), Ok(CacheEvent::Removed(key)));
+		assert!(changes.try_recv().is_err());
+	}
+	#[tokio::test]
+	async fn slow_subscribers_miss_the_oldest_changes() {
+		let cache = Cache::new(peer_connection().await);
+		let mut changes = cache.subscribe();
+		let items: Vec<CacheItem> = (0..=CACHE_EVENT_CAPACITY)
+			.map(|n| item(&format!("/org/a11y/atspi/accessible/{n}")))
+			.collect();
+		cache.add_all(items).expect("items are added");
+		assert!(matches!(changes.try_recv(), Err(TryRecvError::Lagged(1))));
+		assert!(matches!(changes.try_recv(), Ok(CacheEvent::Added(_))));
+	}
+	#[tokio::test]
+	async fn nearest_cached_ancestor_walks_up_past_uncached_items() {
+		let cache = Cache::new(peer_connection().await);
+		let mut root = item("/org/a11y/atspi/accessible/root");
+		root.parent = CacheRef::new(root.object.clone());
+		let mut menu = item("/org/a11y/atspi/accessible/1");
+		menu.parent = CacheRef::new(root.object.clone());
+		cache.add_all(vec![root, menu]).expect("items are added");
+		// the menu item and the submenu it is in are not cached
+		let parents: HashMap<String, AccessiblePrimitive> = [
+			(
+				"/org/a11y/atspi/accessible/3",
+				item("/org/a11y/atspi/accessible/2").object,
+			),
+			(
+				"/org/a11y/atspi/accessible/2",
+				item("/org/a11y/atspi/accessible/1").object,
+			),
+		]
+		.into_iter()
+		.map(|(id, parent)| (id.to_string(), parent))
+		.collect();
+		let leaf = item("/org/a11y/atspi/accessible/3").object;
+
+		let ancestor = cache
+			.nearest_cached_ancestor_by(&leaf, |key| {
+				let parent = parents.get(&key.id).cloned();
+				async move { parent }
+			})
+			.await
+			.expect("the menu is cached");
+		assert_eq!(ancestor.object.id, "/org/a11y/atspi/accessible/1");
+		let unknown = item("/org/a11y/atspi/accessible/99").object;
+		let none = cache.nearest_cached_ancestor_by(&unknown, |_| async { None }).await;
+		assert!(none.is_none());
+	}
+	#[tokio::test]
+	async fn nearest_cached_ancestor_gives_up_on_parent_loops() {
+		let cache = Cache::new(peer_connection().await);
+		let looping = item("/org/a11y/atspi/accessible/1").object;
+		let mut asked = 0;
+		let ancestor = cache
+			.nearest_cached_ancestor_by(&looping, |key| {
+				asked += 1;
+				// 1 and 2 each claim the other as their parent
+				let parent = if key.id.ends_with("/1") { "2" } else { "1" };
+				async move {
+					Some(item(&format!("/org/a11y/atspi/accessible/{parent}"))
+						.object)
+				}
+			})
+			.await;
+		assert!(ancestor.is_none());
+		assert_eq!(asked, ANCESTOR_WALK_LIMIT);
+	}
+	#[tokio::test]
+	async fn ancestors_are_listed_from_parent_to_root() {
+		let cache = Cache::new(peer_connection().await);
+		let mut root = item("/org/a11y/atspi/accessible/root");
+		root.parent = CacheRef::new(root.object.clone());
+		let mut form = item("/org/a11y/atspi/accessible/1");
+		form.parent = CacheRef::new(root.object.clone());
+		let mut entry = item("/org/a11y/atspi/accessible/2");
+		entry.parent = CacheRef::new(form.object.clone());
+		let mut orphan = item("/org/a11y/atspi/accessible/3");
+		orphan.parent = CacheRef::new(item("/org/a11y/atspi/accessible/99").object);
+		let entry_key = entry.object.clone();
+		let orphan_key = orphan.object.clone();
+		cache.add_all(vec![root, form, entry, orphan])
+			.expect("items are added");
+
+		let ids: Vec<String> = cache
+			.ancestors(&entry_key)
+			.into_iter()
+			.map(|item| item.object.id)
+			.collect();
+		assert_eq!(
+			ids,
+			["/org/a11y/atspi/accessible/1", "/org/a11y/atspi/accessible/root"]
+		);
+		assert!(cache.ancestors(&orphan_key).is_empty());
+	}
+	#[tokio::test]
+	async fn index_of_uses_the_given_index_or_the_parents_children() {
+		let cache = Cache::new(peer_connection().await);
+		let mut parent = item("/org/a11y/atspi/accessible/1");
+		let mut indexed = item("/org/a11y/atspi/accessible/2");
+		indexed.parent = CacheRef::new(parent.object.clone());
+		indexed.index = Some(0);
+		let mut unindexed = item("/org/a11y/atspi/accessible/3");
+		unindexed.parent = CacheRef::new(parent.object.clone());
+		parent.children = vec![
+			CacheRef::new(indexed.object.clone()),
+			CacheRef::new(unindexed.object.clone()),
+		];
+		let (parent_key, indexed_key, unindexed_key) =
+			(parent.object.clone(), indexed.object.clone(), unindexed.object.clone());
+		cache.add_all(vec![parent, indexed, unindexed])
+			.expect("items are added");
+
+		assert_eq!(cache.index_of(&indexed_key), Some(0));
+		assert_eq!(cache.index_of(&unindexed_key), Some(1));
+		assert_eq!(
+			cache.parent_of(&unindexed_key).map(|parent| parent.object),
+			Some(parent_key.clone())
+		);
+		// the parent is its own parent, and so is not among its children
+		assert_eq!(cache.index_of(&parent_key), None);
+		assert_eq!(cache.index_of(&item("/org/a11y/atspi/accessible/99").object), None);
+	}
+	#[tokio::test]
+	async fn tree_is_walked_through_parents_children_and_siblings() {
+		let cache = Cache::new(peer_connection().await);
+		let mut root = item("/org/a11y/atspi/accessible/root");
+		root.parent = CacheRef::new(root.object.clone());
+		let mut list = item("/org/a11y/atspi/accessible/1");
+		list.parent = CacheRef::new(root.object.clone());
+		root.children = vec![CacheRef::new(list.object.clone())];
+		let mut entries = Vec::new();
+		for id in ["/org/a11y/atspi/accessible/2", "/org/a11y/atspi/accessible/3"] {
+			let mut entry = item(id);
+			entry.parent = CacheRef::new(list.object.clone());
+			list.children.push(CacheRef::new(entry.object.clone()));
+			entries.push(entry);
+		}
+		let (root_key, list_key) = (root.object.clone(), list.object.clone());
+		let (first, second) = (entries[0].object.clone(), entries[1].object.clone());
+		cache.add_all(vec![root, list]).expect("items are added");
+		cache.add_all(entries).expect("items are added");
+		let id = |item: Option<CacheItem>| item.map(|item| item.object);
+
+		assert_eq!(id(cache.parent_of(&first)), Some(list_key.clone()));
+		assert_eq!(id(cache.parent_of(&root_key)), None);
+		assert_eq!(id(cache.first_child_of(&list_key)), Some(first.clone()));
+		assert_eq!(id(cache.first_child_of(&first)), None);
+		assert_eq!(id(cache.next_sibling_of(&first)), Some(second.clone()));
+		assert_eq!(id(cache.next_sibling_of(&second)), None);
+		assert_eq!(id(cache.previous_sibling_of(&second)), Some(first.clone()));
+		assert_eq!(id(cache.previous_sibling_of(&first)), None);
+		assert_eq!(id(cache.next_sibling_of(&root_key)), None);
+		let order: Vec<AccessiblePrimitive> = cache
+			.descendants(&root_key)
+			.into_iter()
+			.map(|item| item.object)
+			.collect();
+		assert_eq!(
+			order,
+			[root_key.clone(), list_key.clone(), first.clone(), second.clone()]
+		);
+		assert_eq!(cache.descendants(&second).len(), 1);
+	}
+	#[tokio::test]
+	async fn child_windows_keep_gaps_for_uncached_children() {
+		let cache = Cache::new(peer_connection().await);
+		let mut list = item("/org/a11y/atspi/accessible/1");
+		let mut entries = Vec::new();
+		for idx in 2..7 {
+			let entry = item(&format!("/org/a11y/atspi/accessible/{idx}"));
+			list.children.push(CacheRef::new(entry.object.clone()));
+			// every other entry is left out of the cache
+			if idx % 2 == 0 {
+				entries.push(entry);
+			}
+		}
+		let list_key = list.object.clone();
+		cache.add_all(vec![list]).expect("items are added");
+		cache.add_all(entries).expect("items are added");
+		let ids = |window: Vec<Option<CacheItem>>| {
+			window.into_iter()
+				.map(|item| item.map(|item| item.object.id))
+				.collect::<Vec<_>>()
+		};
+
+		assert_eq!(
+			ids(cache.get_many_present(&list_key, 1, 3)),
+			[None, Some("/org/a11y/atspi/accessible/4".to_string()), None]
+		);
+		assert_eq!(
+			ids(cache.get_many_present(&list_key, 3, 10)),
+			[None, Some("/org/a11y/atspi/accessible/6".to_string())]
+		);
+		assert!(cache.get_many_present(&list_key, 5, 2).is_empty());
+		let uncached = item("/org/a11y/atspi/accessible/3").object;
+		assert!(cache.get_many_present(&uncached, 0, 2).is_empty());
+	}
+	#[test]
+	fn evictions_skip_protected_items() {
+		let ids: Vec<AccessiblePrimitive> = (0..5)
+			.map(|idx| item(&format!("/org/a11y/atspi/accessible/{idx}")).object)
+			.collect();
+		let protected: HashSet<AccessiblePrimitive> =
+			[ids[0].clone(), ids[2].clone()].into_iter().collect();
+
+		assert_eq!(
+			select_evictions(ids.clone(), &protected, 3),
+			[ids[1].clone(), ids[3].clone()]
+		);
+		assert!(select_evictions(ids.clone(), &protected, 5).is_empty());
+		// only three items are unprotected, so trimming to one leaves both protected ones
+		assert_eq!(
+			select_evictions(ids.clone(), &protected, 1),
+			[ids[1].clone(), ids[3].clone(), ids[4].clone()]
+		);
+		assert!(select_evictions(Vec::new(), &protected, 0).is_empty());
+	}
+	#[tokio::test]
+	async fn trimming_keeps_kept_items_and_their_ancestors() {
+		let cache = Cache::new(peer_connection().await);
+		let mut root = item("/org/a11y/atspi/accessible/root");
+		root.parent = CacheRef::new(root.object.clone());
+		let mut items = vec![root];
+		// a chain of items, each the parent of the next
+		for idx in 1..6 {
+			let mut child = item(&format!("/org/a11y/atspi/accessible/{idx}"));
+			child.parent = CacheRef::new(items[idx - 1].object.clone());
+			items.push(child);
+		}
+		let mut stray = item("/org/a11y/atspi/accessible/9");
+		stray.parent = CacheRef::new(items[0].object.clone());
+		items.push(stray.clone());
+		let focused = items[3].object.clone();
+		cache.add_all(items).expect("items are added");
+
+		assert_eq!(cache.trim(2, &[focused.clone()]), 3);
+		assert_eq!(cache.by_id.len(), 4);
+		for id in ["root", "1", "2", "3"] {
+			let key = item(&format!("/org/a11y/atspi/accessible/{id}")).object;
+			assert!(cache.get(&key).is_some(), "{id} should have been kept");
+		}
+		assert!(cache.get(&stray.object).is_none());
+		assert_eq!(cache.trim(10, &[]), 0);
+	}
+	#[tokio::test]
+	async fn reordered_children_are_walked_in_their_new_order() {
+		let cache = Cache::new(peer_connection().await);
+		let mut table = item("/org/a11y/atspi/accessible/1");
+		let (mut rows, mut row_items) = (Vec::new(), Vec::new());
+		for (index, id) in ["2", "3", "4"].into_iter().enumerate() {
+			let mut row = item(&format!("/org/a11y/atspi/accessible/{id}"));
+			row.parent = CacheRef::new(table.object.clone());
+			row.index = Some(index);
+			table.children.push(CacheRef::new(row.object.clone()));
+			rows.push(row.object.clone());
+			row_items.push(row);
+		}
+		let table_key = table.object.clone();
+		cache.add_all(vec![table]).expect("items are added");
+		cache.add_all(row_items).expect("items are added");
+
+		// the last row is sorted to the top
+		let reordered = vec![rows[2].clone(), rows[0].clone(), rows[1].clone()];
+		assert!(cache.reorder_children(&table_key, reordered).expect("reordering"));
+		let id = |item: Option<CacheItem>| item.map(|item| item.object);
+		assert_eq!(id(cache.first_child_of(&table_key)), Some(rows[2].clone()));
+		assert_eq!(id(cache.next_sibling_of(&rows[2])), Some(rows[0].clone()));
+		assert_eq!(id(cache.next_sibling_of(&rows[0])), Some(rows[1].clone()));
+		assert_eq!(id(cache.next_sibling_of(&rows[1])), None);
+		assert_eq!(cache.get(&rows[2]).and_then(|row| row.index), Some(0));
+		assert_eq!(cache.get(&rows[1]).and_then(|row| row.index), Some(2));
+		let uncached = item("/org/a11y/atspi/accessible/9").object;
+		assert!(!cache.reorder_children(&uncached, Vec::new()).expect("reordering"));
+	}
+	#[tokio::test]
+	async fn items_are_within_their_ancestors_only() {
+		let cache = Cache::new(peer_connection().await);
+		let mut root = item("/org/a11y/atspi/accessible/root");
+		root.parent = CacheRef::new(root.object.clone());
+		let mut dialog = item("/org/a11y/atspi/accessible/1");
+		dialog.parent = CacheRef::new(root.object.clone());
+		let mut button = item("/org/a11y/atspi/accessible/2");
+		button.parent = CacheRef::new(dialog.object.clone());
+		let mut outside = item("/org/a11y/atspi/accessible/3");
+		outside.parent = CacheRef::new(root.object.clone());
+		let (root_key, dialog_key, button_key, outside_key) = (
+			root.object.clone(),
+			dialog.object.clone(),
+			button.object.clone(),
+			outside.object.clone(),
+		);
+		cache.add_all(vec![root, dialog, button, outside])
+			.expect("items are added");
+
+		assert!(cache.is_within(&button_key, &dialog_key));
+		assert!(cache.is_within(&dialog_key, &dialog_key));
+		assert!(cache.is_within(&button_key, &root_key));
+		assert!(!cache.is_within(&outside_key, &dialog_key));
+		assert!(!cache.is_within(&root_key, &dialog_key));
+		let uncached = item("/org/a11y/atspi/accessible/99").object;
+		assert!(!cache.is_within(&uncached, &dialog_key));
+	}
+	#[tokio::test]
+	async fn uncached_cache_stores_nothing() {
+		let cache = Cache::uncached(peer_connection().await);
+		assert!(!cache.caching_enabled());
+		let first = item("/org/a11y/atspi/accessible/1");
+		let key = first.object.clone();
+		cache.add(first).expect("adding is a no-op");
+		cache.add_all(vec![item("/org/a11y/atspi/accessible/2")])
+			.expect("adding is a no-op");
+		assert!(cache.by_id.is_empty());
+		assert!(cache.get(&key).is_none());
+		assert!(!cache
+			.modify_item(&key, |item| item.index = Some(1))
+			.expect("no lock is taken"));
+		let fresh = cache
+			.add_and_get(item("/org/a11y/atspi/accessible/3"))
+			.expect("the item is handed back");
+		assert_eq!(fresh.object.id, "/org/a11y/atspi/accessible/3");
+		assert!(cache.by_id.is_empty());
+	}
+	#[tokio::test]
+	async fn siblings_are_reindexed_in_one_batch() {
+		let cache = Cache::new(peer_connection().await);
+		let siblings = [
+			"/org/a11y/atspi/accessible/3",
+			"/org/a11y/atspi/accessible/1",
+			"/org/a11y/atspi/accessible/2",
+		];
+		let keys: Vec<CacheKey> = siblings.iter().map(|id| item(id).object).collect();
+		cache.add_all(siblings.into_iter().map(item).collect())
+			.expect("items are added");
+
+		let updated = cache
+			.with_items_mut(&keys, |items| {
+				for (index, item) in items.iter_mut().enumerate() {
+					item.index = Some(index);
+				}
+			})
+			.expect("items can be locked");
+		assert!(updated);
+		let indexes: Vec<Option<usize>> = cache
+			.get_all(&keys)
+			.into_iter()
+			.map(|item| item.and_then(|item| item.index))
+			.collect();
+		assert_eq!(indexes, [Some(0), Some(1), Some(2)]);
+
+		let missing = [keys[0].clone(), item("/org/a11y/atspi/accessible/99").object];
+		let updated = cache
+			.with_items_mut(&missing, |items| items[0].index = None)
+			.expect("items can be locked");
+		assert!(!updated);
+		assert_eq!(cache.get(&keys[0]).and_then(|item| item.index), Some(0));
+	}
+}
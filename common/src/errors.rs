@@ -2,7 +2,7 @@ use atspi::AtspiError;
 use atspi_common::AtspiError as AtspiTypesError;
 use serde_plain::Error as SerdePlainError;
 use smartstring::alias::String as SmartString;
-use std::{error::Error, fmt, str::FromStr};
+use std::{error::Error, fmt, str::FromStr, time::Duration};
 
 #[derive(Debug)]
 pub enum OdiliaError {
@@ -19,6 +19,8 @@ pub enum OdiliaError {
 	ConversionError(std::num::TryFromIntError),
 	Config(ConfigError),
 	PoisoningError,
+	/// An application did not answer a query within this long.
+	Timeout(Duration),
 	Generic(String),
 }
 #[derive(Debug)]
@@ -26,6 +28,22 @@ pub enum ConfigError {
 	Figment(figment::Error),
 	ValueNotFound,
 	PathNotFound,
+	/// Every setting which was read without error, but holds a value odilia can not use.
+	Invalid(Vec<InvalidSetting>),
+}
+/// A setting with a value outside of what it accepts.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InvalidSetting {
+	/// Where the setting is in the configuration file, like `speech.rate`.
+	pub path: String,
+	pub value: String,
+	/// A description of the values which are accepted, like "between -100 and 100".
+	pub expected: String,
+}
+impl fmt::Display for InvalidSetting {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(f, "`{}` is {}; expected {}", self.path, self.value, self.expected)
+	}
 }
 impl From<figment::Error> for ConfigError {
 	fn from(t_err: figment::Error) -> Self {
@@ -40,6 +58,13 @@ impl std::fmt::Display for ConfigError {
 			Self::PathNotFound => {
 				f.write_str("The path for the config file was not found.")
 			}
+			Self::Invalid(settings) => {
+				f.write_str("The config file has invalid settings:")?;
+				for setting in settings {
+					write!(f, "\n\t{setting}")?;
+				}
+				Ok(())
+			}
 		}
 	}
 }
@@ -47,7 +72,17 @@ impl std::error::Error for ConfigError {}
 #[derive(Debug)]
 pub enum CacheError {
 	NotAvailable,
-	NoItem,
+	/// The item with this ID, from the application with this bus name, is not in the cache.
+	NoItem {
+		sender: String,
+		id: String,
+	},
+	/// The item has fewer children than the index asked for.
+	NoChild {
+		sender: String,
+		id: String,
+		index: usize,
+	},
 	NoLock,
 	TextBoundsError,
 }
@@ -55,14 +90,34 @@ impl std::fmt::Display for CacheError {
 	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
 		match self {
 			Self::NotAvailable => f.write_str("The cache has been dropped from memory. This never happens under normal circumstances, and should never happen. Please send a detailed bug report if this ever happens."),
-			Self::NoItem => f.write_str("No item in cache found."),
-      Self::NoLock => f.write_str("It was not possible to get a lock on this item from the cache."),
-      Self::TextBoundsError => f.write_str("The range asked for in a call to a get_string_*_offset function has invalid bounds."),
+			Self::NoItem { sender, id } => write!(f, "No item in cache found for {id} from {sender}."),
+			Self::NoChild { sender, id, index } => write!(f, "The item {id} from {sender} has no child at index {index}."),
+			Self::NoLock => f.write_str("It was not possible to get a lock on this item from the cache."),
+			Self::TextBoundsError => f.write_str("The range asked for in a call to a get_string_*_offset function has invalid bounds."),
 		}
 	}
 }
 impl std::error::Error for CacheError {}
-impl Error for OdiliaError {}
+impl Error for OdiliaError {
+	fn source(&self) -> Option<&(dyn Error + 'static)> {
+		match self {
+			Self::AtspiError(e) | Self::AtspiTypesError(e) => Some(e),
+			Self::PrimitiveConversionError(e) => Some(e),
+			Self::SerdeError(e) => Some(e),
+			Self::Zbus(e) => Some(e),
+			Self::ZbusFdo(e) => Some(e),
+			Self::Zvariant(e) => Some(e),
+			Self::Cache(e) => Some(e),
+			Self::ConversionError(e) => Some(e),
+			Self::Config(e) => Some(e),
+			Self::InfallibleConversion(never) => match *never {},
+			Self::NoAttributeError(_)
+			| Self::PoisoningError
+			| Self::Timeout(_)
+			| Self::Generic(_) => None,
+		}
+	}
+}
 impl<T> From<std::sync::PoisonError<T>> for OdiliaError {
 	fn from(_: std::sync::PoisonError<T>) -> Self {
 		Self::PoisoningError
@@ -110,7 +165,32 @@ impl From<AtspiError> for OdiliaError {
 }
 impl fmt::Display for OdiliaError {
 	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-		write!(f, "{self:?}")
+		match self {
+			Self::AtspiError(_) => f.write_str("AT-SPI error"),
+			Self::AtspiTypesError(_) => f.write_str("AT-SPI type error"),
+			Self::PrimitiveConversionError(_) => {
+				f.write_str("Could not convert to an accessible primitive")
+			}
+			Self::NoAttributeError(name) => {
+				write!(f, "The accessible has no `{name}` attribute.")
+			}
+			Self::SerdeError(_) => f.write_str("Could not parse a value"),
+			Self::Zbus(_) | Self::ZbusFdo(_) => f.write_str("D-Bus error"),
+			Self::Zvariant(_) => f.write_str("D-Bus value error"),
+			Self::Cache(_) => f.write_str("Cache error"),
+			Self::InfallibleConversion(never) => match *never {},
+			Self::ConversionError(_) => f.write_str("Number conversion failed"),
+			Self::Config(_) => f.write_str("Configuration error"),
+			Self::PoisoningError => f.write_str(
+				"A lock was poisoned by a thread which panicked while holding it.",
+			),
+			Self::Timeout(limit) => write!(
+				f,
+				"The application did not answer within {} ms.",
+				limit.as_millis()
+			),
+			Self::Generic(message) => f.write_str(message),
+		}
 	}
 }
 
@@ -165,3 +245,48 @@ pub enum ModeFromStrError {
 	#[error("Mode not found")]
 	ModeNameNotFound,
 }
+
+#[cfg(test)]
+mod tests {
+	use super::{CacheError, ConfigError, OdiliaError};
+	use std::error::Error;
+
+	#[test]
+	fn cache_errors_say_which_item_failed() {
+		let missing = OdiliaError::from(CacheError::NoItem {
+			sender: ":1.42".to_string(),
+			id: "/org/a11y/atspi/accessible/7".to_string(),
+		});
+		// the cause is left to the source, so it is not printed twice when the chain is shown
+		assert_eq!(missing.to_string(), "Cache error");
+		let source = missing.source().expect("cache errors have a source");
+		assert_eq!(
+			source.to_string(),
+			"No item in cache found for /org/a11y/atspi/accessible/7 from :1.42."
+		);
+		assert!(source.source().is_none());
+	}
+	#[test]
+	fn wrapped_errors_are_chained() {
+		let config = OdiliaError::Config(ConfigError::PathNotFound);
+		assert_eq!(config.to_string(), "Configuration error");
+		assert!(config.source().is_some_and(|source| source.is::<ConfigError>()));
+		let conversion = OdiliaError::from(
+			u8::try_from(300_i32).expect_err("300 does not fit in a u8"),
+		);
+		assert_eq!(conversion.to_string(), "Number conversion failed");
+		assert!(conversion
+			.source()
+			.is_some_and(|source| source.is::<std::num::TryFromIntError>()));
+	}
+	#[test]
+	fn errors_without_a_cause_have_no_source() {
+		let generic = OdiliaError::Generic("no configuration file to save to".to_string());
+		assert_eq!(generic.to_string(), "no configuration file to save to");
+		assert!(generic.source().is_none());
+		assert!(OdiliaError::PoisoningError.source().is_none());
+		let timeout = OdiliaError::Timeout(std::time::Duration::from_millis(1500));
+		assert_eq!(timeout.to_string(), "The application did not answer within 1500 ms.");
+		assert!(timeout.source().is_none());
+	}
+}
@@ -12,7 +12,7 @@ pub enum Feature {
 	Braille, // TODO
 }
 
-#[derive(Eq, PartialEq, Clone, Hash, Serialize, Deserialize)]
+#[derive(Debug, Eq, PartialEq, Clone, Copy, Hash, Serialize, Deserialize)]
 #[serde(tag = "direction")]
 pub enum Direction {
 	Forward,
@@ -35,4 +35,67 @@ pub enum ScreenReaderEvent {
 	/// Change mode of the screen reader. This is currently global, but it should be per application, and an update should only affect the current application.
 	ChangeMode(ScreenReaderMode),
 	StructuralNavigation(Direction, Role),
+	/// Turn structural navigation on or off, so that applications with single letter shortcuts of their own can be used without it getting in the way.
+	ToggleStructuralNavigation,
+	/// Read out any status bars and notifications currently known to Odilia, most recent first.
+	ReadStatusBar,
+	/// Speak only the name of the focused item.
+	ReadNameOnly,
+	/// Speak only the description of the focused item, which is often longer than its name.
+	ReadDescriptionOnly,
+	/// Remember the current reading position under the given name.
+	SetMark(String),
+	/// Move focus and the caret back to the reading position saved under the given name, and read from there.
+	JumpToMark(String),
+	/// Speak the message spoken before the one last reviewed, starting from the most recent one.
+	ReviewPreviousMessage,
+	/// Speak the message spoken after the one last reviewed.
+	ReviewNextMessage,
+	/// Move the navigation cursor to the parent of the item it is on, and read it.
+	/// The navigation cursor starts on the focused item, and moving it does not move focus.
+	MoveToParent,
+	/// Move the navigation cursor to the first child of the item it is on, and read it.
+	MoveToFirstChild,
+	/// Move the navigation cursor to the next sibling of the item it is on, and read it.
+	MoveToNextSibling,
+	/// Move the navigation cursor to the previous sibling of the item it is on, and read it.
+	MoveToPreviousSibling,
+	/// Read every cell in the row of the focused table cell, along with the column headers.
+	ReadRow,
+	/// Read every cell in the column of the focused table cell, along with the row headers.
+	ReadColumn,
+	/// Read the alt text of the focused image, and the description from its `Image` interface if it has one.
+	DescribeImage,
+	/// Read the next focused item in full detail, including the states and position which are normally left out.
+	VerboseNext,
+	/// Speak the next message as important, whatever it would normally be spoken as, so that nothing interrupts it.
+	NextUtteranceImportant,
+	/// Read where the focused item is on screen and how big it is, in the unit set in the presentation settings.
+	ReadBounds,
+	/// Perform the default action of the focused item, like clicking a button.
+	DoDefaultAction,
+	/// Read the actions the focused item offers, numbered so one can be chosen with [`ScreenReaderEvent::DoAction`].
+	ListActions,
+	/// Perform the action of the focused item at this position in the list, counting from 1.
+	DoAction(usize),
+	/// Perform the action of the focused item with this name.
+	DoNamedAction(String),
+	/// Read all of the focused item's text at once, like a whole paragraph or field, without moving the caret.
+	ReadAll,
+	/// Speak faster by the configured rate step, up to the fastest rate speech-dispatcher allows.
+	IncreaseRate,
+	/// Speak slower by the configured rate step, down to the slowest rate speech-dispatcher allows.
+	DecreaseRate,
+	/// Turn on or off reading the word around the caret after the character, when moving by character.
+	ToggleSpeakCaretContext,
+	/// Switch between reading focused items by their accessible name and by the text they show, which can differ when a label is given only to assistive technology.
+	ToggleDisplayText,
+	/// Read how far through the document the focused item is, like "42 percent through document".
+	ReadDocumentPosition,
+	/// Speak and log the sender, path, role, states and interfaces of the focused item, when debug commands are turned on.
+	ReportObjectInfo,
+	/// Read a larger (forward) or smaller (backward) unit of text when the caret moves into a different text field, like going from lines to paragraphs.
+	ChangeGranularity(Direction),
+	/// Move to the next (forward) or previous (backward) link inside the focused text, like a paragraph with links in it, and read it.
+	MoveToLink(Direction),
 }
@@ -1,13 +1,125 @@
-use serde::{Deserialize, Serialize};
+use serde::{de, Deserialize, Deserializer, Serialize};
+use std::{fmt, str::FromStr};
 
-#[derive(Clone, PartialEq, Debug, Eq, Hash, Serialize, Deserialize)]
+use crate::errors::ModeFromStrError;
+
+/// Every mode the screen reader knows about, by canonical name.
+pub const KNOWN_MODES: [&str; 3] = ["browse", "focus", "command"];
+
+/// One of [`KNOWN_MODES`]; it can only be made by checking its name, whether it is parsed, built with [`ScreenReaderMode::new`], or deserialized.
+#[derive(Clone, PartialEq, Debug, Eq, Hash, Serialize)]
 pub struct ScreenReaderMode {
-	pub name: String,
+	name: String,
 }
 
 impl ScreenReaderMode {
+	/// The mode called `name`, checked like [`FromStr`] does.
+	/// # Errors
+	/// If `name` is not one of [`KNOWN_MODES`].
+	pub fn new(name: &str) -> Result<Self, ModeFromStrError> {
+		name.parse()
+	}
+	/// The canonical name of the mode, like `browse`.
 	#[must_use]
-	pub fn new(name: &str) -> Self {
-		ScreenReaderMode { name: name.to_string() }
+	pub fn name(&self) -> &str {
+		&self.name
+	}
+}
+
+/// Odilia starts in command mode.
+impl Default for ScreenReaderMode {
+	fn default() -> Self {
+		ScreenReaderMode { name: "command".to_string() }
+	}
+}
+
+impl FromStr for ScreenReaderMode {
+	type Err = ModeFromStrError;
+
+	/// Parse a mode name, ignoring case, spaces, and a trailing "mode"; so `Browse Mode`, `BrowseMode` and `browse` are all the same mode.
+	/// Only [`KNOWN_MODES`] are accepted, so a typo can not create a mode which does nothing.
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		let squashed: String = s
+			.chars()
+			.filter(|chr| !chr.is_whitespace())
+			.flat_map(char::to_lowercase)
+			.collect();
+		let name = squashed.strip_suffix("mode").unwrap_or(&squashed);
+		KNOWN_MODES
+			.iter()
+			.find(|known| **known == name)
+			.map(|known| ScreenReaderMode { name: (*known).to_string() })
+			.ok_or(ModeFromStrError::ModeNameNotFound)
+	}
+}
+
+impl TryFrom<String> for ScreenReaderMode {
+	type Error = ModeFromStrError;
+
+	fn try_from(name: String) -> Result<Self, Self::Error> {
+		name.parse()
+	}
+}
+
+/// Read in the same shape it is written, `{"name": "browse"}`, but with the name checked.
+impl<'de> Deserialize<'de> for ScreenReaderMode {
+	fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+		#[derive(Deserialize)]
+		struct Unchecked {
+			name: String,
+		}
+		let Unchecked { name } = Unchecked::deserialize(deserializer)?;
+		name.parse().map_err(de::Error::custom)
+	}
+}
+
+impl fmt::Display for ScreenReaderMode {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.write_str(&self.name)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::ScreenReaderMode;
+
+	fn parsed_name(name: &str) -> Option<String> {
+		name.parse::<ScreenReaderMode>()
+			.ok()
+			.map(|mode| mode.name().to_string())
+	}
+
+	#[test]
+	fn mode_names_are_canonicalized() {
+		for name in ["browse", "Browse", "Browse Mode", "BrowseMode", " browse mode "] {
+			assert_eq!(parsed_name(name).as_deref(), Some("browse"));
+		}
+		assert_eq!(parsed_name("FOCUS").as_deref(), Some("focus"));
+		assert_eq!(parsed_name("CommandMode").as_deref(), Some("command"));
+	}
+	#[test]
+	fn unknown_modes_are_rejected() {
+		assert!("brwose".parse::<ScreenReaderMode>().is_err());
+		assert!("".parse::<ScreenReaderMode>().is_err());
+		assert!("mode".parse::<ScreenReaderMode>().is_err());
+		assert!(ScreenReaderMode::new("brwose").is_err());
+	}
+	#[test]
+	fn display_round_trips() {
+		let mode: ScreenReaderMode = "Focus Mode".parse().expect("known mode");
+		assert_eq!(mode.to_string().parse::<ScreenReaderMode>().ok(), Some(mode));
+		assert_eq!(ScreenReaderMode::default().name(), "command");
+	}
+	#[test]
+	fn deserializing_checks_the_name() {
+		let mode: ScreenReaderMode =
+			serde_json::from_str(r#"{"name":"Browse Mode"}"#).expect("known mode");
+		assert_eq!(mode.name(), "browse");
+		// the shape sent over the input socket is unchanged
+		assert_eq!(
+			serde_json::to_string(&mode).expect("serializable"),
+			r#"{"name":"browse"}"#
+		);
+		assert!(serde_json::from_str::<ScreenReaderMode>(r#"{"name":"brwose"}"#).is_err());
 	}
 }
@@ -0,0 +1,110 @@
+use serde::{Deserialize, Serialize};
+///structure for the options controlling what odilia announces on its own, without being asked to
+#[derive(Debug, Default, Serialize, Deserialize)]
+// each toggle is its own key in the configuration file, which users set one at a time
+#[allow(clippy::module_name_repetitions, clippy::struct_excessive_bools)]
+pub struct AnnouncementSettings {
+	///speak status bars and notifications as soon as they appear, interrupting other speech like an assertive live region would
+	/// off by default
+	pub live_notifications: bool,
+	///what to speak back while typing into a text field
+	/// this only applies to text typed by the user, not text inserted by the application itself
+	pub echo: TypingEcho,
+	///how the indentation of a line is conveyed when moving onto it, useful when reading code
+	pub indentation: IndentationAnnouncement,
+	///only convey indentation when it differs from the last line read in the same text, instead of on every line
+	pub indentation_changes_only: bool,
+	///say "entering form" and "leaving form" when focus moves across the edge of a form
+	pub form_boundaries: bool,
+	///say "visited" after the role of a link which has already been followed, as browsers show them in a different color
+	pub visited_links: bool,
+	///read whatever is under the mouse pointer as it moves, for users who can see some of the screen
+	/// off by default
+	pub mouse_review: bool,
+	///read the formatting at the caret, like "bold, italic", when focus enters a rich text field, and whenever it changes as the caret moves
+	pub text_formatting: bool,
+	///say "misspelled word" or "grammar error" when the caret moves onto text which the editor has marked as a mistake
+	pub spelling_errors: bool,
+	///how tooltips are read when they pop up
+	pub tooltips: TooltipAnnouncement,
+	///say the name of an application before the focused item when focus moves into it from another one, like "Firefox. Home, link"
+	pub application_changes: bool,
+	///say how big a table is when focus moves into it, like "table, 5 columns, 20 rows"
+	pub table_dimensions: bool,
+	///how inserted and deleted text is worded when it is read
+	pub text_changes: TextChangeTemplates,
+}
+impl AnnouncementSettings {
+	///whether the text attributes at the caret are needed at all, for either formatting or spelling errors
+	#[must_use]
+	pub fn reads_text_attributes(&self) -> bool {
+		self.text_formatting || self.spelling_errors
+	}
+}
+
+///how inserted and deleted text is worded, so the two can be told apart and large changes do not read on and on
+/// in every template, "{text}" is replaced with the text which changed and "{count}" with how many characters it has
+#[derive(Debug, Serialize, Deserialize)]
+#[allow(clippy::module_name_repetitions)]
+pub struct TextChangeTemplates {
+	///what to say for inserted text, like new text in a live region
+	pub inserted: String,
+	///what to say for text the user deletes, unless it is replaced straight away, like by an autocorrection
+	pub deleted: String,
+	///what to say instead of the inserted text when it is longer than `summarize_over`
+	pub inserted_summary: String,
+	///what to say instead of the deleted text when it is longer than `summarize_over`
+	pub deleted_summary: String,
+	///changes with more characters than this are summarized instead of read in full
+	/// 0 never summarizes
+	pub summarize_over: usize,
+}
+impl Default for TextChangeTemplates {
+	fn default() -> Self {
+		Self {
+			inserted: "{text}".to_string(),
+			deleted: "deleted {text}".to_string(),
+			inserted_summary: "inserted {count} characters".to_string(),
+			deleted_summary: "deleted {count} characters".to_string(),
+			summarize_over: 0,
+		}
+	}
+}
+
+///how much of the typed text is spoken back to the user
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TypingEcho {
+	///do not echo anything
+	#[default]
+	None,
+	///speak every character as it is typed
+	Char,
+	///speak every word once it is completed by a space or punctuation
+	Word,
+	///speak every character, and speak the word instead when it is completed
+	Both,
+}
+
+///how tooltips are read when they appear, since they often pop up while the user is reading something else
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TooltipAnnouncement {
+	///read tooltips only once nothing else is being said, without interrupting
+	#[default]
+	Polite,
+	///read tooltips straight away, interrupting other speech
+	Interrupt,
+	///never read tooltips
+	Off,
+}
+
+///how the indentation of a line is conveyed to the user
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum IndentationAnnouncement {
+	///indentation is not announced
+	#[default]
+	Off,
+	///speak the number of columns of indentation before the line, like "indent 4"
+	Spoken,
+	///play a tone before the line, which gets higher the deeper the line is indented
+	Tone,
+}
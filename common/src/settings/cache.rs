@@ -0,0 +1,39 @@
+use serde::{Deserialize, Serialize};
+///structure for the options controlling odilia's cache of the accessibility tree
+#[derive(Debug, Serialize, Deserialize)]
+#[allow(clippy::module_name_repetitions)]
+pub struct CacheSettings {
+	///keep accessibles in a cache instead of asking the application about them every time
+	/// turning this off is only meant for finding out whether a bug is in the cache or in the application; every event then queries the application over dbus, which makes odilia much slower
+	pub enabled: bool,
+	///which applications to load into the cache as soon as odilia starts, instead of as they are used
+	/// a warm cache makes the first moments in an application faster, but loading every application on the desktop can take a while
+	pub warm_cache_on_start: CacheWarming,
+	///how many milliseconds to gather up added and removed children for, before fetching and caching them all in one pass
+	/// pages which build large documents add thousands of children at once; 0 handles each child as soon as it changes
+	pub children_changed_window_ms: u64,
+	///how many milliseconds to wait for an application to answer a question about one of its items, like its name, before giving up on it
+	/// an application which has stopped responding would otherwise hold up everything odilia reads; 0 waits forever
+	pub query_timeout_ms: u64,
+}
+impl Default for CacheSettings {
+	fn default() -> Self {
+		Self {
+			enabled: true,
+			warm_cache_on_start: CacheWarming::Off,
+			children_changed_window_ms: 50,
+			query_timeout_ms: 2000,
+		}
+	}
+}
+
+///which applications are loaded into the cache when odilia starts
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CacheWarming {
+	///nothing is loaded up front; applications are cached as they are used
+	Off,
+	///only the application with the active window
+	FocusedApp,
+	///every application on the desktop
+	AllApps,
+}
@@ -13,6 +13,12 @@ pub struct LogSettings {
 	///the place where odilia should output its logs
 	/// the values possible include tty, file and syslog
 	pub logger: LoggingKind,
+	///how each log line is written, when logging to a file or the terminal
+	/// json is easier to share and search through when reporting a bug
+	pub format: LogFormat,
+	///allow commands meant for developers, like reporting everything known about the focused item
+	/// off by default
+	pub debug_commands: bool,
 }
 impl Default for LogSettings {
 	fn default() -> Self {
@@ -23,7 +29,12 @@ impl Default for LogSettings {
 			.place_state_file("odilia.log")
 			.expect("unable to place log file");
 
-		Self { level: "info".to_owned(), logger: LoggingKind::File(log_path) }
+		Self {
+			level: "info".to_owned(),
+			logger: LoggingKind::File(log_path),
+			format: LogFormat::default(),
+			debug_commands: false,
+		}
 	}
 }
 
@@ -40,3 +51,13 @@ pub enum LoggingKind {
 	/// if that's not the case, this option does nothing
 	Syslog,
 }
+
+///how each log line is written
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum LogFormat {
+	///an indented tree of spans and events, meant to be read by people
+	#[default]
+	Pretty,
+	///one JSON object per line, holding the fields of the event and of every span it happened in, along with how long each span took
+	Json,
+}
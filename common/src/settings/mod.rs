@@ -1,9 +1,22 @@
+pub mod announcements;
+pub mod cache;
 pub mod log;
+pub mod navigation;
+pub mod presentation;
+pub mod priority;
 pub mod speech;
+pub mod watchdog;
 
+use announcements::AnnouncementSettings;
+use cache::CacheSettings;
 use log::LogSettings;
+use navigation::NavigationSettings;
+use presentation::PresentationSettings;
+use priority::PrioritySettings;
 use speech::SpeechSettings;
+use watchdog::WatchdogSettings;
 
+use crate::errors::{ConfigError, InvalidSetting};
 use serde::{Deserialize, Serialize};
 
 ///type representing a *read-only* view of the odilia screenreader configuration
@@ -13,4 +26,136 @@ use serde::{Deserialize, Serialize};
 pub struct ApplicationConfig {
 	pub speech: SpeechSettings,
 	pub log: LogSettings,
+	pub announcements: AnnouncementSettings,
+	pub navigation: NavigationSettings,
+	pub presentation: PresentationSettings,
+	pub priorities: PrioritySettings,
+	pub cache: CacheSettings,
+	pub watchdog: WatchdogSettings,
+}
+impl ApplicationConfig {
+	///check the values which parse fine but can not be used, like a rate outside of what speech-dispatcher accepts
+	/// every invalid setting is reported at once, so they can all be fixed in one go
+	/// # Errors
+	/// [`ConfigError::Invalid`] listing each invalid setting, with its path and the values it accepts
+	pub fn validate(&self) -> Result<(), ConfigError> {
+		let mut invalid = Vec::new();
+		let speech = &self.speech;
+		check_speech_range(&mut invalid, "speech.rate", speech.rate);
+		check_speech_range(&mut invalid, "speech.pitch", speech.pitch);
+		check_speech_range(&mut invalid, "speech.volume", speech.volume);
+		if speech.language.trim().is_empty() {
+			invalid.push(InvalidSetting {
+				path: "speech.language".to_string(),
+				value: "empty".to_string(),
+				expected: "a language code like \"en-US\"".to_string(),
+			});
+		}
+		for (priority, voice) in &self.priorities.voices {
+			if let Some(rate) = voice.rate {
+				let path = format!("priorities.voices.{priority:?}.rate");
+				check_speech_range(&mut invalid, &path, rate);
+			}
+		}
+		let presentation = &self.presentation;
+		let role_names = presentation.role_names.iter().map(|names| ("role_names", names));
+		let braille_role_names = presentation
+			.braille_role_names
+			.iter()
+			.map(|names| ("braille_role_names", names));
+		for (table, (role, name)) in role_names.chain(braille_role_names) {
+			if name.trim().is_empty() {
+				invalid.push(InvalidSetting {
+					path: format!("presentation.{table}.{role:?}"),
+					value: "empty".to_string(),
+					expected: "a name for the role".to_string(),
+				});
+			}
+		}
+		if invalid.is_empty() {
+			Ok(())
+		} else {
+			invalid.sort_by(|a, b| a.path.cmp(&b.path));
+			Err(ConfigError::Invalid(invalid))
+		}
+	}
+}
+
+///the range speech-dispatcher accepts for rate, pitch and volume
+pub const SPEECH_RANGE: std::ops::RangeInclusive<i8> = -100..=100;
+
+fn check_speech_range(invalid: &mut Vec<InvalidSetting>, path: &str, value: i8) {
+	if !SPEECH_RANGE.contains(&value) {
+		invalid.push(InvalidSetting {
+			path: path.to_string(),
+			value: value.to_string(),
+			expected: format!(
+				"a value between {} and {}",
+				SPEECH_RANGE.start(),
+				SPEECH_RANGE.end()
+			),
+		});
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{
+		priority::{SpeechPriority, VoiceOverride},
+		ApplicationConfig,
+	};
+	use crate::errors::ConfigError;
+	use atspi_common::Role;
+
+	fn invalid_paths(config: &ApplicationConfig) -> Vec<String> {
+		match config.validate() {
+			Ok(()) => Vec::new(),
+			Err(ConfigError::Invalid(settings)) => {
+				settings.into_iter().map(|setting| setting.path).collect()
+			}
+			Err(other) => panic!("unexpected error: {other}"),
+		}
+	}
+
+	#[test]
+	fn default_config_is_valid() {
+		assert!(ApplicationConfig::default().validate().is_ok());
+	}
+	#[test]
+	fn out_of_range_speech_settings_are_reported() {
+		let mut config = ApplicationConfig::default();
+		config.speech.rate = 120;
+		config.speech.volume = -128;
+		assert_eq!(invalid_paths(&config), ["speech.rate", "speech.volume"]);
+	}
+	#[test]
+	fn every_invalid_setting_is_reported_together() {
+		let mut config = ApplicationConfig::default();
+		config.speech.pitch = 101;
+		config.speech.language = " ".to_string();
+		config.priorities.voices.insert(
+			SpeechPriority::Important,
+			VoiceOverride { rate: Some(127), person: None },
+		);
+		config.presentation.role_names.insert(Role::PushButton, String::new());
+		assert_eq!(
+			invalid_paths(&config),
+			[
+				"presentation.role_names.PushButton",
+				"priorities.voices.Important.rate",
+				"speech.language",
+				"speech.pitch",
+			]
+		);
+	}
+	#[test]
+	fn invalid_settings_are_explained() {
+		let mut config = ApplicationConfig::default();
+		config.speech.rate = 120;
+		let message = config.validate().expect_err("the rate is too high").to_string();
+		assert_eq!(
+			message,
+			"The config file has invalid settings:\n\t`speech.rate` is 120; expected a value between -100 and 100"
+		);
+	}
 }
@@ -0,0 +1,142 @@
+use atspi_common::Granularity;
+use serde::{Deserialize, Serialize};
+///structure for the options controlling how odilia moves between elements
+#[derive(Debug, Serialize, Deserialize)]
+// each toggle is its own key in the configuration file, which users set one at a time
+#[allow(clippy::module_name_repetitions, clippy::struct_excessive_bools)]
+pub struct NavigationSettings {
+	///skip items which are not on screen, like the entries of a closed menu, when using structural navigation
+	pub skip_hidden: bool,
+	///read a list item as soon as it is selected, when focus stays on the list itself
+	/// some applications only move the selection when arrowing through a list, which would otherwise be silent
+	pub selection_follows_focus: bool,
+	///while a modal dialog is open, keep structural navigation inside of it
+	/// the rest of the application can not be used until the dialog closes anyway
+	pub contain_to_modal: bool,
+	///when the caret moves by line onto a line which is empty or only whitespace, move it on to the next line with text
+	pub skip_blank_lines: bool,
+	///read the children of each item in the order they appear on screen, top to bottom and left to right, instead of the order the application lists them in
+	/// for applications whose accessibility tree does not follow their visual layout; this asks the application for the bounds of every item, so it is slower
+	pub spatial_reading_order: bool,
+	///how much of the text is read when the caret moves into a different text field
+	/// one of char, word, sentence, line or paragraph
+	pub default_granularity: CaretGranularity,
+	///write the granularity back to the configuration file whenever it is changed with a command, so it is kept after a restart
+	/// off by default, in which case the configured granularity is used again on the next start
+	pub remember_granularity: bool,
+	///how long, in milliseconds, to wait before reading a newly focused item
+	/// if focus moves again in that time, only the newest item is read; this stops applications which move focus and then correct it from being read twice
+	pub focus_settle_ms: u64,
+}
+impl Default for NavigationSettings {
+	fn default() -> Self {
+		Self {
+			skip_hidden: true,
+			selection_follows_focus: true,
+			contain_to_modal: true,
+			skip_blank_lines: false,
+			spatial_reading_order: false,
+			default_granularity: CaretGranularity::Line,
+			remember_granularity: false,
+			focus_settle_ms: 50,
+		}
+	}
+}
+
+///a unit of text to read at the caret, written as a word in the configuration file
+/// this stands in for the at-spi [`Granularity`], which is (de)serialized as a number
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CaretGranularity {
+	Char,
+	Word,
+	Sentence,
+	#[default]
+	Line,
+	Paragraph,
+}
+impl CaretGranularity {
+	///every granularity, from the smallest unit of text to the largest
+	pub const ALL: [Self; 5] =
+		[Self::Char, Self::Word, Self::Sentence, Self::Line, Self::Paragraph];
+	///the word used for this granularity, both in the configuration file and when it is spoken
+	#[must_use]
+	pub fn as_str(self) -> &'static str {
+		match self {
+			Self::Char => "char",
+			Self::Word => "word",
+			Self::Sentence => "sentence",
+			Self::Line => "line",
+			Self::Paragraph => "paragraph",
+		}
+	}
+	///the granularity written as `name`, ignoring case, or `None` if there is no such granularity
+	#[must_use]
+	pub fn from_name(name: &str) -> Option<Self> {
+		Self::ALL
+			.into_iter()
+			.find(|granularity| granularity.as_str().eq_ignore_ascii_case(name.trim()))
+	}
+}
+impl From<CaretGranularity> for Granularity {
+	fn from(granularity: CaretGranularity) -> Self {
+		match granularity {
+			CaretGranularity::Char => Self::Char,
+			CaretGranularity::Word => Self::Word,
+			CaretGranularity::Sentence => Self::Sentence,
+			CaretGranularity::Line => Self::Line,
+			CaretGranularity::Paragraph => Self::Paragraph,
+		}
+	}
+}
+impl From<Granularity> for CaretGranularity {
+	fn from(granularity: Granularity) -> Self {
+		match granularity {
+			Granularity::Char => Self::Char,
+			Granularity::Word => Self::Word,
+			Granularity::Sentence => Self::Sentence,
+			Granularity::Line => Self::Line,
+			Granularity::Paragraph => Self::Paragraph,
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{CaretGranularity, NavigationSettings};
+	use atspi_common::Granularity;
+	use figment::{providers::Serialized, Figment};
+
+	#[test]
+	fn granularities_map_to_and_from_names_and_atspi() {
+		for granularity in CaretGranularity::ALL {
+			assert_eq!(
+				CaretGranularity::from_name(granularity.as_str()),
+				Some(granularity)
+			);
+			let atspi: Granularity = granularity.into();
+			assert_eq!(CaretGranularity::from(atspi), granularity);
+			assert_eq!(
+				serde_plain::to_string(&granularity).ok().as_deref(),
+				Some(granularity.as_str())
+			);
+		}
+		assert_eq!(
+			CaretGranularity::from_name(" Paragraph "),
+			Some(CaretGranularity::Paragraph)
+		);
+		assert_eq!(CaretGranularity::from_name("page"), None);
+		assert_eq!(Granularity::from(CaretGranularity::default()), Granularity::Line);
+	}
+	#[test]
+	fn granularity_survives_the_config_loader() {
+		let settings = NavigationSettings {
+			default_granularity: CaretGranularity::Word,
+			..NavigationSettings::default()
+		};
+		let settings: NavigationSettings = Figment::from(Serialized::defaults(settings))
+			.extract()
+			.expect("navigation settings can be loaded through figment");
+		assert_eq!(settings.default_granularity, CaretGranularity::Word);
+	}
+}
@@ -0,0 +1,203 @@
+use atspi_common::{Role, State, StateSet};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+///structure for the options controlling how information about an accessible is worded when it is spoken
+#[derive(Debug, Serialize, Deserialize)]
+#[allow(clippy::module_name_repetitions)]
+pub struct PresentationSettings {
+	///what to call each role when speaking it, for example `PushButton = "button"`
+	/// roles which are not listed here use the name given to them by at-spi
+	pub role_names: HashMap<Role, String>,
+	///shorter names for roles, used only when rendering to a braille display, where every cell counts
+	/// for example `PushButton = "btn"`; roles which are not listed here use their spoken name
+	pub braille_role_names: HashMap<Role, String>,
+	///how the position and size of an item are given when reading its bounds
+	pub coordinates: CoordinateUnit,
+	///what the position through a document is measured in when reading how far through it the focus is
+	pub document_position: DocumentPositionUnit,
+	///what to say after the role of a focused control which can not be used right now, like "dimmed" or "unavailable"
+	/// leave this empty to not mention it
+	pub disabled: String,
+	///whether, and how, the position of a focused table cell is read
+	pub cell_position: CellPosition,
+	///states which are never spoken, neither when an item is read nor when they change, like `["read-only", "has-popup"]`
+	/// states are written the way at-spi names them; this does not change how items behave, only what is said about them
+	pub silent_states: Vec<State>,
+}
+
+///the unit to read on-screen positions and sizes in
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CoordinateUnit {
+	///exact pixels, like "at 120, 40, size 300 by 20"
+	#[default]
+	Pixels,
+	///a percentage of the width or height of the screen, which is easier to picture
+	PercentOfScreen,
+}
+///how the position through a document is measured
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DocumentPositionUnit {
+	///count every item in the document the same, whatever its length
+	#[default]
+	Nodes,
+	///count the characters of text in each item, so long paragraphs weigh more than short ones
+	Characters,
+}
+///how the position of a table cell is read when it is focused
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CellPosition {
+	///the position is not read
+	#[default]
+	Off,
+	///by row and column number, like "row 7, column 2"
+	RowAndColumn,
+	///as a spreadsheet cell reference, with the column as letters, like "B7"
+	Reference,
+}
+impl Default for PresentationSettings {
+	fn default() -> Self {
+		let role_names = [
+			(Role::PushButton, "button"),
+			(Role::PageTab, "tab"),
+			(Role::PageTabList, "tab list"),
+			(Role::PasswordText, "password"),
+		]
+		.into_iter()
+		.map(|(role, name)| (role, name.to_string()))
+		.collect();
+		let braille_role_names = [
+			(Role::PushButton, "btn"),
+			(Role::ToggleButton, "tgl btn"),
+			(Role::CheckBox, "chk"),
+			(Role::RadioButton, "rad"),
+			(Role::ComboBox, "cbo"),
+			(Role::Entry, "edt"),
+			(Role::PasswordText, "pwd"),
+			(Role::Heading, "hdg"),
+			(Role::Link, "lnk"),
+			(Role::List, "lst"),
+			(Role::ListItem, "li"),
+			(Role::Menu, "mnu"),
+			(Role::MenuItem, "mnu itm"),
+			(Role::PageTab, "tab"),
+			(Role::Slider, "sldr"),
+			(Role::SpinButton, "spn"),
+			(Role::Table, "tbl"),
+			(Role::Dialog, "dlg"),
+		]
+		.into_iter()
+		.map(|(role, name)| (role, name.to_string()))
+		.collect();
+		Self {
+			role_names,
+			braille_role_names,
+			coordinates: CoordinateUnit::Pixels,
+			document_position: DocumentPositionUnit::Nodes,
+			disabled: "dimmed".to_string(),
+			cell_position: CellPosition::Off,
+			silent_states: Vec::new(),
+		}
+	}
+}
+impl PresentationSettings {
+	///the name to speak for the given role, taking the user's overrides into account
+	#[must_use]
+	pub fn role_name(&self, role: Role) -> &str {
+		self.role_names.get(&role).map_or_else(|| role.name(), String::as_str)
+	}
+	///the name to show for the given role on a braille display
+	/// falls back to the spoken name, so overriding a role's spoken name also changes it in braille unless it has its own abbreviation
+	#[must_use]
+	pub fn braille_role_name(&self, role: Role) -> &str {
+		self.braille_role_names
+			.get(&role)
+			.map_or_else(|| self.role_name(role), String::as_str)
+	}
+	///whether the user wants to hear about the given state at all
+	#[must_use]
+	pub fn is_spoken(&self, state: State) -> bool {
+		!self.silent_states.contains(&state)
+	}
+	///the states of an item which may be spoken: all of them, except the silent ones
+	#[must_use]
+	pub fn spoken_states(&self, states: StateSet) -> StateSet {
+		let mut spoken = states;
+		for state in &self.silent_states {
+			spoken.remove(*state);
+		}
+		spoken
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::PresentationSettings;
+	use atspi_common::{Role, State, StateSet};
+	use figment::{providers::Serialized, Figment};
+
+	#[test]
+	fn overridden_roles_use_the_configured_name() {
+		let settings = PresentationSettings::default();
+		assert_eq!(settings.role_name(Role::PushButton), "button");
+		assert_eq!(settings.role_name(Role::PageTab), "tab");
+	}
+	#[test]
+	fn other_roles_fall_through_to_the_atspi_name() {
+		let settings = PresentationSettings::default();
+		assert_eq!(settings.role_name(Role::CheckBox), "check box");
+		let settings = PresentationSettings {
+			role_names: [].into(),
+			..PresentationSettings::default()
+		};
+		assert_eq!(settings.role_name(Role::PushButton), "push button");
+	}
+	#[test]
+	fn braille_uses_abbreviations_and_speech_keeps_full_words() {
+		let settings = PresentationSettings::default();
+		assert_eq!(settings.braille_role_name(Role::PushButton), "btn");
+		assert_eq!(settings.braille_role_name(Role::Heading), "hdg");
+		assert_eq!(settings.role_name(Role::Heading), "heading");
+	}
+	#[test]
+	fn braille_falls_back_to_the_spoken_name() {
+		let settings = PresentationSettings {
+			braille_role_names: [].into(),
+			..PresentationSettings::default()
+		};
+		assert_eq!(settings.braille_role_name(Role::PushButton), "button");
+		assert_eq!(settings.braille_role_name(Role::Paragraph), "paragraph");
+	}
+	#[test]
+	fn role_names_survive_the_config_loader() {
+		let settings: PresentationSettings =
+			Figment::from(Serialized::defaults(PresentationSettings::default()))
+				.extract()
+				.expect("role names can be loaded through figment");
+		assert_eq!(settings.role_name(Role::PushButton), "button");
+	}
+	#[test]
+	fn silent_states_are_left_out() {
+		let states = StateSet::new(State::ReadOnly | State::Required | State::Selectable);
+		assert_eq!(PresentationSettings::default().spoken_states(states), states);
+		let settings = PresentationSettings {
+			silent_states: vec![State::Selectable, State::ReadOnly, State::Busy],
+			..PresentationSettings::default()
+		};
+		assert_eq!(settings.spoken_states(states), StateSet::new(State::Required));
+		assert!(!settings.is_spoken(State::Busy));
+		assert!(settings.is_spoken(State::Expanded));
+	}
+	#[test]
+	fn silent_states_are_written_as_atspi_names() {
+		let settings: PresentationSettings =
+			Figment::from(Serialized::defaults(PresentationSettings::default()))
+				.merge(Serialized::default(
+					"silent_states",
+					["read-only", "has-popup"],
+				))
+				.extract()
+				.expect("states can be given by name");
+		assert_eq!(settings.silent_states, vec![State::ReadOnly, State::HasPopup]);
+	}
+}
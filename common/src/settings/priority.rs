@@ -0,0 +1,154 @@
+use crate::types::AriaLive;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+///structure for the options controlling how urgently each kind of event is spoken
+/// a higher priority interrupts speech of a lower one, so these decide what cuts off what
+#[derive(Debug, Serialize, Deserialize)]
+#[allow(clippy::module_name_repetitions)]
+pub struct PrioritySettings {
+	///the priority of the announcement made when focus moves to a new item
+	pub focus: SpeechPriority,
+	///the priority of the text read when the caret moves
+	pub caret: SpeechPriority,
+	///the priority of text inserted into a live region
+	pub text_inserted: InsertionPriority,
+	///a different rate or voice for messages of a priority, so urgent ones stand out
+	/// for example `voices.Important = { rate = 70 }`; anything not given keeps the normal speech settings
+	pub voices: HashMap<SpeechPriority, VoiceOverride>,
+}
+impl Default for PrioritySettings {
+	fn default() -> Self {
+		Self {
+			focus: SpeechPriority::Text,
+			caret: SpeechPriority::Text,
+			text_inserted: InsertionPriority::Live,
+			voices: HashMap::new(),
+		}
+	}
+}
+impl PrioritySettings {
+	///the priority to speak an event of the given category with
+	/// `live` is the aria-live state of the object the event came from, if it has one
+	#[must_use]
+	pub fn resolve(&self, category: EventCategory, live: Option<&AriaLive>) -> SpeechPriority {
+		match category {
+			EventCategory::Focus => self.focus,
+			EventCategory::Caret => self.caret,
+			EventCategory::TextInserted => match self.text_inserted {
+				InsertionPriority::Live => live
+					.map_or(SpeechPriority::Message, SpeechPriority::from_live),
+				InsertionPriority::Fixed(priority) => priority,
+			},
+		}
+	}
+}
+
+///the kinds of event which have a configurable priority
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EventCategory {
+	Focus,
+	Caret,
+	TextInserted,
+}
+
+///the speech-dispatcher message priorities, from most to least urgent
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum SpeechPriority {
+	///interrupts everything, and is never interrupted itself
+	Important,
+	Message,
+	///interrupts other text, and is interrupted by anything more urgent
+	Text,
+	Notification,
+	///progress updates, dropped when anything else is speaking
+	Progress,
+}
+impl SpeechPriority {
+	///the priority matching an aria-live state
+	/// assertive regions interrupt other speech, polite ones wait for it to finish
+	#[must_use]
+	pub fn from_live(live: &AriaLive) -> Self {
+		match live {
+			AriaLive::Assertive => SpeechPriority::Important,
+			AriaLive::Polite => SpeechPriority::Notification,
+			_ => SpeechPriority::Message,
+		}
+	}
+}
+
+///how the priority of inserted text is chosen
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum InsertionPriority {
+	///follow the aria-live state of the region the text was inserted into
+	Live,
+	///always use the given priority, for example `text_inserted = { Fixed = "Text" }`
+	Fixed(SpeechPriority),
+}
+
+///speech settings which replace the normal ones while a message of one priority is spoken
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VoiceOverride {
+	///the rate to speak at, from -100 to 100
+	pub rate: Option<i8>,
+	///the voice to use within the output module
+	pub person: Option<String>,
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{
+		EventCategory, InsertionPriority, PrioritySettings, SpeechPriority, VoiceOverride,
+	};
+	use crate::types::AriaLive;
+	use figment::{providers::Serialized, Figment};
+
+	#[test]
+	fn defaults_keep_focus_and_caret_at_text() {
+		let settings = PrioritySettings::default();
+		assert_eq!(settings.resolve(EventCategory::Focus, None), SpeechPriority::Text);
+		assert_eq!(settings.resolve(EventCategory::Caret, None), SpeechPriority::Text);
+	}
+	#[test]
+	fn inserted_text_follows_the_live_state_by_default() {
+		let settings = PrioritySettings::default();
+		let resolve = |live| settings.resolve(EventCategory::TextInserted, live);
+		assert_eq!(resolve(Some(&AriaLive::Assertive)), SpeechPriority::Important);
+		assert_eq!(resolve(Some(&AriaLive::Polite)), SpeechPriority::Notification);
+		assert_eq!(resolve(Some(&AriaLive::Off)), SpeechPriority::Message);
+		assert_eq!(resolve(None), SpeechPriority::Message);
+	}
+	#[test]
+	fn configured_priorities_override_the_defaults() {
+		let settings = PrioritySettings {
+			focus: SpeechPriority::Important,
+			caret: SpeechPriority::Notification,
+			text_inserted: InsertionPriority::Fixed(SpeechPriority::Progress),
+			..PrioritySettings::default()
+		};
+		assert_eq!(settings.resolve(EventCategory::Focus, None), SpeechPriority::Important);
+		assert_eq!(
+			settings.resolve(EventCategory::Caret, None),
+			SpeechPriority::Notification
+		);
+		assert_eq!(
+			settings.resolve(EventCategory::TextInserted, Some(&AriaLive::Assertive)),
+			SpeechPriority::Progress
+		);
+	}
+	#[test]
+	fn priorities_survive_the_config_loader() {
+		let fast = VoiceOverride { rate: Some(70), person: None };
+		let settings = PrioritySettings {
+			text_inserted: InsertionPriority::Fixed(SpeechPriority::Text),
+			voices: [(SpeechPriority::Important, fast.clone())].into_iter().collect(),
+			..PrioritySettings::default()
+		};
+		let settings: PrioritySettings = Figment::from(Serialized::defaults(settings))
+			.extract()
+			.expect("priorities can be loaded through figment");
+		assert_eq!(settings.text_inserted, InsertionPriority::Fixed(SpeechPriority::Text));
+		assert_eq!(settings.voices.get(&SpeechPriority::Important), Some(&fast));
+		assert_eq!(settings.voices.get(&SpeechPriority::Text), None);
+	}
+}
@@ -3,28 +3,70 @@ use serde::{Deserialize, Serialize};
 #[derive(Debug, Serialize, Deserialize)]
 #[allow(clippy::module_name_repetitions)]
 pub struct SpeechSettings {
+	///where speech goes: speech-dispatcher, a command of your choosing, standard output, or nowhere
+	/// printing to standard output shows what would have been said, which is useful when running odilia in CI or over SSH
+	pub output: SpeechOutput,
+	///the program, and its arguments, to speak through when the output is set to Command; it reads the text to speak from its standard input
+	/// `{rate}` and `{voice}` in the arguments are replaced by the current rate and person
+	/// left empty, espeak-ng is used
+	pub command: Vec<String>,
 	pub rate: i8,
+	///how much the rate changes by each time it is increased or decreased with a command
+	pub rate_step: u8,
+	///write the rate back to the configuration file whenever it is changed with a command, so it is kept after a restart
+	/// off by default, in which case the configured rate is used again on the next start
+	pub persist_rate: bool,
 	pub pitch: i8,
 	pub volume: i8,
+	///the speech-dispatcher output module to speak through, for example espeak-ng or pico
+	/// if the module is not available, or this is left empty, speech-dispatcher's default module is used instead
 	pub module: String,
 	pub language: String,
+	///the voice to use within the output module
 	pub person: String,
 	pub punctuation: PunctuationSpellingMode,
+	///whether numbers are read as whole numbers, or one digit at a time
+	/// reading digits is useful for things like verification codes and phone numbers
+	pub number_reading: NumberReading,
+	///what to do with runs of spaces, tabs and newlines in spoken text
+	pub whitespace: WhitespaceMode,
+	///how blank lines are read when reading a whole multiline text field at once
+	pub newline_handling: NewlineHandling,
 }
 impl Default for SpeechSettings {
 	fn default() -> Self {
 		Self {
+			output: SpeechOutput::SpeechDispatcher,
+			command: Vec::new(),
 			rate: 50,
+			rate_step: 10,
+			persist_rate: false,
 			pitch: 0,
 			volume: 100,
 			module: "espeak-ng".into(),
 			language: "en-US".into(),
 			person: "English (America)+Max".into(),
 			punctuation: PunctuationSpellingMode::Some,
+			number_reading: NumberReading::Words,
+			whitespace: WhitespaceMode::Collapse,
+			newline_handling: NewlineHandling::Pause,
 		}
 	}
 }
 
+///where odilia sends the text it speaks
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SpeechOutput {
+	///spoken aloud through speech-dispatcher
+	SpeechDispatcher,
+	///spoken aloud by running the configured command, for systems without speech-dispatcher
+	Command,
+	///printed to standard output, one message per line, prefixed with its priority
+	Stdout,
+	///dropped without being spoken or printed
+	Silent,
+}
+
 #[derive(Clone, Copy, Debug, Serialize, Deserialize)]
 pub enum PunctuationSpellingMode {
 	Some,
@@ -32,3 +74,35 @@ pub enum PunctuationSpellingMode {
 	None,
 	All,
 }
+
+///how numbers in spoken text are read
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum NumberReading {
+	///"1024" is read as "one thousand twenty-four"
+	Words,
+	///"1024" is read as "one zero two four"
+	Digits,
+}
+
+///how whitespace in spoken text is handled
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum WhitespaceMode {
+	///runs of whitespace become a single space, and whitespace at either end is removed
+	Collapse,
+	///text is sent to the speech server untouched
+	Preserve,
+	///like collapse, but every line break is spoken as "new line", which is useful in editors
+	Announce,
+}
+
+///how blank lines are read when reading a multiline text field from top to bottom
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum NewlineHandling {
+	///each line is spoken on its own, so the speech pauses between lines and blank lines are skipped
+	#[default]
+	Pause,
+	///like pause, but each run of blank lines is spoken as the given word, for example `{ Word = "new line" }`
+	Word(String),
+	///every line is run together into one message, as if there were no line breaks
+	Ignore,
+}
@@ -0,0 +1,27 @@
+use serde::{Deserialize, Serialize};
+///structure for the options controlling the watchdog, which notices when odilia has stopped handling accessibility events
+#[derive(Debug, Serialize, Deserialize)]
+#[allow(clippy::module_name_repetitions)]
+pub struct WatchdogSettings {
+	///watch for event handling getting stuck, and report it in the log
+	pub enabled: bool,
+	///how many seconds handling one event may take before odilia counts as stuck
+	pub stall_seconds: u64,
+	///what to do once odilia is stuck, besides logging it
+	pub on_stall: StallAction,
+}
+impl Default for WatchdogSettings {
+	fn default() -> Self {
+		Self { enabled: true, stall_seconds: 10, on_stall: StallAction::Log }
+	}
+}
+
+///what the watchdog does when event handling is stuck
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum StallAction {
+	///only log an error, and keep waiting in case it recovers
+	#[default]
+	Log,
+	///shut odilia down, so that whatever started it, like a session manager, can start it again
+	Shutdown,
+}
@@ -4,15 +4,18 @@ use zbus::zvariant::OwnedObjectPath;
 
 pub type Accessible = (String, OwnedObjectPath);
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub struct IndexesSelection {
 	pub start: i32,
 	pub end: i32,
 }
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub struct GranularSelection {
 	pub index: i32,
 	pub granularity: Granularity,
 }
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub enum TextSelectionArea {
 	Index(IndexesSelection),
 	Granular(GranularSelection),
@@ -28,3 +31,36 @@ pub enum AriaLive {
 }
 
 pub type AriaAtomic = bool;
+
+#[cfg(test)]
+mod tests {
+	use super::{GranularSelection, IndexesSelection, TextSelectionArea};
+	use atspi_common::Granularity;
+
+	fn round_trip(area: TextSelectionArea) -> TextSelectionArea {
+		let json = serde_json::to_string(&area).expect("selection can be serialized");
+		serde_json::from_str(&json).expect("selection can be deserialized")
+	}
+
+	#[test]
+	fn index_selections_survive_serialization() {
+		let area = TextSelectionArea::Index(IndexesSelection { start: 4, end: 12 });
+		assert_eq!(round_trip(area), area);
+	}
+	#[test]
+	fn granular_selections_survive_serialization() {
+		for granularity in [
+			Granularity::Char,
+			Granularity::Word,
+			Granularity::Sentence,
+			Granularity::Line,
+			Granularity::Paragraph,
+		] {
+			let area = TextSelectionArea::Granular(GranularSelection {
+				index: 7,
+				granularity,
+			});
+			assert_eq!(round_trip(area), area);
+		}
+	}
+}
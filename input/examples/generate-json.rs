@@ -8,7 +8,7 @@ fn main() {
 	let stop = ScreenReaderEvent::StopSpeech;
 
 	// change to an arbitrary mode
-	let mode_change = ScreenReaderEvent::ChangeMode(ScreenReaderMode::new("browse mode"));
+	let mode_change = ScreenReaderEvent::ChangeMode(ScreenReaderMode::new("browse").unwrap());
 
 	println!("{}", serde_json::to_string(&noop).unwrap());
 	println!("{}", serde_json::to_string(&stop).unwrap());
@@ -8,6 +8,9 @@
 )]
 #![allow(clippy::multiple_crate_versions)]
 
+mod source;
+pub use source::{run_sources, InputFuture, InputSource, ScriptedSource, SocketSource};
+
 use eyre::Context;
 use nix::unistd::Uid;
 use odilia_common::events::ScreenReaderEvent;
@@ -0,0 +1,171 @@
+//! Places [`ScreenReaderEvent`]s come from, behind a common interface.
+//! Odilia only ever reads events from one channel; every source just sends into it, so any number of them can run side by side.
+
+use odilia_common::events::ScreenReaderEvent;
+use std::{future::Future, pin::Pin};
+use tokio::{sync::mpsc::Sender, task::JoinSet};
+use tokio_util::sync::CancellationToken;
+
+use crate::sr_event_receiver;
+
+/// The result of running an [`InputSource`], boxed so the trait can be used as `dyn InputSource`.
+pub type InputFuture = Pin<Box<dyn Future<Output = eyre::Result<()>> + Send>>;
+
+/// Something which produces screen reader events, like the socket commands are sent over.
+pub trait InputSource: Send {
+	/// A short name for the source, used when logging.
+	fn name(&self) -> &'static str;
+	/// Send events to `events` until the source runs out of them, every receiver is dropped, or `shutdown` is cancelled.
+	fn run(
+		self: Box<Self>,
+		events: Sender<ScreenReaderEvent>,
+		shutdown: CancellationToken,
+	) -> InputFuture;
+}
+
+/// Events sent as JSON over Odilia's Unix socket; see [`sr_event_receiver`].
+#[derive(Debug, Default)]
+pub struct SocketSource;
+
+impl InputSource for SocketSource {
+	fn name(&self) -> &'static str {
+		"socket"
+	}
+	fn run(
+		self: Box<Self>,
+		events: Sender<ScreenReaderEvent>,
+		shutdown: CancellationToken,
+	) -> InputFuture {
+		Box::pin(sr_event_receiver(events, shutdown))
+	}
+}
+
+/// A fixed list of events, sent one after the other as soon as the source runs.
+/// Useful for driving Odilia through a sequence of commands in tests.
+#[derive(Default)]
+pub struct ScriptedSource {
+	events: Vec<ScreenReaderEvent>,
+}
+
+impl ScriptedSource {
+	#[must_use]
+	pub fn new(events: Vec<ScreenReaderEvent>) -> Self {
+		Self { events }
+	}
+}
+
+impl InputSource for ScriptedSource {
+	fn name(&self) -> &'static str {
+		"script"
+	}
+	fn run(
+		self: Box<Self>,
+		events: Sender<ScreenReaderEvent>,
+		shutdown: CancellationToken,
+	) -> InputFuture {
+		Box::pin(async move {
+			for event in self.events {
+				tokio::select! {
+					biased;
+					() = shutdown.cancelled() => break,
+					sent = events.send(event) => {
+						if sent.is_err() {
+							tracing::debug!("Nobody is listening for scripted events anymore");
+							break;
+						}
+					}
+				}
+			}
+			Ok(())
+		})
+	}
+}
+
+/// Run every source at once, all sending into `events`.
+/// This finishes once every source has finished.
+/// # Errors
+/// The first error returned by any of the sources; the others keep running until they finish on their own.
+#[tracing::instrument(level = "debug", skip_all, err)]
+pub async fn run_sources(
+	sources: Vec<Box<dyn InputSource>>,
+	events: Sender<ScreenReaderEvent>,
+	shutdown: CancellationToken,
+) -> eyre::Result<()> {
+	let mut running = JoinSet::new();
+	for source in sources {
+		let name = source.name();
+		tracing::debug!(source = name, "Starting input source");
+		let run = source.run(events.clone(), shutdown.clone());
+		running.spawn(async move { (name, run.await) });
+	}
+	// only the sources hold a sender now, so the receiver sees the channel close once they are all done
+	drop(events);
+	let mut result = Ok(());
+	while let Some(finished) = running.join_next().await {
+		let (name, finished) = finished?;
+		tracing::debug!(source = name, "Input source finished");
+		if let Err(e) = finished {
+			tracing::error!(source = name, error = %e, "Input source failed");
+			if result.is_ok() {
+				result = Err(e);
+			}
+		}
+	}
+	result
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{run_sources, InputSource, ScriptedSource};
+	use odilia_common::events::{Direction, ScreenReaderEvent};
+	use tokio::sync::mpsc;
+	use tokio_util::sync::CancellationToken;
+
+	#[tokio::test]
+	async fn scripted_sources_feed_one_channel() {
+		let (events, mut received) = mpsc::channel(8);
+		let sources: Vec<Box<dyn InputSource>> = vec![
+			Box::new(ScriptedSource::new(vec![
+				ScreenReaderEvent::StopSpeech,
+				ScreenReaderEvent::IncreaseRate,
+				ScreenReaderEvent::ReviewNextMessage,
+			])),
+			Box::new(ScriptedSource::new(vec![ScreenReaderEvent::ChangeGranularity(
+				Direction::Backward,
+			)])),
+		];
+		run_sources(sources, events, CancellationToken::new())
+			.await
+			.expect("scripted sources never fail");
+		let mut all = Vec::new();
+		while let Some(event) = received.recv().await {
+			all.push(event);
+		}
+		assert_eq!(all.len(), 4);
+		// events from one source keep their order
+		let first: Vec<&ScreenReaderEvent> = all
+			.iter()
+			.filter(|event| {
+				**event != ScreenReaderEvent::ChangeGranularity(Direction::Backward)
+			})
+			.collect();
+		assert!(first
+			== [
+				&ScreenReaderEvent::StopSpeech,
+				&ScreenReaderEvent::IncreaseRate,
+				&ScreenReaderEvent::ReviewNextMessage,
+			]);
+	}
+	#[tokio::test]
+	async fn cancelled_sources_stop_sending() {
+		let (events, mut received) = mpsc::channel(8);
+		let shutdown = CancellationToken::new();
+		shutdown.cancel();
+		let source: Box<dyn InputSource> =
+			Box::new(ScriptedSource::new(vec![ScreenReaderEvent::StopSpeech]));
+		run_sources(vec![source], events, shutdown)
+			.await
+			.expect("cancelling is not an error");
+		assert!(received.recv().await.is_none());
+	}
+}
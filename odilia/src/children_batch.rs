@@ -0,0 +1,152 @@
+//! Children recently added to or removed from the accessibility tree, gathered up so they can be handled in one pass.
+//! Pages which build large documents add thousands of children at once, and handling each one on its own means a separate round trip to the application for every child.
+
+use odilia_cache::AccessiblePrimitive;
+use std::{
+	collections::HashMap,
+	time::{Duration, Instant},
+};
+
+/// How many changed children are gathered before they are handled, even if the window has not passed yet.
+pub const MAX_BATCH_SIZE: usize = 256;
+
+/// Whether a child was added or removed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ChildChange {
+	Added,
+	Removed,
+}
+
+/// The changes taken out of a batch at once, ready to be handled.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct ChildChanges {
+	/// Added children, by the parent they were added to, in the order they were added.
+	pub added: HashMap<AccessiblePrimitive, Vec<AccessiblePrimitive>>,
+	/// Removed children, in the order they were removed.
+	pub removed: Vec<AccessiblePrimitive>,
+}
+
+/// Changed children waiting to be handled, until either the window has passed since the oldest one or there are too many of them.
+#[derive(Debug)]
+pub struct ChildrenBatch {
+	changes: ChildChanges,
+	/// When the oldest change waiting in the batch arrived.
+	opened: Option<Instant>,
+	window: Duration,
+	max_size: usize,
+}
+
+impl ChildrenBatch {
+	#[must_use]
+	pub fn new(window: Duration, max_size: usize) -> Self {
+		Self { changes: ChildChanges::default(), opened: None, window, max_size }
+	}
+	/// How many changes are waiting.
+	#[must_use]
+	pub fn len(&self) -> usize {
+		self.changes.added.values().map(Vec::len).sum::<usize>()
+			+ self.changes.removed.len()
+	}
+	#[must_use]
+	pub fn is_empty(&self) -> bool {
+		self.len() == 0
+	}
+	/// Add a change to the batch.
+	/// Only the latest change to a child is kept, so a child which is added and removed again straight away is only removed.
+	pub fn push(
+		&mut self,
+		parent: AccessiblePrimitive,
+		child: AccessiblePrimitive,
+		change: ChildChange,
+		now: Instant,
+	) {
+		for children in self.changes.added.values_mut() {
+			children.retain(|added| added != &child);
+		}
+		self.changes.added.retain(|_, children| !children.is_empty());
+		self.changes.removed.retain(|removed| removed != &child);
+		match change {
+			ChildChange::Added => {
+				self.changes.added.entry(parent).or_default().push(child);
+			}
+			ChildChange::Removed => self.changes.removed.push(child),
+		}
+		self.opened.get_or_insert(now);
+	}
+	/// Whether the batch has as many changes as it may hold.
+	#[must_use]
+	pub fn is_full(&self) -> bool {
+		self.len() >= self.max_size
+	}
+	/// Whether the batch should be handled now: it is full, or its oldest change has waited for the whole window.
+	#[must_use]
+	pub fn is_due(&self, now: Instant) -> bool {
+		self.is_full()
+			|| self.opened.is_some_and(|opened| {
+				now.saturating_duration_since(opened) >= self.window
+			})
+	}
+	/// Take every waiting change out of the batch, leaving it empty.
+	pub fn take(&mut self) -> ChildChanges {
+		self.opened = None;
+		std::mem::take(&mut self.changes)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{ChildChange, ChildrenBatch};
+	use odilia_cache::AccessiblePrimitive;
+	use std::time::{Duration, Instant};
+
+	fn prim(id: &str) -> AccessiblePrimitive {
+		AccessiblePrimitive {
+			id: format!("/org/a11y/atspi/accessible/{id}"),
+			sender: ":1.2".into(),
+		}
+	}
+
+	#[test]
+	fn added_children_are_grouped_by_parent() {
+		let now = Instant::now();
+		let mut batch = ChildrenBatch::new(Duration::from_millis(50), 10);
+		batch.push(prim("list"), prim("1"), ChildChange::Added, now);
+		batch.push(prim("list"), prim("2"), ChildChange::Added, now);
+		batch.push(prim("table"), prim("3"), ChildChange::Added, now);
+		batch.push(prim("table"), prim("4"), ChildChange::Removed, now);
+		assert_eq!(batch.len(), 4);
+		let changes = batch.take();
+		assert_eq!(changes.added[&prim("list")], [prim("1"), prim("2")]);
+		assert_eq!(changes.added[&prim("table")], [prim("3")]);
+		assert_eq!(changes.removed, [prim("4")]);
+		assert!(batch.is_empty());
+	}
+	#[test]
+	fn only_the_latest_change_to_a_child_is_kept() {
+		let now = Instant::now();
+		let mut batch = ChildrenBatch::new(Duration::from_millis(50), 10);
+		batch.push(prim("list"), prim("1"), ChildChange::Added, now);
+		batch.push(prim("list"), prim("1"), ChildChange::Removed, now);
+		batch.push(prim("list"), prim("2"), ChildChange::Removed, now);
+		batch.push(prim("list"), prim("2"), ChildChange::Added, now);
+		let changes = batch.take();
+		assert_eq!(changes.added[&prim("list")], [prim("2")]);
+		assert_eq!(changes.removed, [prim("1")]);
+	}
+	#[test]
+	fn batches_are_due_after_the_window_or_once_full() {
+		let start = Instant::now();
+		let window = Duration::from_millis(50);
+		let mut batch = ChildrenBatch::new(window, 3);
+		assert!(!batch.is_due(start + window));
+		batch.push(prim("list"), prim("1"), ChildChange::Added, start);
+		batch.push(prim("list"), prim("2"), ChildChange::Added, start + window / 2);
+		assert!(!batch.is_due(start + window / 2));
+		assert!(batch.is_due(start + window));
+		batch.push(prim("list"), prim("3"), ChildChange::Added, start);
+		assert!(batch.is_full());
+		assert!(batch.is_due(start));
+		batch.take();
+		assert!(!batch.is_due(start + window * 2));
+	}
+}
@@ -1,10 +1,70 @@
 use clap::Parser;
 use std::path::PathBuf;
 
-#[derive(Parser)]
+#[derive(Debug, Parser)]
 #[command(version, about, author)]
 pub struct Args {
 	/// Specify a custom Odilia configuration path
 	#[arg(short, long, value_name = "FILE")]
 	pub config: Option<PathBuf>,
+	/// Run without speech, dropping everything which would be spoken; useful for debugging the cache and events
+	#[arg(long)]
+	pub no_speech: bool,
+	/// Print everything which would be spoken to standard output instead of speaking it
+	#[arg(long, conflicts_with = "no_speech")]
+	pub print_speech: bool,
+	/// Override the logging level, using the same filter syntax as the `log.level` setting, like "debug" or "odilia=trace"
+	#[arg(short, long, value_name = "FILTER")]
+	pub log_level: Option<String>,
+	/// Write logs as JSON, one object per line, whatever the `log.format` setting says
+	#[arg(long)]
+	pub json_logs: bool,
+}
+
+#[cfg(test)]
+mod tests {
+	use super::Args;
+	use clap::Parser;
+	use std::path::PathBuf;
+
+	fn parse(argv: &[&str]) -> Args {
+		Args::try_parse_from(argv).expect("arguments should be valid")
+	}
+
+	#[test]
+	fn no_arguments_keep_the_defaults() {
+		let args = parse(&["odilia"]);
+		assert_eq!(args.config, None);
+		assert!(!args.no_speech);
+		assert!(!args.print_speech);
+		assert_eq!(args.log_level, None);
+		assert!(!args.json_logs);
+	}
+	#[test]
+	fn all_arguments_are_read() {
+		let args = parse(&[
+			"odilia",
+			"--config",
+			"/tmp/odilia.toml",
+			"--no-speech",
+			"--log-level",
+			"odilia=trace",
+			"--json-logs",
+		]);
+		assert_eq!(args.config, Some(PathBuf::from("/tmp/odilia.toml")));
+		assert!(args.no_speech);
+		assert_eq!(args.log_level.as_deref(), Some("odilia=trace"));
+		assert!(args.json_logs);
+		let short = parse(&["odilia", "-c", "odilia.toml", "-l", "debug"]);
+		assert_eq!(short.config, Some(PathBuf::from("odilia.toml")));
+		assert_eq!(short.log_level.as_deref(), Some("debug"));
+		assert!(parse(&["odilia", "--print-speech"]).print_speech);
+	}
+	#[test]
+	fn invalid_arguments_are_rejected() {
+		assert!(Args::try_parse_from(["odilia", "--config"]).is_err());
+		assert!(Args::try_parse_from(["odilia", "--no-speech=yes"]).is_err());
+		assert!(Args::try_parse_from(["odilia", "--speech"]).is_err());
+		assert!(Args::try_parse_from(["odilia", "--no-speech", "--print-speech"]).is_err());
+	}
 }
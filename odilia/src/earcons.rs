@@ -0,0 +1,96 @@
+//! Short, non-speech sounds used to convey information faster than speech can.
+//! Tones are generated on the fly and played through `aplay`, so no sound files need to be installed.
+
+use std::{
+	f32::consts::TAU,
+	io::Write,
+	process::{Command, Stdio},
+	time::Duration,
+};
+
+/// Sample rate of the generated tones, in Hz.
+const SAMPLE_RATE: u32 = 22_050;
+/// The pitch of the tone for a line with no indentation, in Hz.
+const BASE_INDENT_FREQUENCY: f32 = 220.0;
+/// Indentation past this many columns all get the same tone, so deeply nested code does not become painfully high pitched.
+const MAX_INDENT_COLUMNS: usize = 48;
+/// How long each indentation tone plays for.
+pub const INDENT_TONE_DURATION: Duration = Duration::from_millis(60);
+
+/// Map the indentation of a line (in columns) to the frequency of the tone played before reading it.
+/// Every column raises the pitch by one semitone, so each level of four-space indentation is a major third higher than the last.
+#[must_use]
+#[allow(clippy::cast_precision_loss)]
+pub fn indent_tone_frequency(columns: usize) -> f32 {
+	let semitones = columns.min(MAX_INDENT_COLUMNS) as f32;
+	BASE_INDENT_FREQUENCY * (semitones / 12.0).exp2()
+}
+
+/// Generate a sine wave of the given frequency and length, as signed 16-bit little endian mono samples.
+#[must_use]
+#[allow(clippy::cast_possible_truncation, clippy::cast_precision_loss, clippy::cast_sign_loss)]
+pub fn sine_wave(frequency: f32, duration: Duration) -> Vec<u8> {
+	let sample_count = (duration.as_secs_f32() * SAMPLE_RATE as f32) as usize;
+	// keep the volume well below the maximum so tones do not drown out speech
+	let amplitude = f32::from(i16::MAX) * 0.3;
+	(0..sample_count)
+		.map(|idx| {
+			let time = idx as f32 / SAMPLE_RATE as f32;
+			((TAU * frequency * time).sin() * amplitude) as i16
+		})
+		.flat_map(i16::to_le_bytes)
+		.collect()
+}
+
+/// Play a tone of the given frequency and length.
+/// This returns once the tone has finished playing.
+/// # Errors
+/// If `aplay` is not installed, or exits with an error.
+#[tracing::instrument(level = "debug", err)]
+pub async fn play_tone(frequency: f32, duration: Duration) -> eyre::Result<()> {
+	let samples = sine_wave(frequency, duration);
+	tokio::task::spawn_blocking(move || -> eyre::Result<()> {
+		let mut player = Command::new("aplay")
+			.args(["-q", "-t", "raw", "-f", "S16_LE", "-c", "1", "-r"])
+			.arg(SAMPLE_RATE.to_string())
+			.stdin(Stdio::piped())
+			.stdout(Stdio::null())
+			.stderr(Stdio::null())
+			.spawn()?;
+		if let Some(mut stdin) = player.stdin.take() {
+			stdin.write_all(&samples)?;
+		}
+		let status = player.wait()?;
+		if !status.success() {
+			return Err(eyre::eyre!("aplay exited with {status}"));
+		}
+		Ok(())
+	})
+	.await?
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{indent_tone_frequency, sine_wave, BASE_INDENT_FREQUENCY, SAMPLE_RATE};
+	use std::time::Duration;
+
+	#[test]
+	fn unindented_lines_use_the_base_tone() {
+		assert!((indent_tone_frequency(0) - BASE_INDENT_FREQUENCY).abs() < f32::EPSILON);
+	}
+	#[test]
+	fn twelve_columns_is_an_octave_higher() {
+		assert!((indent_tone_frequency(12) - BASE_INDENT_FREQUENCY * 2.0).abs() < 0.01);
+	}
+	#[test]
+	fn tone_rises_with_indentation_and_is_capped() {
+		assert!(indent_tone_frequency(4) < indent_tone_frequency(8));
+		assert!((indent_tone_frequency(200) - indent_tone_frequency(48)).abs()
+			< f32::EPSILON);
+	}
+	#[test]
+	fn sine_wave_has_two_bytes_per_sample() {
+		let samples = sine_wave(440.0, Duration::from_secs(1));
+		assert_eq!(samples.len(), SAMPLE_RATE as usize * 2);
+	}
+}
@@ -1,8 +1,16 @@
+use std::sync::Arc;
+
 use crate::ScreenReaderState;
 use atspi::events::{
 	AddAccessibleEvent, CacheEvents, LegacyAddAccessibleEvent, RemoveAccessibleEvent,
 };
+use atspi_common::State;
+use futures::StreamExt;
 use odilia_cache::AccessiblePrimitive;
+use odilia_common::{result::OdiliaResult, settings::cache::CacheWarming};
+use ssip_client_async::Priority;
+use tokio_util::sync::CancellationToken;
+use zbus::names::UniqueName;
 
 #[tracing::instrument(level = "debug", skip(state), ret, err)]
 pub async fn dispatch(state: &ScreenReaderState, event: &CacheEvents) -> eyre::Result<()> {
@@ -11,7 +19,7 @@ pub async fn dispatch(state: &ScreenReaderState, event: &CacheEvents) -> eyre::R
 		CacheEvents::LegacyAdd(ladd_event) => {
 			legacy_add_accessible(state, ladd_event).await?;
 		}
-		CacheEvents::Remove(rem_event) => remove_accessible(state, rem_event)?,
+		CacheEvents::Remove(rem_event) => remove_accessible(state, rem_event).await?,
 	}
 	Ok(())
 }
@@ -37,11 +45,218 @@ pub async fn legacy_add_accessible(
 }
 
 #[tracing::instrument(level = "debug", skip(state), ret, err)]
-pub fn remove_accessible(
+pub async fn remove_accessible(
 	state: &ScreenReaderState,
 	event: &RemoveAccessibleEvent,
 ) -> eyre::Result<()> {
 	let accessible_prim: AccessiblePrimitive = AccessiblePrimitive::from_event(event)?;
 	state.cache.remove(&accessible_prim);
+	state.live_attributes.lock().await.remove(&accessible_prim);
 	Ok(())
 }
+
+/// If a `NameOwnerChanged` signal means an application's connection has closed, return its unique name.
+/// Well-known names (like `org.a11y.atspi.Registry`) changing hands are ignored, since the cache is keyed by unique names.
+pub fn lost_unique_name<'a>(
+	name: &'a str,
+	old_owner: Option<&str>,
+	new_owner: Option<&str>,
+) -> Option<&'a str> {
+	let owned_before = old_owner.is_some_and(|owner| !owner.is_empty());
+	let owned_now = new_owner.is_some_and(|owner| !owner.is_empty());
+	(name.starts_with(':') && owned_before && !owned_now).then_some(name)
+}
+
+/// Remove the cached items of every application which disconnects from the accessibility bus.
+#[tracing::instrument(level = "debug", skip_all, err)]
+pub async fn prune_dead_applications(
+	state: Arc<ScreenReaderState>,
+	shutdown: CancellationToken,
+) -> eyre::Result<()> {
+	let mut owner_changes = state.dbus.receive_name_owner_changed().await?;
+	loop {
+		tokio::select! {
+		    Some(signal) = owner_changes.next() => {
+			let args = signal.args()?;
+			let old_owner = args.old_owner.as_ref().map(UniqueName::as_str);
+			let new_owner = args.new_owner.as_ref().map(UniqueName::as_str);
+			if let Some(name) = lost_unique_name(args.name.as_str(), old_owner, new_owner) {
+			    let removed = state.cache.prune_by_sender(name);
+			    state.marks.lock().await.remove_sender(name);
+			    state.last_indentation.lock().await.retain(|accessible, _| accessible.sender.as_str() != name);
+			    state.live_attributes.lock().await.retain(|accessible, _| accessible.sender.as_str() != name);
+			    let mut active_modal = state.active_modal.lock().await;
+			    if active_modal.as_ref().is_some_and(|modal| modal.sender.as_str() == name) {
+				*active_modal = None;
+			    }
+			    tracing::debug!(name, removed, "Application left the bus; pruned its cached items.");
+			}
+		    }
+		    () = shutdown.cancelled() => {
+			tracing::debug!("Shutting down application pruning task.");
+			break;
+		    }
+		}
+	}
+	Ok(())
+}
+
+/// Warming more applications than this at once is announced, since it can take a while.
+const WARM_ANNOUNCE_THRESHOLD: usize = 5;
+
+/// The ways the cache can be filled up front, so warming can be checked without an accessibility bus.
+pub trait CachePopulator {
+	/// The unique bus name of the application with the active window, if any.
+	async fn focused_app(&self) -> OdiliaResult<Option<String>>;
+	/// Cache every item of one application, returning how many items were added.
+	async fn populate_from_app(&self, app: &str) -> OdiliaResult<usize>;
+	/// Cache every item of every application on the desktop, returning how many items were added.
+	async fn populate_from_desktop(&self) -> OdiliaResult<usize>;
+}
+
+/// The applications on the desktop, as children of the registry's root.
+pub(super) async fn desktop_apps(
+	state: &ScreenReaderState,
+) -> OdiliaResult<Vec<AccessiblePrimitive>> {
+	let desktop = AccessiblePrimitive {
+		id: "/org/a11y/atspi/accessible/root".to_string(),
+		sender: "org.a11y.atspi.Registry".into(),
+	};
+	let desktop = desktop.into_accessible(state.atspi.connection()).await?;
+	Ok(desktop
+		.get_children()
+		.await?
+		.into_iter()
+		.map(AccessiblePrimitive::from)
+		.collect())
+}
+
+impl CachePopulator for ScreenReaderState {
+	async fn focused_app(&self) -> OdiliaResult<Option<String>> {
+		for app in desktop_apps(self).await? {
+			let app_proxy =
+				app.clone().into_accessible(self.atspi.connection()).await?;
+			for window in app_proxy.get_children().await? {
+				let window = AccessiblePrimitive::from(window)
+					.into_accessible(self.atspi.connection())
+					.await?;
+				if window.get_state().await?.contains(State::Active) {
+					return Ok(Some(app.sender.to_string()));
+				}
+			}
+		}
+		Ok(None)
+	}
+	async fn populate_from_app(&self, app: &str) -> OdiliaResult<usize> {
+		let cache = self.build_cache(app).await?;
+		let items = cache.get_items().await?;
+		let count = items.len();
+		for item in items {
+			self.get_or_create_atspi_cache_item_to_cache(item).await?;
+		}
+		Ok(count)
+	}
+	async fn populate_from_desktop(&self) -> OdiliaResult<usize> {
+		let apps = desktop_apps(self).await?;
+		let announce = apps.len() > WARM_ANNOUNCE_THRESHOLD;
+		if announce {
+			self.say(
+				Priority::Progress,
+				format!("Caching {} applications", apps.len()),
+			)
+			.await;
+		}
+		let mut count = 0;
+		for app in apps {
+			// one application which does not answer should not keep the others out of the cache
+			match self.populate_from_app(app.sender.as_str()).await {
+				Ok(added) => count += added,
+				Err(e) => {
+					tracing::debug!(error = %e, app = %app.sender, "Could not cache application; skipping");
+				}
+			}
+		}
+		if announce {
+			self.say(Priority::Progress, "Cache ready".to_string()).await;
+		}
+		Ok(count)
+	}
+}
+
+/// Fill the cache with the applications chosen by `scope`, returning how many items were added.
+#[tracing::instrument(level = "debug", skip(populator), ret, err)]
+pub async fn warm_cache(
+	populator: &impl CachePopulator,
+	scope: CacheWarming,
+) -> OdiliaResult<usize> {
+	match scope {
+		CacheWarming::Off => Ok(0),
+		CacheWarming::FocusedApp => match populator.focused_app().await? {
+			Some(app) => populator.populate_from_app(&app).await,
+			None => Ok(0),
+		},
+		CacheWarming::AllApps => populator.populate_from_desktop().await,
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{lost_unique_name, warm_cache, CachePopulator};
+	use odilia_common::{result::OdiliaResult, settings::cache::CacheWarming};
+	use std::sync::Mutex;
+
+	/// Records which populate calls were made, instead of talking to any application.
+	#[derive(Default)]
+	struct PopulateSpy {
+		focused: Option<String>,
+		calls: Mutex<Vec<String>>,
+	}
+
+	impl CachePopulator for PopulateSpy {
+		async fn focused_app(&self) -> OdiliaResult<Option<String>> {
+			Ok(self.focused.clone())
+		}
+		async fn populate_from_app(&self, app: &str) -> OdiliaResult<usize> {
+			self.calls.lock().unwrap().push(app.to_string());
+			Ok(10)
+		}
+		async fn populate_from_desktop(&self) -> OdiliaResult<usize> {
+			self.calls.lock().unwrap().push("desktop".to_string());
+			Ok(100)
+		}
+	}
+
+	#[test]
+	fn closed_connection_is_detected() {
+		assert_eq!(lost_unique_name(":1.42", Some(":1.42"), None), Some(":1.42"));
+		assert_eq!(lost_unique_name(":1.42", Some(":1.42"), Some("")), Some(":1.42"));
+	}
+	#[test]
+	fn new_connections_are_ignored() {
+		assert_eq!(lost_unique_name(":1.42", None, Some(":1.42")), None);
+		assert_eq!(lost_unique_name(":1.42", Some(""), Some(":1.42")), None);
+	}
+	#[test]
+	fn well_known_names_are_ignored() {
+		assert_eq!(lost_unique_name("org.a11y.atspi.Registry", Some(":1.0"), None), None);
+	}
+	#[tokio::test]
+	async fn warming_off_populates_nothing() {
+		let spy = PopulateSpy { focused: Some(":1.42".to_string()), ..Default::default() };
+		assert_eq!(warm_cache(&spy, CacheWarming::Off).await.unwrap(), 0);
+		assert!(spy.calls.lock().unwrap().is_empty());
+	}
+	#[tokio::test]
+	async fn warming_scope_picks_the_populate_call() {
+		let spy = PopulateSpy { focused: Some(":1.42".to_string()), ..Default::default() };
+		assert_eq!(warm_cache(&spy, CacheWarming::FocusedApp).await.unwrap(), 10);
+		assert_eq!(warm_cache(&spy, CacheWarming::AllApps).await.unwrap(), 100);
+		assert_eq!(*spy.calls.lock().unwrap(), [":1.42", "desktop"]);
+	}
+	#[tokio::test]
+	async fn warming_the_focused_app_without_focus_populates_nothing() {
+		let spy = PopulateSpy::default();
+		assert_eq!(warm_cache(&spy, CacheWarming::FocusedApp).await.unwrap(), 0);
+		assert!(spy.calls.lock().unwrap().is_empty());
+	}
+}
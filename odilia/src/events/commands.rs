@@ -0,0 +1,1519 @@
+use crate::marks::Mark;
+use crate::message_history::Review;
+use crate::number_format::NumberFormat;
+use crate::state::ScreenReaderState;
+use atspi_common::{
+	CoordType, Granularity, Interface, ObjectRef, RelationType, Role, State, StateSet,
+};
+use atspi_proxies::{accessible::AccessibleProxy, action::ActionProxy};
+use odilia_cache::{
+	children_with_embeds, embedder, AccessibleExt, AccessibleId, AccessiblePrimitive, Cache,
+	CacheItem, Convertable,
+};
+use odilia_common::{
+	errors::OdiliaError,
+	events::{Direction, ScreenReaderEvent},
+	result::OdiliaResult,
+	settings::{
+		navigation::CaretGranularity,
+		presentation::{CoordinateUnit, DocumentPositionUnit},
+		speech::NewlineHandling,
+		SPEECH_RANGE,
+	},
+};
+use ssip_client_async::Priority;
+use std::sync::atomic::Ordering;
+use tokio::fs;
+use toml_edit::{DocumentMut, Item};
+
+/// Roles which are read by [`read_status_bar`].
+pub const STATUS_BAR_ROLES: [Role; 2] = [Role::StatusBar, Role::Notification];
+
+/// Guess how recently an item was created from the number at the end of its ID.
+/// Toolkits hand out these numbers in increasing order, so a bigger number means a newer item.
+/// Items without a numeric ID (like `root`) are treated as the oldest.
+fn creation_order(item: &CacheItem) -> Option<u64> {
+	match AccessibleId::try_from(&item.object) {
+		Ok(AccessibleId::Number(number)) => Some(number),
+		_ => None,
+	}
+}
+
+/// Order status bars and notifications from most to least recent, then join them into a single announcement.
+/// Items without any text are skipped.
+#[must_use]
+pub fn status_bar_announcement(mut items: Vec<CacheItem>) -> String {
+	items.sort_by_key(|item| std::cmp::Reverse(creation_order(item)));
+	let parts: Vec<String> = items
+		.iter()
+		.filter(|item| !item.text.trim().is_empty())
+		.map(|item| format!("{}, {}", item.role, item.text.trim()))
+		.collect();
+	if parts.is_empty() {
+		return "No status bar or notifications".to_string();
+	}
+	parts.join(". ")
+}
+
+#[tracing::instrument(level = "debug", skip(state))]
+pub async fn read_status_bar(state: &ScreenReaderState) {
+	let items = state.cache.find_by_role(&STATUS_BAR_ROLES);
+	state.say(Priority::Text, status_bar_announcement(items)).await;
+}
+
+/// A single piece of information about an accessible which can be read on demand.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AccessibleField {
+	Name,
+	Description,
+}
+
+/// The text to speak for `field`, given its current value.
+/// Empty values are announced as such, rather than speaking nothing at all.
+#[must_use]
+pub fn field_announcement(field: AccessibleField, value: &str) -> String {
+	let value = value.trim();
+	match (field, value.is_empty()) {
+		(AccessibleField::Name, true) => "no name".to_string(),
+		(AccessibleField::Description, true) => "no description".to_string(),
+		(_, false) => value.to_string(),
+	}
+}
+
+/// Speak only one field of the focused item, fetched live from the application so that it is up to date.
+#[tracing::instrument(level = "debug", skip(state), err)]
+pub async fn read_focused_field(
+	state: &ScreenReaderState,
+	field: AccessibleField,
+) -> OdiliaResult<()> {
+	let Some(focused) = state.history_item(0).await else {
+		state.say(Priority::Text, "Nothing is focused".to_string()).await;
+		return Ok(());
+	};
+	let item = state.get_or_create_cache_item(focused).await?;
+	let value = match field {
+		AccessibleField::Name => item.name().await?,
+		AccessibleField::Description => item.description().await?,
+	};
+	state.say(Priority::Text, field_announcement(field, &value)).await;
+	Ok(())
+}
+
+/// Roles which are described with their alt text by [`describe_image`].
+const IMAGE_ROLES: [Role; 2] = [Role::Image, Role::Icon];
+
+/// Put together what is said to describe an image, from its alt text and the description given by the `Image` interface.
+/// The image description is left out when it only repeats the alt text.
+#[must_use]
+pub fn image_announcement(alt_text: &str, image_description: &str) -> String {
+	let alt_text = alt_text.trim();
+	let image_description = image_description.trim();
+	let parts: Vec<&str> = [alt_text, image_description]
+		.into_iter()
+		.filter(|part| !part.is_empty())
+		.collect();
+	match parts.as_slice() {
+		[] => "image, no description".to_string(),
+		[only] => format!("image: {only}"),
+		[alt, image, ..] if alt == image => format!("image: {alt}"),
+		_ => format!("image: {}", parts.join(", ")),
+	}
+}
+
+/// Describe the focused image, with its alt text fetched live from the application.
+#[tracing::instrument(level = "debug", skip(state), err)]
+pub async fn describe_image(state: &ScreenReaderState) -> OdiliaResult<()> {
+	let Some(focused) = state.history_item(0).await else {
+		state.say(Priority::Text, "Nothing is focused".to_string()).await;
+		return Ok(());
+	};
+	let item = state.get_or_create_cache_item(focused).await?;
+	if !IMAGE_ROLES.contains(&item.role) {
+		state.say(Priority::Text, "Not an image".to_string()).await;
+		return Ok(());
+	}
+	let alt_text = item.description().await?;
+	let image_description = if item.interfaces.contains(Interface::Image) {
+		item.object
+			.clone()
+			.into_accessible(state.atspi.connection())
+			.await?
+			.to_image()
+			.await?
+			.image_description()
+			.await?
+	} else {
+		String::new()
+	};
+	state.say(Priority::Text, image_announcement(&alt_text, &image_description))
+		.await;
+	Ok(())
+}
+
+/// The position and size of an item on screen, in pixels: x, y, width and height.
+pub type Bounds = (i32, i32, i32, i32);
+
+/// Put together what is said for the bounds of an item, like "at 120, 40, size 300 by 20".
+/// Percentages need the size of the screen; without it, pixels are read instead.
+#[must_use]
+pub fn bounds_announcement(
+	format: &dyn NumberFormat,
+	unit: CoordinateUnit,
+	bounds: Bounds,
+	screen: Option<(i32, i32)>,
+) -> String {
+	let (x, y, width, height) = bounds;
+	match (unit, screen) {
+		(CoordinateUnit::PercentOfScreen, Some((screen_width, screen_height)))
+			if screen_width > 0 && screen_height > 0 =>
+		{
+			let horizontal = |n: i32| {
+				format.percentage(f64::from(n) * 100.0 / f64::from(screen_width))
+			};
+			let vertical = |n: i32| {
+				format.percentage(f64::from(n) * 100.0 / f64::from(screen_height))
+			};
+			format!(
+				"at {}, {}, size {} by {}",
+				horizontal(x),
+				vertical(y),
+				horizontal(width),
+				vertical(height)
+			)
+		}
+		_ => format!("at {x}, {y}, size {width} by {height}"),
+	}
+}
+
+/// The size of the screen, from the extents of the desktop the registry exposes.
+async fn screen_size(state: &ScreenReaderState) -> OdiliaResult<(i32, i32)> {
+	let desktop = AccessiblePrimitive {
+		id: "/org/a11y/atspi/accessible/root".to_string(),
+		sender: "org.a11y.atspi.Registry".into(),
+	};
+	let desktop = desktop.into_accessible(state.atspi.connection()).await?;
+	let component = desktop.to_component().await?;
+	let (_, _, width, height) = component.get_extents(CoordType::Screen).await?;
+	Ok((width, height))
+}
+
+/// What is said when structural navigation lands on a landmark: what kind it is, then its label, like "navigation region, Main menu".
+/// `landmark` is the kind read from its attributes; landmarks which do not say are just called "landmark".
+#[must_use]
+pub fn landmark_announcement(landmark: Option<&str>, label: &str) -> String {
+	let kind = match landmark {
+		Some("region") => "region".to_string(),
+		Some(landmark) => format!("{landmark} region"),
+		None => "landmark".to_string(),
+	};
+	match label.trim() {
+		"" => kind,
+		label => format!("{kind}, {label}"),
+	}
+}
+
+/// Roles of the top-level windows structural navigation can move between.
+pub const WINDOW_ROLES: [Role; 3] = [Role::Frame, Role::Window, Role::Dialog];
+
+/// How many minimised or off-screen windows are passed over in one move before giving up.
+const WINDOW_SKIP_LIMIT: usize = 64;
+
+/// Whether a window is worth moving to: it is not minimised, has a size, and some of it is on screen.
+/// Without the size of the screen, only windows placed entirely above or to the left of it count as off-screen.
+#[must_use]
+pub fn is_navigable_window(states: &StateSet, bounds: Bounds, screen: Option<(i32, i32)>) -> bool {
+	let (x, y, width, height) = bounds;
+	if states.contains(State::Iconified) || width <= 0 || height <= 0 {
+		return false;
+	}
+	let before_screen = x.saturating_add(width) <= 0 || y.saturating_add(height) <= 0;
+	let after_screen = screen.is_some_and(|(screen_width, screen_height)| {
+		screen_width > 0 && screen_height > 0 && (x >= screen_width || y >= screen_height)
+	});
+	!before_screen && !after_screen
+}
+
+/// Starting from `next`, pass over windows which are minimised or off screen, and return the first one which is not.
+/// Windows whose states or bounds can not be fetched are not skipped.
+pub async fn skip_hidden_windows<'a>(
+	state: &ScreenReaderState,
+	mut next: Option<AccessibleProxy<'a>>,
+	role: Role,
+	direction: Direction,
+) -> OdiliaResult<Option<AccessibleProxy<'a>>> {
+	let screen = screen_size(state).await.ok();
+	for _ in 0..WINDOW_SKIP_LIMIT {
+		let Some(window) = next else {
+			return Ok(None);
+		};
+		let states = window.get_state().await;
+		let bounds = match window.to_component().await {
+			Ok(component) => component.get_extents(CoordType::Screen).await.ok(),
+			Err(_) => None,
+		};
+		let navigable = match (states, bounds) {
+			(Ok(states), Some(bounds)) => is_navigable_window(&states, bounds, screen),
+			_ => true,
+		};
+		if navigable {
+			return Ok(Some(window));
+		}
+		tracing::debug!("Skipping a window which is minimised or off screen");
+		next = window.get_next(role, direction == Direction::Backward, true).await?;
+	}
+	Ok(None)
+}
+
+/// Read where the focused item is on screen, and how big it is.
+#[tracing::instrument(level = "debug", skip(state), err)]
+pub async fn read_bounds(state: &ScreenReaderState) -> OdiliaResult<()> {
+	let Some(focused) = state.history_item(0).await else {
+		state.say(Priority::Text, "Nothing is focused".to_string()).await;
+		return Ok(());
+	};
+	// bounds are not cached, so they are always fetched from the application
+	let focused = focused.into_accessible(state.atspi.connection()).await?;
+	let component = focused.to_component().await?;
+	let bounds = component.get_extents(CoordType::Screen).await?;
+	let unit = state.config.presentation.coordinates;
+	let screen = match unit {
+		CoordinateUnit::Pixels => None,
+		CoordinateUnit::PercentOfScreen => match screen_size(state).await {
+			Ok(size) => Some(size),
+			Err(e) => {
+				tracing::debug!(error = %e, "Could not get the screen size; reading pixels instead");
+				None
+			}
+		},
+	};
+	let text = bounds_announcement(state.number_format(), unit, bounds, screen);
+	state.say(Priority::Text, text).await;
+	Ok(())
+}
+
+/// Which of an item's actions to perform.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ActionChoice<'a> {
+	/// The first action, which is the default by convention.
+	Default,
+	/// The action at this position in the list read by [`list_actions`], counting from 1.
+	Position(usize),
+	/// The action with this name, ignoring case.
+	Name(&'a str),
+}
+
+/// Find the index of the chosen action among an item's action names.
+/// Returns `None` when the item has no such action.
+#[must_use]
+pub fn select_action(names: &[String], choice: ActionChoice<'_>) -> Option<usize> {
+	match choice {
+		ActionChoice::Default => (!names.is_empty()).then_some(0),
+		ActionChoice::Position(position) => {
+			position.checked_sub(1).filter(|index| *index < names.len())
+		}
+		ActionChoice::Name(name) => names
+			.iter()
+			.position(|action| action.trim().eq_ignore_ascii_case(name.trim())),
+	}
+}
+
+/// Put together the list of an item's actions, numbered from 1 so that one can be chosen by its position.
+#[must_use]
+pub fn actions_announcement(names: &[String]) -> String {
+	if names.is_empty() {
+		return "No actions".to_string();
+	}
+	names.iter()
+		.enumerate()
+		.map(|(index, name)| format!("{}: {name}", index + 1))
+		.collect::<Vec<String>>()
+		.join(", ")
+}
+
+/// The focused item, if it offers any actions.
+async fn focused_with_actions(
+	state: &ScreenReaderState,
+) -> OdiliaResult<Option<AccessibleProxy<'static>>> {
+	let Some(focused) = state.history_item(0).await else {
+		return Ok(None);
+	};
+	let item = state.get_or_create_cache_item(focused).await?;
+	if !item.interfaces.contains(Interface::Action) {
+		return Ok(None);
+	}
+	Ok(Some(item.object.into_accessible(state.atspi.connection()).await?))
+}
+
+/// The localized names of an item's actions, in order.
+async fn action_names(action: &ActionProxy<'_>) -> OdiliaResult<Vec<String>> {
+	Ok(action
+		.get_actions()
+		.await?
+		.into_iter()
+		.map(|(name, _, _)| name)
+		.collect())
+}
+
+/// Read the actions the focused item offers.
+#[tracing::instrument(level = "debug", skip(state), err)]
+pub async fn list_actions(state: &ScreenReaderState) -> OdiliaResult<()> {
+	let names = match focused_with_actions(state).await? {
+		Some(accessible) => action_names(&accessible.to_action().await?).await?,
+		None => Vec::new(),
+	};
+	state.say(Priority::Text, actions_announcement(&names)).await;
+	Ok(())
+}
+
+/// Perform one of the focused item's actions, and say which one was done.
+#[tracing::instrument(level = "debug", skip(state), err)]
+pub async fn do_action(state: &ScreenReaderState, choice: ActionChoice<'_>) -> OdiliaResult<()> {
+	let Some(accessible) = focused_with_actions(state).await? else {
+		state.say(Priority::Text, "No actions".to_string()).await;
+		return Ok(());
+	};
+	let action = accessible.to_action().await?;
+	let names = action_names(&action).await?;
+	let Some(index) = select_action(&names, choice) else {
+		state.say(Priority::Text, "No such action".to_string()).await;
+		return Ok(());
+	};
+	if action.do_action(i32::try_from(index)?).await? {
+		state.say(Priority::Text, names[index].clone()).await;
+	} else {
+		state.say(Priority::Text, format!("Could not {}", names[index])).await;
+	}
+	Ok(())
+}
+
+/// Choose the text to read for [`read_all`]: the text fetched live when the cached copy was out of date, then the cached text, then the name.
+/// An item with none of these is read as "blank".
+#[must_use]
+pub fn read_all_text<'a>(cached: &'a str, live: Option<&'a str>, name: &'a str) -> &'a str {
+	[live.unwrap_or_default(), cached, name]
+		.into_iter()
+		.find(|text| !text.trim().is_empty())
+		.unwrap_or("blank")
+}
+
+/// Split multiline text into the messages [`read_all`] speaks, following how blank lines should be handled.
+/// Line breaks at the very end of the text only end its last line, so they are never read as blank lines.
+#[must_use]
+pub fn newline_chunks(text: &str, handling: &NewlineHandling) -> Vec<String> {
+	let mut chunks: Vec<String> = Vec::new();
+	let mut blank_run = false;
+	for line in text.lines() {
+		if line.trim().is_empty() {
+			blank_run = true;
+			continue;
+		}
+		if let (true, NewlineHandling::Word(word)) = (blank_run, handling) {
+			chunks.push(word.clone());
+		}
+		blank_run = false;
+		chunks.push(line.trim().to_string());
+	}
+	if let (true, NewlineHandling::Word(word)) = (blank_run, handling) {
+		chunks.push(word.clone());
+	}
+	if *handling == NewlineHandling::Ignore && !chunks.is_empty() {
+		return vec![chunks.join(" ")];
+	}
+	chunks
+}
+
+/// Roles which hold a whole document, whose reading position can be read.
+const DOCUMENT_ROLES: [Role; 5] = [
+	Role::DocumentWeb,
+	Role::DocumentFrame,
+	Role::DocumentText,
+	Role::DocumentEmail,
+	Role::DocumentPresentation,
+];
+
+/// How far through a document the item at `index` is, as a percentage, given the weight of every item in the document in reading order.
+/// The item itself counts as read, so the last item is at 100 percent.
+/// Returns `None` when `index` is past the end, or the document weighs nothing at all.
+#[must_use]
+#[allow(clippy::cast_precision_loss)]
+pub fn reading_percentage(weights: &[usize], index: usize) -> Option<f64> {
+	if index >= weights.len() {
+		return None;
+	}
+	let total: usize = weights.iter().sum();
+	if total == 0 {
+		return None;
+	}
+	let read: usize = weights[..=index].iter().sum();
+	Some(read as f64 * 100.0 / total as f64)
+}
+
+/// Put items into the order they appear on screen: top to bottom, and left to right along each line.
+/// An item is on the same line as the one above it in this order when its top edge is above the middle of the first item of that line, so slightly misaligned items are still read left to right.
+#[must_use]
+pub fn spatial_order<T>(mut items: Vec<(T, Bounds)>) -> Vec<T> {
+	items.sort_by_key(|(_, (x, y, _, _))| (*y, *x));
+	let mut ordered = Vec::with_capacity(items.len());
+	let mut line: Vec<(T, Bounds)> = Vec::new();
+	let mut line_middle = i32::MIN;
+	for (item, bounds) in items {
+		let (_, top, _, height) = bounds;
+		if top >= line_middle {
+			line.sort_by_key(|(_, (x, _, _, _))| *x);
+			ordered.extend(line.drain(..).map(|(item, _)| item));
+			line_middle = top.saturating_add((height / 2).max(1));
+		}
+		line.push((item, bounds));
+	}
+	line.sort_by_key(|(_, (x, _, _, _))| *x);
+	ordered.extend(line.into_iter().map(|(item, _)| item));
+	ordered
+}
+
+/// The bounds of an item on screen, fetched from its application.
+async fn item_bounds(state: &ScreenReaderState, item: &CacheItem) -> OdiliaResult<Bounds> {
+	let accessible = item.object.clone().into_accessible(state.atspi.connection()).await?;
+	let component = accessible.to_component().await?;
+	Ok(component.get_extents(CoordType::Screen).await?)
+}
+
+/// Put the children of an item in the order they appear on screen.
+/// Children whose bounds can not be fetched are kept at the end, in the order the application listed them.
+async fn spatially_ordered(state: &ScreenReaderState, children: Vec<CacheItem>) -> Vec<CacheItem> {
+	if children.len() < 2 {
+		return children;
+	}
+	let mut placed = Vec::with_capacity(children.len());
+	let mut unplaced = Vec::new();
+	for child in children {
+		match item_bounds(state, &child).await {
+			Ok(bounds) => placed.push((child, bounds)),
+			Err(e) => {
+				tracing::debug!(error = %e, "Could not get the bounds of a child; keeping it at the end");
+				unplaced.push(child);
+			}
+		}
+	}
+	let mut ordered = spatial_order(placed);
+	ordered.extend(unplaced);
+	ordered
+}
+
+/// Flatten an item and its cached descendants into the order they are read in, with each item before its children.
+/// Normally children are read in the order the application lists them; with spatial reading order turned on, they are read in the order they appear on screen instead.
+async fn reading_order(state: &ScreenReaderState, root: &CacheItem) -> Vec<CacheItem> {
+	if !state.config.navigation.spatial_reading_order {
+		return state.cache.descendants(&root.object);
+	}
+	let mut flattened: Vec<CacheItem> = Vec::new();
+	let mut stack = vec![root.clone()];
+	while let Some(item) = stack.pop() {
+		// a child listed twice, or a loop in the tree, would otherwise be walked forever
+		if flattened.iter().any(|seen| seen.object == item.object) {
+			continue;
+		}
+		let children: Vec<CacheItem> = item
+			.children
+			.iter()
+			.filter_map(|child| state.cache.get(&child.key))
+			.collect();
+		let children = spatially_ordered(state, children).await;
+		stack.extend(children.into_iter().rev());
+		flattened.push(item);
+	}
+	flattened
+}
+
+/// Read how far through its document the focused item is.
+#[tracing::instrument(level = "debug", skip(state), err)]
+pub async fn read_document_position(state: &ScreenReaderState) -> OdiliaResult<()> {
+	let Some(focused) = state.history_item(0).await else {
+		state.say(Priority::Text, "Nothing is focused".to_string()).await;
+		return Ok(());
+	};
+	let item = state.get_or_create_cache_item(focused).await?;
+	let document = std::iter::once(item.clone())
+		.chain(state.cache.ancestors(&item.object))
+		.find(|ancestor| DOCUMENT_ROLES.contains(&ancestor.role));
+	let Some(document) = document else {
+		state.say(Priority::Text, "Not in a document".to_string()).await;
+		return Ok(());
+	};
+	let flattened = reading_order(state, &document).await;
+	let weights: Vec<usize> = flattened
+		.iter()
+		.map(|item| match state.config.presentation.document_position {
+			DocumentPositionUnit::Nodes => 1,
+			DocumentPositionUnit::Characters => item.text.chars().count(),
+		})
+		.collect();
+	let percentage = flattened
+		.iter()
+		.position(|descendant| descendant.object == item.object)
+		.and_then(|index| reading_percentage(&weights, index));
+	let text = match percentage {
+		Some(percentage) => {
+			format!("{} through document", state.number_format().percentage(percentage))
+		}
+		None => "Position in document unknown".to_string(),
+	};
+	state.say(Priority::Text, text).await;
+	Ok(())
+}
+
+/// Put together everything needed to identify an item in a bug report: where it lives on the bus, its role, and its states and interfaces.
+/// Sets with nothing in them are read as "none".
+#[must_use]
+pub fn object_info(item: &CacheItem) -> String {
+	let list = |names: Vec<String>| {
+		if names.is_empty() {
+			"none".to_string()
+		} else {
+			names.join(", ")
+		}
+	};
+	let states = list(item.states.iter().map(|state| format!("{state:?}")).collect());
+	let interfaces = list(item
+		.interfaces
+		.iter()
+		.map(|interface| format!("{interface:?}"))
+		.collect());
+	format!(
+		"sender {}; path {}; role {}; states {states}; interfaces {interfaces}",
+		item.object.sender, item.object.id, item.role
+	)
+}
+
+/// Speak and log the full details of the focused item, for reporting bugs and writing scripts.
+/// Only available when debug commands are turned on in the settings.
+#[tracing::instrument(level = "debug", skip(state), err)]
+pub async fn report_object_info(state: &ScreenReaderState) -> OdiliaResult<()> {
+	if !state.config.log.debug_commands {
+		tracing::debug!("Debug commands are turned off; not reporting object info");
+		return Ok(());
+	}
+	let Some(focused) = state.history_item(0).await else {
+		state.say(Priority::Text, "Nothing is focused".to_string()).await;
+		return Ok(());
+	};
+	let item = state.get_or_create_cache_item(focused).await?;
+	let info = object_info(&item);
+	tracing::info!(info = %info, "Focused object");
+	state.say(Priority::Text, info).await;
+	Ok(())
+}
+
+/// Read the whole text of the focused item at once, without moving the caret.
+/// It is spoken as a message, so anything more urgent can interrupt it.
+#[tracing::instrument(level = "debug", skip(state), err)]
+pub async fn read_all(state: &ScreenReaderState) -> OdiliaResult<()> {
+	let Some(focused) = state.history_item(0).await else {
+		state.say(Priority::Text, "Nothing is focused".to_string()).await;
+		return Ok(());
+	};
+	let item = state.get_or_create_cache_item(focused).await?;
+	let mut live = None;
+	if item.interfaces.contains(Interface::Text) {
+		let proxy = item.object.clone().into_accessible(state.atspi.connection()).await?;
+		let text = proxy.to_text().await?;
+		let length = text.character_count().await?;
+		// the cached text can fall behind the application, so only trust it if it is the same length
+		if usize::try_from(length)? != item.text.chars().count() {
+			live = Some(text.get_text(0, length).await?);
+		}
+	}
+	let name = item.name().await?;
+	let text = read_all_text(&item.text, live.as_deref(), &name);
+	for chunk in newline_chunks(text, &state.config.speech.newline_handling) {
+		state.say(Priority::Message, chunk).await;
+	}
+	Ok(())
+}
+
+/// Read the next focused item in full detail, once.
+#[tracing::instrument(level = "debug", skip(state))]
+pub async fn verbose_next(state: &ScreenReaderState) {
+	state.verbose_next.store(true, Ordering::Relaxed);
+	state.say(Priority::Text, "Next item in full detail".to_string())
+		.await;
+}
+
+/// Speak the next message as important, once.
+#[tracing::instrument(level = "debug", skip(state))]
+pub async fn important_next(state: &ScreenReaderState) {
+	// said before the request is made, so that this is not the message it applies to
+	state.say(Priority::Text, "Next message important".to_string()).await;
+	state.important_next.store(true, Ordering::Relaxed);
+}
+
+/// Turn structural navigation on or off, and say which it now is.
+#[tracing::instrument(level = "debug", skip(state))]
+pub async fn toggle_structural_navigation(state: &ScreenReaderState) {
+	let enabled = !state.structural_navigation.fetch_xor(true, Ordering::Relaxed);
+	let text = if enabled { "Structural navigation on" } else { "Structural navigation off" };
+	state.say(Priority::Text, text.to_string()).await;
+}
+
+/// Whether Odilia acts on `event`: everything is, except structural navigation while it is turned off.
+/// The switch itself is always acted on, so that structural navigation can be turned back on.
+#[must_use]
+pub fn consumes_event(event: &ScreenReaderEvent, structural_navigation: bool) -> bool {
+	structural_navigation || !matches!(event, ScreenReaderEvent::StructuralNavigation(..))
+}
+
+/// Turn reading the word around the caret on or off, and say which it now is.
+#[tracing::instrument(level = "debug", skip(state))]
+pub async fn toggle_caret_context(state: &ScreenReaderState) {
+	let enabled = !state.speak_caret_context.fetch_xor(true, Ordering::Relaxed);
+	let text = if enabled { "Caret context on" } else { "Caret context off" };
+	state.say(Priority::Text, text.to_string()).await;
+}
+
+/// Switch between reading focused items by their accessible name and by the text they show, and say which it now is.
+#[tracing::instrument(level = "debug", skip(state))]
+pub async fn toggle_display_text(state: &ScreenReaderState) {
+	let enabled = !state.read_display_text.fetch_xor(true, Ordering::Relaxed);
+	let text = if enabled { "Reading display text" } else { "Reading accessible names" };
+	state.say(Priority::Text, text.to_string()).await;
+}
+
+/// The rate after one step of `step` in `direction`: forward is faster, backward is slower.
+/// The rate is kept within what speech-dispatcher accepts, so stepping past either end stays there.
+#[must_use]
+pub fn stepped_rate(rate: i8, step: u8, direction: Direction) -> i8 {
+	let rate = match direction {
+		Direction::Forward => rate.saturating_add_unsigned(step),
+		Direction::Backward => rate.saturating_sub_unsigned(step),
+	};
+	rate.clamp(*SPEECH_RANGE.start(), *SPEECH_RANGE.end())
+}
+
+/// The user's configuration file, with `key` in the `section` table set to `value` and everything else, comments and formatting included, left alone.
+/// # Errors
+/// If `config` is not valid TOML, or its `section` key is not a table.
+pub fn config_with_setting(
+	config: &str,
+	section: &str,
+	key: &str,
+	value: toml_edit::Value,
+) -> OdiliaResult<String> {
+	let mut document: DocumentMut = config
+		.parse()
+		.map_err(|e: toml_edit::TomlError| OdiliaError::Generic(e.to_string()))?;
+	let table = document
+		.entry(section)
+		.or_insert_with(toml_edit::table)
+		.as_table_like_mut()
+		.ok_or_else(|| OdiliaError::Generic(format!("{section} is not a table")))?;
+	if let Some(existing) = table.get_mut(key).and_then(Item::as_value_mut) {
+		// keep a comment after the old value
+		let decor = existing.decor().clone();
+		*existing = value;
+		*existing.decor_mut() = decor;
+	} else {
+		table.insert(key, Item::Value(value));
+	}
+	Ok(document.to_string())
+}
+
+/// The user's configuration file, with its speech rate set to `rate` and everything else left alone.
+/// # Errors
+/// If `config` is not valid TOML, or its `speech` key is not a table.
+pub fn config_with_rate(config: &str, rate: i8) -> OdiliaResult<String> {
+	config_with_setting(config, "speech", "rate", i64::from(rate).into())
+}
+
+/// Write `value` into the user's configuration file as `key` in the `section` table, so it is used again after a restart.
+async fn persist_setting(section: &str, key: &str, value: toml_edit::Value) -> OdiliaResult<()> {
+	let xdg_dirs = xdg::BaseDirectories::with_prefix("odilia")
+		.map_err(|e| OdiliaError::Generic(e.to_string()))?;
+	let path = xdg_dirs.find_config_file("config.toml").ok_or_else(|| {
+		OdiliaError::Generic("no configuration file to save to".to_string())
+	})?;
+	let config = fs::read_to_string(&path)
+		.await
+		.map_err(|e| OdiliaError::Generic(e.to_string()))?;
+	fs::write(&path, config_with_setting(&config, section, key, value)?)
+		.await
+		.map_err(|e| OdiliaError::Generic(e.to_string()))
+}
+
+/// Make speech faster (forward) or slower (backward) by the configured step, and say the new rate.
+#[tracing::instrument(level = "debug", skip(state), err)]
+pub async fn change_rate(state: &ScreenReaderState, direction: Direction) -> OdiliaResult<()> {
+	let rate = state.speech_rate.load(Ordering::Relaxed);
+	let new_rate = stepped_rate(rate, state.config.speech.rate_step, direction);
+	if new_rate != rate {
+		state.speech_rate.store(new_rate, Ordering::Relaxed);
+		state.set_rate(new_rate).await;
+	}
+	let announcement = match (new_rate == rate, direction) {
+		(true, Direction::Forward) => format!("Rate {new_rate}, fastest"),
+		(true, Direction::Backward) => format!("Rate {new_rate}, slowest"),
+		(false, _) => format!("Rate {new_rate}"),
+	};
+	state.say(Priority::Text, announcement).await;
+	if state.config.speech.persist_rate && new_rate != rate {
+		persist_setting("speech", "rate", i64::from(new_rate).into()).await?;
+	}
+	Ok(())
+}
+
+/// The granularity one step larger (forward) or smaller (backward) than `granularity`.
+/// Stepping past the largest or smallest granularity stays there.
+#[must_use]
+pub fn stepped_granularity(
+	granularity: CaretGranularity,
+	direction: Direction,
+) -> CaretGranularity {
+	let all = CaretGranularity::ALL;
+	let index = all.iter().position(|step| *step == granularity).unwrap_or_default();
+	let index = match direction {
+		Direction::Forward => (index + 1).min(all.len() - 1),
+		Direction::Backward => index.saturating_sub(1),
+	};
+	all[index]
+}
+
+/// Read a larger (forward) or smaller (backward) unit of text when the caret moves into a different text field, and say the new one.
+#[tracing::instrument(level = "debug", skip(state), err)]
+pub async fn change_granularity(
+	state: &ScreenReaderState,
+	direction: Direction,
+) -> OdiliaResult<()> {
+	let mut granularity = state.granularity.lock().await;
+	let current = CaretGranularity::from(*granularity);
+	let new = stepped_granularity(current, direction);
+	*granularity = new.into();
+	drop(granularity);
+	state.say(Priority::Text, new.as_str().to_string()).await;
+	if state.config.navigation.remember_granularity && new != current {
+		persist_setting("navigation", "default_granularity", new.as_str().into()).await?;
+	}
+	Ok(())
+}
+
+/// Save the focused item and caret position as a mark called `name`.
+#[tracing::instrument(level = "debug", skip(state))]
+pub async fn set_mark(state: &ScreenReaderState, name: &str) {
+	let Some(accessible) = state.history_item(0).await else {
+		state.say(Priority::Text, "Nothing to mark".to_string()).await;
+		return;
+	};
+	let caret_offset = state.previous_caret_position.load(Ordering::Relaxed);
+	state.marks.lock().await.set(name, Mark { accessible, caret_offset });
+	state.say(Priority::Text, format!("Mark {name} set")).await;
+}
+
+/// Focus the item saved as mark `name`, put the caret back where it was, then read the line there.
+#[tracing::instrument(level = "debug", skip(state), err)]
+pub async fn jump_to_mark(state: &ScreenReaderState, name: &str) -> OdiliaResult<()> {
+	let Some(mark) = state.marks.lock().await.get(name).cloned() else {
+		state.say(Priority::Text, format!("No mark named {name}")).await;
+		return Ok(());
+	};
+	let accessible = mark
+		.accessible
+		.clone()
+		.into_accessible(state.atspi.connection())
+		.await?;
+	let _: bool = accessible.to_component().await?.grab_focus().await?;
+	let caret_offset = i32::try_from(mark.caret_offset)?;
+	let _: bool = accessible.to_text().await?.set_caret_offset(caret_offset).await?;
+	state.update_accessible(mark.accessible.clone()).await;
+	let item = state.get_or_create_cache_item(mark.accessible).await?;
+	let (line, _, _) = item
+		.get_string_at_offset(mark.caret_offset, Granularity::Line)
+		.await?;
+	state.say(Priority::Text, line).await;
+	Ok(())
+}
+
+/// Speak the previous or next message in the message history, or say that the end of it was reached.
+#[tracing::instrument(level = "debug", skip(state))]
+pub async fn review_message(state: &ScreenReaderState, direction: Direction) {
+	let text = {
+		let mut history = state.message_history.lock().await;
+		let review = match direction {
+			Direction::Backward => history.review_previous(),
+			Direction::Forward => history.review_next(),
+		};
+		match review {
+			Review::Message(message) => message.to_string(),
+			Review::StartOfHistory => "start of history".to_string(),
+			Review::EndOfHistory => "end of history".to_string(),
+		}
+	};
+	// replaying must not record the message again, or the history would shift under the cursor
+	state.speak(Priority::Text, text).await;
+}
+
+/// A step through the accessibility tree, taken by the navigation cursor.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TreeMove {
+	Parent,
+	FirstChild,
+	NextSibling,
+	PreviousSibling,
+}
+
+/// The item the navigation cursor lands on after moving from `from`, or what to say when there is nowhere to go.
+pub fn tree_move(
+	cache: &Cache,
+	from: &AccessiblePrimitive,
+	movement: TreeMove,
+) -> Result<CacheItem, &'static str> {
+	let (target, boundary) = match movement {
+		TreeMove::Parent => (cache.parent_of(from), "no parent"),
+		TreeMove::FirstChild => (cache.first_child_of(from), "no children"),
+		TreeMove::NextSibling => (cache.next_sibling_of(from), "no next sibling"),
+		TreeMove::PreviousSibling => {
+			(cache.previous_sibling_of(from), "no previous sibling")
+		}
+	};
+	target.ok_or(boundary)
+}
+
+/// The relations of `accessible`, or none if the application can not be asked for them.
+async fn relations_of(
+	state: &ScreenReaderState,
+	accessible: &AccessiblePrimitive,
+) -> Vec<(RelationType, Vec<ObjectRef>)> {
+	let relations = match accessible.clone().into_accessible(state.atspi.connection()).await {
+		Ok(proxy) => proxy.get_relation_set().await,
+		Err(e) => Err(e),
+	};
+	relations.unwrap_or_else(|e| {
+		tracing::debug!(error = %e, "Could not get relations; assuming there are none");
+		Vec::new()
+	})
+}
+
+/// Like [`tree_move`], but when the cached tree ends, also cross into and out of trees embedded from other applications.
+/// The cache does not link an embedded tree to its embedder, only their [`RelationType::Embeds`] and [`RelationType::EmbeddedBy`] relations do.
+async fn tree_move_with_embeds(
+	state: &ScreenReaderState,
+	from: &AccessiblePrimitive,
+	movement: TreeMove,
+) -> OdiliaResult<Result<CacheItem, &'static str>> {
+	let boundary = match tree_move(&state.cache, from, movement) {
+		Ok(landed) => return Ok(Ok(landed)),
+		Err(boundary) => boundary,
+	};
+	let relations = relations_of(state, from).await;
+	let target = match movement {
+		TreeMove::Parent => embedder(&relations),
+		TreeMove::FirstChild => {
+			let item = state.get_or_create_cache_item(from.clone()).await?;
+			children_with_embeds(&item, &relations).into_iter().next()
+		}
+		TreeMove::NextSibling | TreeMove::PreviousSibling => {
+			// the root of an embedded tree is a sibling of its embedder's other children
+			let parent = embedder(&relations).or_else(|| {
+				state.cache.parent_of(from).map(|parent| parent.object)
+			});
+			let Some(parent) = parent else {
+				return Ok(Err(boundary));
+			};
+			let parent_relations = relations_of(state, &parent).await;
+			let parent = state.get_or_create_cache_item(parent).await?;
+			let siblings = children_with_embeds(&parent, &parent_relations);
+			let Some(position) = siblings.iter().position(|sibling| sibling == from)
+			else {
+				return Ok(Err(boundary));
+			};
+			let sibling_position = match movement {
+				TreeMove::NextSibling => Some(position + 1),
+				_ => position.checked_sub(1),
+			};
+			sibling_position.and_then(|position| siblings.get(position).cloned())
+		}
+	};
+	match target {
+		Some(target) => Ok(Ok(state.get_or_create_cache_item(target).await?)),
+		None => Ok(Err(boundary)),
+	}
+}
+
+/// Move the navigation cursor through the tree and read the item it lands on, without moving focus.
+#[tracing::instrument(level = "debug", skip(state), err)]
+pub async fn move_nav_cursor(state: &ScreenReaderState, movement: TreeMove) -> OdiliaResult<()> {
+	// copied out so the lock is not held while waiting on the history or on speech
+	let cursor = state.nav_cursor.lock().await.clone();
+	let from = match cursor {
+		Some(cursor) => cursor,
+		None => {
+			let Some(focused) = state.history_item(0).await else {
+				state.say(Priority::Text, "Nothing is focused".to_string()).await;
+				return Ok(());
+			};
+			focused
+		}
+	};
+	let landed = match tree_move_with_embeds(state, &from, movement).await? {
+		Ok(landed) => landed,
+		Err(boundary) => {
+			state.say(Priority::Text, boundary.to_string()).await;
+			return Ok(());
+		}
+	};
+	*state.nav_cursor.lock().await = Some(landed.object.clone());
+	let name = landed.name().await?;
+	let role_name = state.config.presentation.role_name(landed.role);
+	state.say(Priority::Text, format!("{name}, {role_name}")).await;
+	Ok(())
+}
+
+/// A link inside a run of text, like a paragraph with links in it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TextLink {
+	/// Where the link starts in the text, in characters.
+	pub start: usize,
+	/// Where the link ends in the text, in characters.
+	pub end: usize,
+	/// Where the link goes.
+	pub target: String,
+	/// The text of the link itself.
+	pub text: String,
+}
+
+/// The index of the link to move to from `current`, among `count` links.
+/// With no link read yet, forward starts at the first link and backward at the last.
+/// `None` once there are no more links in that direction.
+#[must_use]
+pub fn step_link(current: Option<usize>, count: usize, direction: Direction) -> Option<usize> {
+	let next = match (current, direction) {
+		(None, Direction::Forward) => Some(0),
+		(None, Direction::Backward) => count.checked_sub(1),
+		(Some(current), Direction::Forward) => current.checked_add(1),
+		(Some(current), Direction::Backward) => current.checked_sub(1),
+	};
+	next.filter(|next| *next < count)
+}
+
+/// Put together what is said for a link inside text, like "About us, link".
+/// A link without any text is read by where it goes instead.
+#[must_use]
+pub fn link_announcement(link: &TextLink) -> String {
+	let text = link.text.trim();
+	let text = if text.is_empty() { link.target.trim() } else { text };
+	format!("{text}, link")
+}
+
+/// The links inside an item's text, in the order they appear in it.
+async fn text_links(state: &ScreenReaderState, item: &CacheItem) -> OdiliaResult<Vec<TextLink>> {
+	let accessible = item.object.clone().into_accessible(state.atspi.connection()).await?;
+	let hypertext = accessible.to_hypertext().await?;
+	let mut links = Vec::new();
+	for index in 0..hypertext.get_nlinks().await? {
+		let link = AccessiblePrimitive::from(hypertext.get_link(index).await?)
+			.into_accessible(state.atspi.connection())
+			.await?;
+		let link = link.to_hyperlink().await?;
+		let start = usize::try_from(link.start_index().await?)?;
+		let end = usize::try_from(link.end_index().await?)?;
+		let target = link.get_uri(0).await.unwrap_or_default();
+		let text = item
+			.text
+			.chars()
+			.skip(start)
+			.take(end.saturating_sub(start))
+			.collect();
+		links.push(TextLink { start, end, target, text });
+	}
+	links.sort_by_key(|link| link.start);
+	Ok(links)
+}
+
+/// Move the caret to the next (forward) or previous (backward) link inside the focused text, and read it.
+#[tracing::instrument(level = "debug", skip(state), err)]
+pub async fn move_to_link(state: &ScreenReaderState, direction: Direction) -> OdiliaResult<()> {
+	let Some(focused) = state.history_item(0).await else {
+		state.say(Priority::Text, "Nothing is focused".to_string()).await;
+		return Ok(());
+	};
+	let item = state.get_or_create_cache_item(focused).await?;
+	if !item.interfaces.contains(Interface::Hypertext) {
+		state.say(Priority::Text, "No links".to_string()).await;
+		return Ok(());
+	}
+	let links = text_links(state, &item).await?;
+	if links.is_empty() {
+		state.say(Priority::Text, "No links".to_string()).await;
+		return Ok(());
+	}
+	// the cursor only carries over while the same text stays focused
+	let current = match &*state.link_cursor.lock().await {
+		Some((text, index)) if *text == item.object => Some(*index),
+		_ => None,
+	};
+	let Some(index) = step_link(current, links.len(), direction) else {
+		state.say(Priority::Text, "No more links".to_string()).await;
+		return Ok(());
+	};
+	*state.link_cursor.lock().await = Some((item.object.clone(), index));
+	let link = &links[index];
+	if let Err(e) = item.set_caret_offset(i32::try_from(link.start)?).await {
+		tracing::debug!(error = %e, "Could not move the caret to the link");
+	}
+	state.say(Priority::Text, link_announcement(link)).await;
+	Ok(())
+}
+
+/// Whether to read the row or the column of the focused table cell.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TableLine {
+	Row,
+	Column,
+}
+
+/// The content of one cell in a row or column, with the header it sits under, if it has one.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CellText {
+	pub header: Option<String>,
+	pub text: String,
+}
+
+/// Join the cells of a row or column into a single announcement, like "Name: Apple, Price: $5".
+/// Empty cells are read as "blank", so the position of the cells that follow is not lost.
+#[must_use]
+pub fn table_line_announcement(cells: &[CellText]) -> String {
+	cells.iter()
+		.map(|cell| {
+			let text = match cell.text.trim() {
+				"" => "blank",
+				text => text,
+			};
+			match cell.header.as_deref().map(str::trim) {
+				Some(header) if !header.is_empty() => format!("{header}: {text}"),
+				_ => text.to_string(),
+			}
+		})
+		.collect::<Vec<String>>()
+		.join(", ")
+}
+
+/// The text of a table cell or header, or `None` for the null object, which tables return for missing cells.
+async fn cell_text(state: &ScreenReaderState, cell: ObjectRef) -> OdiliaResult<Option<String>> {
+	if cell.path.as_str().ends_with("/null") {
+		return Ok(None);
+	}
+	let item = state.get_or_create_cache_item(cell.into()).await?;
+	if !item.text.trim().is_empty() {
+		return Ok(Some(item.text));
+	}
+	Ok(Some(item.name().await?))
+}
+
+/// Read every cell in the same row or column as the focused cell, paired with its header.
+#[tracing::instrument(level = "debug", skip(state), err)]
+pub async fn read_table_line(state: &ScreenReaderState, line: TableLine) -> OdiliaResult<()> {
+	let Some(focused) = state.history_item(0).await else {
+		state.say(Priority::Text, "Nothing is focused".to_string()).await;
+		return Ok(());
+	};
+	let focused = state.get_or_create_cache_item(focused).await?;
+	if !focused.interfaces.contains(Interface::TableCell) {
+		state.say(Priority::Text, "Not in a table".to_string()).await;
+		return Ok(());
+	}
+	let focused = focused.object.into_accessible(state.atspi.connection()).await?;
+	let cell = focused.to_table_cell().await?;
+	let (row, column) = cell.position().await?;
+	let table = AccessiblePrimitive::from(cell.table().await?)
+		.into_accessible(state.atspi.connection())
+		.await?;
+	let table = table.to_table().await?;
+	let length = match line {
+		TableLine::Row => table.ncolumns().await?,
+		TableLine::Column => table.nrows().await?,
+	};
+	let mut cells = Vec::new();
+	for index in 0..length {
+		// a cell in a row sits under its column's header, and a cell in a column next to its row's header
+		let (cell, header) = match line {
+			TableLine::Row => (
+				table.get_accessible_at(row, index).await?,
+				table.get_column_header(index).await?,
+			),
+			TableLine::Column => (
+				table.get_accessible_at(index, column).await?,
+				table.get_row_header(index).await?,
+			),
+		};
+		let Some(text) = cell_text(state, cell).await? else {
+			continue;
+		};
+		let header = cell_text(state, header).await?;
+		cells.push(CellText { header, text });
+	}
+	state.say(Priority::Text, table_line_announcement(&cells)).await;
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{
+		actions_announcement, bounds_announcement, config_with_rate, config_with_setting,
+		consumes_event, field_announcement, image_announcement, is_navigable_window,
+		landmark_announcement, link_announcement, newline_chunks, read_all_text,
+		reading_percentage, select_action, spatial_order, status_bar_announcement,
+		step_link, stepped_granularity, stepped_rate, table_line_announcement,
+		AccessibleField, ActionChoice, CellText, TextLink,
+	};
+	use crate::number_format::English;
+	use atspi_common::{InterfaceSet, Role, State, StateSet};
+	use odilia_cache::{AccessiblePrimitive, CacheItem};
+	use odilia_common::{
+		events::{Direction, ScreenReaderEvent},
+		settings::{
+			navigation::CaretGranularity, presentation::CoordinateUnit,
+			speech::NewlineHandling,
+		},
+	};
+	use std::sync::Weak;
+
+	fn item(id: &str, role: Role, text: &str) -> CacheItem {
+		let prim = |id: &str| AccessiblePrimitive {
+			id: id.to_string(),
+			sender: ":1.2".into(),
+		};
+		CacheItem {
+			object: prim(id),
+			app: prim("/org/a11y/atspi/accessible/root"),
+			parent: prim("/org/a11y/atspi/accessible/root").into(),
+			index: None,
+			children_num: Some(0),
+			interfaces: InterfaceSet::empty(),
+			role,
+			states: StateSet::empty(),
+			text: text.to_string(),
+			children: Vec::new(),
+			cache: Weak::new(),
+		}
+	}
+
+	#[test]
+	fn most_recent_item_is_read_first() {
+		let items = vec![
+			item("/org/a11y/atspi/accessible/12", Role::StatusBar, "Ready"),
+			item("/org/a11y/atspi/accessible/130", Role::Notification, "New mail"),
+			item("/org/a11y/atspi/accessible/root", Role::StatusBar, "Old"),
+		];
+		assert_eq!(
+			status_bar_announcement(items),
+			"notification, New mail. status bar, Ready. status bar, Old"
+		);
+	}
+	#[test]
+	fn empty_items_are_skipped() {
+		let items = vec![
+			item("/org/a11y/atspi/accessible/1", Role::StatusBar, "  "),
+			item("/org/a11y/atspi/accessible/2", Role::StatusBar, "Saved\n"),
+		];
+		assert_eq!(status_bar_announcement(items), "status bar, Saved");
+	}
+	#[test]
+	fn nothing_to_read() {
+		assert_eq!(status_bar_announcement(Vec::new()), "No status bar or notifications");
+	}
+	#[test]
+	fn the_requested_field_is_spoken() {
+		assert_eq!(field_announcement(AccessibleField::Name, "OK"), "OK");
+		assert_eq!(
+			field_announcement(AccessibleField::Description, " Saves the file "),
+			"Saves the file"
+		);
+	}
+	#[test]
+	fn empty_fields_are_announced() {
+		assert_eq!(field_announcement(AccessibleField::Name, ""), "no name");
+		assert_eq!(
+			field_announcement(AccessibleField::Description, "  "),
+			"no description"
+		);
+	}
+	fn cell(header: Option<&str>, text: &str) -> CellText {
+		CellText { header: header.map(str::to_string), text: text.to_string() }
+	}
+	#[test]
+	fn cells_are_read_with_their_headers() {
+		let row = [
+			cell(Some("Name"), "Apple"),
+			cell(Some("Price"), "$5"),
+			cell(Some("Stock"), "12"),
+		];
+		assert_eq!(table_line_announcement(&row), "Name: Apple, Price: $5, Stock: 12");
+	}
+	#[test]
+	fn cells_without_headers_or_text() {
+		let row = [cell(None, "Apple"), cell(Some(" "), "$5"), cell(Some("Stock"), "")];
+		assert_eq!(table_line_announcement(&row), "Apple, $5, Stock: blank");
+		assert_eq!(table_line_announcement(&[]), "");
+	}
+	#[test]
+	fn images_are_labeled_with_their_descriptions() {
+		assert_eq!(image_announcement("a cat sleeping", ""), "image: a cat sleeping");
+		assert_eq!(image_announcement(" ", "a cat sleeping"), "image: a cat sleeping");
+		assert_eq!(image_announcement("a cat", "a cat"), "image: a cat");
+		assert_eq!(image_announcement("a cat", "on a sofa"), "image: a cat, on a sofa");
+		assert_eq!(image_announcement("", " "), "image, no description");
+	}
+	#[test]
+	fn bounds_are_read_in_pixels_or_percentages() {
+		let bounds = (192, 108, 960, 54);
+		let screen = Some((1920, 1080));
+		assert_eq!(
+			bounds_announcement(&English, CoordinateUnit::Pixels, bounds, screen),
+			"at 192, 108, size 960 by 54"
+		);
+		assert_eq!(
+			bounds_announcement(
+				&English,
+				CoordinateUnit::PercentOfScreen,
+				bounds,
+				screen
+			),
+			"at 10 percent, 10 percent, size 50 percent by 5 percent"
+		);
+	}
+	#[test]
+	fn bounds_fall_back_to_pixels_without_a_screen_size() {
+		let bounds = (10, 20, 30, 40);
+		let pixels = "at 10, 20, size 30 by 40";
+		let percent = CoordinateUnit::PercentOfScreen;
+		assert_eq!(bounds_announcement(&English, percent, bounds, None), pixels);
+		assert_eq!(bounds_announcement(&English, percent, bounds, Some((0, 0))), pixels);
+	}
+	fn actions() -> Vec<String> {
+		vec!["click".to_string(), "press".to_string(), "Activate".to_string()]
+	}
+	#[test]
+	fn actions_are_chosen_by_default_position_or_name() {
+		let actions = actions();
+		assert_eq!(select_action(&actions, ActionChoice::Default), Some(0));
+		assert_eq!(select_action(&actions, ActionChoice::Position(2)), Some(1));
+		assert_eq!(select_action(&actions, ActionChoice::Name("activate")), Some(2));
+	}
+	#[test]
+	fn missing_actions_are_not_chosen() {
+		let actions = actions();
+		assert_eq!(select_action(&actions, ActionChoice::Position(0)), None);
+		assert_eq!(select_action(&actions, ActionChoice::Position(4)), None);
+		assert_eq!(select_action(&actions, ActionChoice::Name("expand")), None);
+		assert_eq!(select_action(&[], ActionChoice::Default), None);
+	}
+	#[test]
+	fn actions_are_listed_by_position() {
+		assert_eq!(actions_announcement(&actions()), "1: click, 2: press, 3: Activate");
+		assert_eq!(actions_announcement(&[]), "No actions");
+	}
+	#[test]
+	fn read_all_prefers_live_then_cached_text_then_name() {
+		let paragraph = "The whole paragraph, not just the line the caret is on.";
+		assert_eq!(read_all_text(paragraph, None, "Intro"), paragraph);
+		assert_eq!(read_all_text("The whole", Some(paragraph), "Intro"), paragraph);
+		assert_eq!(read_all_text("", None, "OK"), "OK");
+		assert_eq!(read_all_text(" ", Some(""), ""), "blank");
+	}
+	#[test]
+	fn newline_handling_splits_multiline_text() {
+		let text = "Dear Sam,\n\nThanks for the notes.\nSee you soon.\n\n\n";
+		assert_eq!(
+			newline_chunks(text, &NewlineHandling::Pause),
+			["Dear Sam,", "Thanks for the notes.", "See you soon."]
+		);
+		let word = NewlineHandling::Word("new line".into());
+		assert_eq!(
+			newline_chunks(text, &word),
+			[
+				"Dear Sam,",
+				"new line",
+				"Thanks for the notes.",
+				"See you soon.",
+				"new line"
+			]
+		);
+		assert_eq!(
+			newline_chunks(text, &NewlineHandling::Ignore),
+			["Dear Sam, Thanks for the notes. See you soon."]
+		);
+		// a single line break at the end only finishes the line
+		assert_eq!(newline_chunks("One line\n", &word), ["One line"]);
+		assert_eq!(newline_chunks("\r\n  \nTitle", &word), ["new line", "Title"]);
+		assert!(newline_chunks("\n\n", &NewlineHandling::Ignore).is_empty());
+	}
+	#[test]
+	fn link_steps_start_at_either_end_and_stop_there() {
+		assert_eq!(step_link(None, 3, Direction::Forward), Some(0));
+		assert_eq!(step_link(None, 3, Direction::Backward), Some(2));
+		assert_eq!(step_link(Some(0), 3, Direction::Forward), Some(1));
+		assert_eq!(step_link(Some(2), 3, Direction::Forward), None);
+		assert_eq!(step_link(Some(1), 3, Direction::Backward), Some(0));
+		assert_eq!(step_link(Some(0), 3, Direction::Backward), None);
+		assert_eq!(step_link(None, 0, Direction::Forward), None);
+		assert_eq!(step_link(None, 0, Direction::Backward), None);
+		// a cursor left over from a longer list of links starts over inside the new one
+		assert_eq!(step_link(Some(5), 3, Direction::Forward), None);
+	}
+	#[test]
+	fn links_are_read_by_text_or_target() {
+		let mut link = TextLink {
+			start: 10,
+			end: 18,
+			target: "https://odilia.app/about".to_string(),
+			text: "About us".to_string(),
+		};
+		assert_eq!(link_announcement(&link), "About us, link");
+		link.text = " ".to_string();
+		assert_eq!(link_announcement(&link), "https://odilia.app/about, link");
+	}
+	#[test]
+	fn rate_steps_stop_at_the_ends_of_the_range() {
+		assert_eq!(stepped_rate(50, 10, Direction::Forward), 60);
+		assert_eq!(stepped_rate(50, 10, Direction::Backward), 40);
+		assert_eq!(stepped_rate(95, 10, Direction::Forward), 100);
+		assert_eq!(stepped_rate(100, 10, Direction::Forward), 100);
+		assert_eq!(stepped_rate(-95, 10, Direction::Backward), -100);
+		assert_eq!(stepped_rate(-100, 10, Direction::Backward), -100);
+		assert_eq!(stepped_rate(0, 255, Direction::Forward), 100);
+		assert_eq!(stepped_rate(0, 255, Direction::Backward), -100);
+	}
+	#[test]
+	fn persisting_the_rate_keeps_the_rest_of_the_config() {
+		let config = "[speech]\nrate = 50\nperson = \"Max\"\n\n[log]\nlevel = \"debug\"\n";
+		let saved: toml::Table = config_with_rate(config, 70).unwrap().parse().unwrap();
+		assert_eq!(saved["speech"]["rate"].as_integer(), Some(70));
+		assert_eq!(saved["speech"]["person"].as_str(), Some("Max"));
+		assert_eq!(saved["log"]["level"].as_str(), Some("debug"));
+		let saved: toml::Table = config_with_rate("", -20).unwrap().parse().unwrap();
+		assert_eq!(saved["speech"]["rate"].as_integer(), Some(-20));
+		assert!(config_with_rate("speech = 3", 10).is_err());
+	}
+	#[test]
+	fn persisting_a_setting_keeps_comments() {
+		let config = "# my settings\n[speech]\n# how fast\nrate = 50 # quite fast\nperson = \"Max\"\n";
+		assert_eq!(
+			config_with_rate(config, 70).unwrap(),
+			"# my settings\n[speech]\n# how fast\nrate = 70 # quite fast\nperson = \"Max\"\n"
+		);
+		let inline: toml::Table = config_with_rate("speech = { person = \"Max\" }\n", 70)
+			.unwrap()
+			.parse()
+			.unwrap();
+		assert_eq!(inline["speech"]["rate"].as_integer(), Some(70));
+		assert_eq!(inline["speech"]["person"].as_str(), Some("Max"));
+	}
+	#[test]
+	fn structural_navigation_is_only_left_alone_while_off() {
+		let next_heading =
+			ScreenReaderEvent::StructuralNavigation(Direction::Forward, Role::Heading);
+		assert!(consumes_event(&next_heading, true));
+		assert!(!consumes_event(&next_heading, false));
+		for other in [
+			ScreenReaderEvent::ToggleStructuralNavigation,
+			ScreenReaderEvent::StopSpeech,
+			ScreenReaderEvent::MoveToLink(Direction::Forward),
+		] {
+			assert!(consumes_event(&other, true));
+			assert!(consumes_event(&other, false));
+		}
+	}
+	#[test]
+	fn landmarks_are_read_by_kind_and_label() {
+		assert_eq!(
+			landmark_announcement(Some("navigation"), "Main menu"),
+			"navigation region, Main menu"
+		);
+		assert_eq!(landmark_announcement(Some("main"), " "), "main region");
+		assert_eq!(landmark_announcement(Some("region"), "Comments"), "region, Comments");
+		assert_eq!(landmark_announcement(None, "Sidebar"), "landmark, Sidebar");
+	}
+	#[test]
+	fn minimised_and_off_screen_windows_are_not_navigable() {
+		let shown = StateSet::new(State::Showing | State::Visible);
+		let screen = Some((1920, 1080));
+		assert!(is_navigable_window(&shown, (0, 0, 800, 600), screen));
+		assert!(is_navigable_window(&shown, (-400, 100, 800, 600), screen));
+		assert!(!is_navigable_window(
+			&StateSet::new(State::Showing | State::Iconified),
+			(0, 0, 800, 600),
+			screen
+		));
+		assert!(!is_navigable_window(&shown, (0, 0, 0, 0), screen));
+		assert!(!is_navigable_window(&shown, (-32000, -32000, 800, 600), screen));
+		assert!(!is_navigable_window(&shown, (1920, 0, 800, 600), screen));
+		assert!(is_navigable_window(&shown, (1920, 0, 800, 600), None));
+	}
+	#[test]
+	fn granularity_steps_stop_at_the_smallest_and_largest() {
+		assert_eq!(
+			stepped_granularity(CaretGranularity::Line, Direction::Forward),
+			CaretGranularity::Paragraph
+		);
+		assert_eq!(
+			stepped_granularity(CaretGranularity::Line, Direction::Backward),
+			CaretGranularity::Sentence
+		);
+		assert_eq!(
+			stepped_granularity(CaretGranularity::Paragraph, Direction::Forward),
+			CaretGranularity::Paragraph
+		);
+		assert_eq!(
+			stepped_granularity(CaretGranularity::Char, Direction::Backward),
+			CaretGranularity::Char
+		);
+		let config = "[navigation]\nskip_hidden = false\n";
+		let saved: toml::Table = config_with_setting(
+			config,
+			"navigation",
+			"default_granularity",
+			"word".into(),
+		)
+		.unwrap()
+		.parse()
+		.unwrap();
+		assert_eq!(saved["navigation"]["default_granularity"].as_str(), Some("word"));
+		assert_eq!(saved["navigation"]["skip_hidden"].as_bool(), Some(false));
+	}
+	#[test]
+	fn reading_percentage_counts_the_item_itself() {
+		// a flattened document of a heading, two paragraphs and a link, weighed by characters
+		let weights = [10, 40, 0, 50];
+		assert_eq!(reading_percentage(&weights, 0), Some(10.0));
+		assert_eq!(reading_percentage(&weights, 1), Some(50.0));
+		assert_eq!(reading_percentage(&weights, 2), Some(50.0));
+		assert_eq!(reading_percentage(&weights, 3), Some(100.0));
+		assert_eq!(reading_percentage(&[1, 1, 1], 0).map(f64::round), Some(33.0));
+		assert_eq!(reading_percentage(&weights, 4), None);
+		assert_eq!(reading_percentage(&[0, 0], 1), None);
+		assert_eq!(reading_percentage(&[], 0), None);
+	}
+	#[test]
+	fn spatial_order_reads_lines_top_to_bottom_and_left_to_right() {
+		// a two by two grid, listed by the application column by column
+		let items = vec![
+			("top left", (0, 0, 100, 20)),
+			("bottom left", (0, 30, 100, 20)),
+			("top right", (120, 0, 100, 20)),
+			("bottom right", (120, 30, 100, 20)),
+		];
+		assert_eq!(
+			spatial_order(items),
+			["top left", "top right", "bottom left", "bottom right"]
+		);
+	}
+	#[test]
+	fn spatial_order_keeps_slightly_misaligned_items_on_one_line() {
+		let items = vec![
+			("label", (0, 4, 50, 12)),
+			("field", (60, 0, 200, 20)),
+			("button", (270, 2, 60, 16)),
+			("next line", (0, 25, 50, 12)),
+		];
+		assert_eq!(spatial_order(items), ["label", "field", "button", "next line"]);
+		let flat = vec![("b", (10, 5, 10, 0)), ("a", (0, 5, 10, 0))];
+		assert_eq!(spatial_order(flat), ["a", "b"]);
+		assert!(spatial_order::<&str>(Vec::new()).is_empty());
+	}
+}
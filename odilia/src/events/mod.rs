@@ -1,8 +1,15 @@
 mod cache;
+mod commands;
 mod document;
+mod mouse;
 mod object;
 
-use std::sync::Arc;
+pub use cache::{prune_dead_applications, warm_cache};
+
+use std::{
+	sync::{atomic::Ordering, Arc},
+	time::{Duration, Instant},
+};
 
 use futures::stream::StreamExt;
 use tokio::sync::mpsc::{Receiver, Sender};
@@ -11,14 +18,24 @@ use tokio_util::sync::CancellationToken;
 use crate::state::ScreenReaderState;
 use atspi_common::events::Event;
 use atspi_common::{Role, ScrollType};
-use odilia_cache::AccessibleExt;
+use commands::{AccessibleField, ActionChoice, TableLine, TreeMove};
 use odilia_cache::Convertable;
+use odilia_cache::{landmark_type, AccessibleExt, AccessiblePrimitive};
 use odilia_common::{
 	events::{Direction, ScreenReaderEvent},
 	result::OdiliaResult,
 };
 use ssip_client_async::Priority;
 
+/// The modal dialog which structural navigation should stay inside of, if any.
+async fn contained_modal(state: &ScreenReaderState) -> Option<AccessiblePrimitive> {
+	// without a cache the dialog's descendants can not be found, so containment would block all navigation
+	if !state.config.navigation.contain_to_modal || !state.cache.caching_enabled() {
+		return None;
+	}
+	state.active_modal.lock().await.clone()
+}
+
 #[tracing::instrument(level = "debug", skip_all, ret, err)]
 pub async fn structural_navigation(
 	state: &ScreenReaderState,
@@ -30,13 +47,37 @@ pub async fn structural_navigation(
 		Some(acc) => acc.into_accessible(state.atspi.connection()).await?,
 		None => return Ok(false),
 	};
-	if let Some(next) = curr.get_next(role, dir == Direction::Backward).await? {
+	let visible_only = state.config.navigation.skip_hidden;
+	let next = curr.get_next(role, dir == Direction::Backward, visible_only).await?;
+	let next = if visible_only && commands::WINDOW_ROLES.contains(&role) {
+		commands::skip_hidden_windows(state, next, role, dir).await?
+	} else {
+		next
+	};
+	let next = match (next, contained_modal(state).await) {
+		(Some(next), Some(modal)) => {
+			let next_prim = AccessiblePrimitive::try_from(&next)?;
+			state.get_or_create_cache_item(next_prim.clone()).await?;
+			state.cache.is_within(&next_prim, &modal).then_some(next)
+		}
+		(next, _) => next,
+	};
+	if let Some(next) = next {
 		let comp = next.to_component().await?;
-		let texti = next.to_text().await?;
 		let curr_prim = curr.try_into()?;
 		let _: bool = comp.grab_focus().await?;
 		comp.scroll_to(ScrollType::TopLeft).await?;
 		state.update_accessible(curr_prim).await;
+		if role == Role::Landmark {
+			// landmarks hold other items rather than text of their own, so they are read by kind and label instead
+			let (attributes, label) =
+				tokio::try_join!(next.get_attributes(), next.name())?;
+			let text =
+				commands::landmark_announcement(landmark_type(&attributes), &label);
+			state.say(Priority::Text, text).await;
+			return Ok(true);
+		}
+		let texti = next.to_text().await?;
 		let _: bool = texti.set_caret_offset(0).await?;
 		let role = next.get_role().await?;
 		let len = texti.character_count().await?;
@@ -60,7 +101,15 @@ pub async fn sr_event(
 		tokio::select! {
 		    sr_event = sr_events.recv() => {
 			tracing::debug!("SR Event received");
+			let navigation_on = state.structural_navigation.load(Ordering::Relaxed);
+			if sr_event.as_ref().is_some_and(|event| !commands::consumes_event(event, navigation_on)) {
+				tracing::debug!("Structural navigation is off; leaving the event alone.");
+				continue;
+			}
 			match sr_event {
+			    Some(ScreenReaderEvent::ToggleStructuralNavigation) => {
+						commands::toggle_structural_navigation(&state).await;
+			    }
 			    Some(ScreenReaderEvent::StructuralNavigation(dir, role)) => {
 				 if let Err(e) = structural_navigation(&state, dir, role).await {
 				    tracing::debug!(error = %e, "There was an error with the structural navigation call.");
@@ -77,6 +126,140 @@ pub async fn sr_event(
 						let mut sr_mode = state.mode.lock().await;
 						*sr_mode = new_sr_mode;
 			    }
+			    Some(ScreenReaderEvent::ReadStatusBar) => {
+						commands::read_status_bar(&state).await;
+			    }
+			    Some(ScreenReaderEvent::ReadNameOnly) => {
+						if let Err(e) = commands::read_focused_field(&state, AccessibleField::Name).await {
+							tracing::debug!(error = %e, "Could not read the name of the focused item.");
+						}
+			    }
+			    Some(ScreenReaderEvent::SetMark(name)) => {
+						commands::set_mark(&state, &name).await;
+			    }
+			    Some(ScreenReaderEvent::JumpToMark(name)) => {
+						if let Err(e) = commands::jump_to_mark(&state, &name).await {
+							tracing::debug!(error = %e, "Could not jump to mark.");
+						}
+			    }
+			    Some(ScreenReaderEvent::ReviewPreviousMessage) => {
+						commands::review_message(&state, Direction::Backward).await;
+			    }
+			    Some(ScreenReaderEvent::ReviewNextMessage) => {
+						commands::review_message(&state, Direction::Forward).await;
+			    }
+			    Some(ScreenReaderEvent::MoveToParent) => {
+						if let Err(e) = commands::move_nav_cursor(&state, TreeMove::Parent).await {
+							tracing::debug!(error = %e, "Could not move to the parent.");
+						}
+			    }
+			    Some(ScreenReaderEvent::MoveToFirstChild) => {
+						if let Err(e) = commands::move_nav_cursor(&state, TreeMove::FirstChild).await {
+							tracing::debug!(error = %e, "Could not move to the first child.");
+						}
+			    }
+			    Some(ScreenReaderEvent::MoveToNextSibling) => {
+						if let Err(e) = commands::move_nav_cursor(&state, TreeMove::NextSibling).await {
+							tracing::debug!(error = %e, "Could not move to the next sibling.");
+						}
+			    }
+			    Some(ScreenReaderEvent::MoveToPreviousSibling) => {
+						if let Err(e) = commands::move_nav_cursor(&state, TreeMove::PreviousSibling).await {
+							tracing::debug!(error = %e, "Could not move to the previous sibling.");
+						}
+			    }
+			    Some(ScreenReaderEvent::ReadRow) => {
+						if let Err(e) = commands::read_table_line(&state, TableLine::Row).await {
+							tracing::debug!(error = %e, "Could not read the table row.");
+						}
+			    }
+			    Some(ScreenReaderEvent::ReadColumn) => {
+						if let Err(e) = commands::read_table_line(&state, TableLine::Column).await {
+							tracing::debug!(error = %e, "Could not read the table column.");
+						}
+			    }
+			    Some(ScreenReaderEvent::DescribeImage) => {
+						if let Err(e) = commands::describe_image(&state).await {
+							tracing::debug!(error = %e, "Could not describe the image.");
+						}
+			    }
+			    Some(ScreenReaderEvent::VerboseNext) => {
+							commands::verbose_next(&state).await;
+			    }
+			    Some(ScreenReaderEvent::NextUtteranceImportant) => {
+							commands::important_next(&state).await;
+			    }
+			    Some(ScreenReaderEvent::ReadBounds) => {
+						if let Err(e) = commands::read_bounds(&state).await {
+							tracing::debug!(error = %e, "Could not read the bounds of the focused item.");
+						}
+			    }
+			    Some(ScreenReaderEvent::DoDefaultAction) => {
+						if let Err(e) = commands::do_action(&state, ActionChoice::Default).await {
+							tracing::debug!(error = %e, "Could not perform the default action.");
+						}
+			    }
+			    Some(ScreenReaderEvent::ListActions) => {
+						if let Err(e) = commands::list_actions(&state).await {
+							tracing::debug!(error = %e, "Could not list the actions.");
+						}
+			    }
+			    Some(ScreenReaderEvent::DoAction(position)) => {
+						if let Err(e) = commands::do_action(&state, ActionChoice::Position(position)).await {
+							tracing::debug!(error = %e, "Could not perform the action.");
+						}
+			    }
+			    Some(ScreenReaderEvent::DoNamedAction(name)) => {
+						if let Err(e) = commands::do_action(&state, ActionChoice::Name(&name)).await {
+							tracing::debug!(error = %e, "Could not perform the action.");
+						}
+			    }
+			    Some(ScreenReaderEvent::ReadAll) => {
+						if let Err(e) = commands::read_all(&state).await {
+							tracing::debug!(error = %e, "Could not read the text of the focused item.");
+						}
+			    }
+			    Some(ScreenReaderEvent::IncreaseRate) => {
+						if let Err(e) = commands::change_rate(&state, Direction::Forward).await {
+							tracing::debug!(error = %e, "Could not increase the speech rate.");
+						}
+			    }
+			    Some(ScreenReaderEvent::DecreaseRate) => {
+						if let Err(e) = commands::change_rate(&state, Direction::Backward).await {
+							tracing::debug!(error = %e, "Could not decrease the speech rate.");
+						}
+			    }
+			    Some(ScreenReaderEvent::ToggleSpeakCaretContext) => {
+						commands::toggle_caret_context(&state).await;
+			    }
+			    Some(ScreenReaderEvent::ToggleDisplayText) => {
+						commands::toggle_display_text(&state).await;
+			    }
+			    Some(ScreenReaderEvent::ReadDocumentPosition) => {
+						if let Err(e) = commands::read_document_position(&state).await {
+							tracing::debug!(error = %e, "Could not read the position in the document.");
+						}
+			    }
+			    Some(ScreenReaderEvent::ReportObjectInfo) => {
+						if let Err(e) = commands::report_object_info(&state).await {
+							tracing::debug!(error = %e, "Could not report the focused object.");
+						}
+			    }
+			    Some(ScreenReaderEvent::ChangeGranularity(direction)) => {
+						if let Err(e) = commands::change_granularity(&state, direction).await {
+							tracing::debug!(error = %e, "Could not change the granularity.");
+						}
+			    }
+			    Some(ScreenReaderEvent::MoveToLink(direction)) => {
+						if let Err(e) = commands::move_to_link(&state, direction).await {
+							tracing::debug!(error = %e, "Could not move to the link.");
+						}
+			    }
+			    Some(ScreenReaderEvent::ReadDescriptionOnly) => {
+						if let Err(e) = commands::read_focused_field(&state, AccessibleField::Description).await {
+							tracing::debug!(error = %e, "Could not read the description of the focused item.");
+						}
+			    }
 			    _ => { continue; }
 			};
 			continue;
@@ -148,10 +331,37 @@ pub async fn process(
 	}
 }
 
+/// Handle the added and removed children gathered up by `ChildrenChanged` events, whenever the oldest of them has waited for the configured window.
+#[tracing::instrument(level = "debug", skip_all)]
+pub async fn flush_children_changes(state: Arc<ScreenReaderState>, shutdown: CancellationToken) {
+	let window = Duration::from_millis(state.config.cache.children_changed_window_ms);
+	let mut interval = tokio::time::interval(window.max(Duration::from_millis(1)));
+	loop {
+		tokio::select! {
+			_ = interval.tick() => {
+				let mut batch = state.children_batch.lock().await;
+				if !batch.is_due(Instant::now()) {
+					continue;
+				}
+				let changes = batch.take();
+				drop(batch);
+				if let Err(e) = object::children_changed::apply(&state, changes).await {
+					tracing::error!(error = format_args!("{e:#}"), "Could not handle a batch of changed children");
+				}
+			}
+			() = shutdown.cancelled() => {
+				tracing::debug!("Shutting down the changed children task.");
+				break;
+			}
+		}
+	}
+}
+
 #[tracing::instrument(level = "debug", skip(state))]
 async fn dispatch_wrapper(state: Arc<ScreenReaderState>, good_event: Event) {
+	let _running = state.handlers_in_flight.start();
 	if let Err(e) = dispatch(&state, good_event).await {
-		tracing::error!(error = %e, "Could not handle event");
+		tracing::error!(error = format_args!("{e:#}"), "Could not handle event");
 	} else {
 		tracing::debug!("Event handled without error");
 	}
@@ -168,6 +378,7 @@ async fn dispatch(state: &ScreenReaderState, event: Event) -> eyre::Result<()> {
 			document::dispatch(state, document_event).await?;
 		}
 		Event::Cache(cache_event) => cache::dispatch(state, cache_event).await?,
+		Event::Mouse(mouse_event) => mouse::dispatch(state, mouse_event).await?,
 		other_event => {
 			tracing::debug!(
 				"Ignoring event with unknown interface: {:#?}",
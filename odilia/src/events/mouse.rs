@@ -0,0 +1,158 @@
+use crate::state::ScreenReaderState;
+use atspi_common::{
+	events::mouse::{AbsEvent, MouseEvents},
+	CoordType,
+};
+use odilia_cache::{AccessiblePrimitive, Convertable};
+use odilia_common::errors::OdiliaError;
+use ssip_client_async::Priority;
+use std::time::{Duration, Instant};
+
+use super::cache::desktop_apps;
+use super::object::state_changed::speak_accessible;
+
+/// How long the pointer has to rest before what is under it is read, so moving the mouse does not flood the bus with hit tests.
+pub const MOUSE_REVIEW_INTERVAL: Duration = Duration::from_millis(150);
+
+/// How many levels to descend into when looking for the deepest item under the pointer.
+const MAX_HIT_TEST_DEPTH: usize = 32;
+
+/// Whether the item under the pointer should be read; it is not read again while the pointer stays over it.
+#[must_use]
+pub fn mouse_item_changed(
+	last: Option<&AccessiblePrimitive>,
+	under_pointer: &AccessiblePrimitive,
+) -> bool {
+	last != Some(under_pointer)
+}
+
+/// `apps` with the one which has focus, sent from `focused_sender`, moved to the front, so its windows win where windows overlap.
+#[must_use]
+pub fn focused_app_first(
+	mut apps: Vec<AccessiblePrimitive>,
+	focused_sender: Option<&str>,
+) -> Vec<AccessiblePrimitive> {
+	apps.sort_by_key(|app| Some(app.sender.as_str()) != focused_sender);
+	apps
+}
+
+/// Find the deepest item under the point, in the windows of every application on the desktop.
+async fn accessible_at_point(
+	state: &ScreenReaderState,
+	x: i32,
+	y: i32,
+) -> Result<Option<AccessiblePrimitive>, OdiliaError> {
+	let focused = state.history_item(0).await;
+	let apps = focused_app_first(
+		desktop_apps(state).await?,
+		focused.as_ref().map(|focused| focused.sender.as_str()),
+	);
+	for app in apps {
+		let app = app.into_accessible(state.atspi.connection()).await?;
+		for window in app.get_children().await? {
+			let mut current: AccessiblePrimitive = window.into();
+			let mut found = false;
+			for _ in 0..MAX_HIT_TEST_DEPTH {
+				let accessible = current
+					.clone()
+					.into_accessible(state.atspi.connection())
+					.await?;
+				let Ok(component) = accessible.to_component().await else {
+					break;
+				};
+				let child = component
+					.get_accessible_at_point(x, y, CoordType::Screen)
+					.await?;
+				// the null object means nothing inside of `current` is at that point
+				if child.path.as_str().ends_with("/null") {
+					break;
+				}
+				current = child.into();
+				found = true;
+			}
+			if found {
+				return Ok(Some(current));
+			}
+		}
+	}
+	Ok(None)
+}
+
+#[tracing::instrument(level = "debug", skip(state), ret, err)]
+pub async fn moved(state: &ScreenReaderState, event: &AbsEvent) -> Result<(), OdiliaError> {
+	let now = Instant::now();
+	state.mouse_review.lock().await.moved_at = Some(now);
+	tokio::time::sleep(MOUSE_REVIEW_INTERVAL).await;
+	// the pointer moved again while waiting; that move is read instead, once the pointer rests
+	if state.mouse_review.lock().await.moved_at != Some(now) {
+		return Ok(());
+	}
+	let Some(under_pointer) = accessible_at_point(state, event.x, event.y).await? else {
+		return Ok(());
+	};
+	{
+		let mut review = state.mouse_review.lock().await;
+		if !mouse_item_changed(review.item.as_ref(), &under_pointer) {
+			return Ok(());
+		}
+		review.item = Some(under_pointer.clone());
+	}
+	// the review cursor follows the pointer, so tree navigation carries on from what was just read
+	*state.nav_cursor.lock().await = Some(under_pointer.clone());
+	let accessible = state.get_or_create_cache_item(under_pointer).await?;
+	state.stop_speech().await;
+	if let Err(e) = speak_accessible(state, &accessible, None).await {
+		tracing::debug!(error = %e, "Could not read the item under the pointer");
+		state.say(Priority::Text, accessible.text).await;
+	}
+	Ok(())
+}
+
+#[tracing::instrument(level = "debug", skip(state), ret, err)]
+pub async fn dispatch(state: &ScreenReaderState, event: &MouseEvents) -> eyre::Result<()> {
+	// Dispatch based on member
+	match event {
+		MouseEvents::Abs(abs_event) if state.config.announcements.mouse_review => {
+			moved(state, abs_event).await?;
+		}
+		other_member => {
+			tracing::debug!("Ignoring event with unknown member: {:#?}", other_member);
+		}
+	}
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{focused_app_first, mouse_item_changed};
+	use odilia_cache::AccessiblePrimitive;
+
+	fn prim(id: &str) -> AccessiblePrimitive {
+		AccessiblePrimitive { id: id.to_string(), sender: ":1.2".into() }
+	}
+
+	#[test]
+	fn the_focused_application_is_hit_tested_first() {
+		let app = |sender: &str| AccessiblePrimitive {
+			id: "/org/a11y/atspi/accessible/root".to_string(),
+			sender: sender.into(),
+		};
+		let apps = vec![app(":1.2"), app(":1.5"), app(":1.9")];
+		let senders = |apps: Vec<AccessiblePrimitive>| -> Vec<String> {
+			apps.into_iter().map(|app| app.sender.to_string()).collect()
+		};
+		assert_eq!(
+			senders(focused_app_first(apps.clone(), Some(":1.5"))),
+			[":1.5", ":1.2", ":1.9"]
+		);
+		assert_eq!(senders(focused_app_first(apps, None)), [":1.2", ":1.5", ":1.9"]);
+	}
+	#[test]
+	fn items_are_not_reread_while_the_pointer_stays_on_them() {
+		let button = prim("/org/a11y/atspi/accessible/1");
+		let label = prim("/org/a11y/atspi/accessible/2");
+		assert!(mouse_item_changed(None, &button));
+		assert!(!mouse_item_changed(Some(&button), &button));
+		assert!(mouse_item_changed(Some(&button), &label));
+	}
+}
@@ -1,3 +1,4 @@
+use crate::events::commands::TableLine;
 use crate::state::ScreenReaderState;
 use atspi_common::events::object::ObjectEvents;
 
@@ -17,6 +18,32 @@ pub async fn dispatch(state: &ScreenReaderState, event: &ObjectEvents) -> eyre::
 		ObjectEvents::ChildrenChanged(children_changed_event) => {
 			children_changed::dispatch(state, children_changed_event).await?;
 		}
+		ObjectEvents::ActiveDescendantChanged(active_descendant_changed_event) => {
+			active_descendant_changed::dispatch(state, active_descendant_changed_event)
+				.await?;
+		}
+		ObjectEvents::PropertyChange(property_change_event) => {
+			property_change::dispatch(state, property_change_event).await?;
+		}
+		// not to be confused with `TextSelectionChanged`, which is about selected text and is ignored here
+		ObjectEvents::SelectionChanged(selection_changed_event) => {
+			selection_changed::dispatch(state, selection_changed_event).await?;
+		}
+		ObjectEvents::TextAttributesChanged(text_attributes_changed_event) => {
+			text_attributes_changed::dispatch(state, text_attributes_changed_event)
+				.await?;
+		}
+		ObjectEvents::AttributesChanged(attributes_changed_event) => {
+			attributes_changed::dispatch(state, attributes_changed_event).await?;
+		}
+		ObjectEvents::RowReordered(row_reordered_event) => {
+			table_reordered::dispatch(state, row_reordered_event, TableLine::Row)
+				.await?;
+		}
+		ObjectEvents::ColumnReordered(column_reordered_event) => {
+			table_reordered::dispatch(state, column_reordered_event, TableLine::Column)
+				.await?;
+		}
 		other_member => {
 			tracing::debug!("Ignoring event with unknown member: {:#?}", other_member);
 		}
@@ -25,16 +52,24 @@ pub async fn dispatch(state: &ScreenReaderState, event: &ObjectEvents) -> eyre::
 }
 
 mod text_changed {
-	use crate::state::ScreenReaderState;
+	use crate::state::{ssip_priority, PendingDeletion, ScreenReaderState};
 	use atspi_common::events::object::TextChangedEvent;
-	use odilia_cache::CacheItem;
+	use atspi_proxies::accessible::AccessibleProxy;
+	use odilia_cache::{AccessiblePrimitive, CacheItem};
 	use odilia_common::{
 		errors::OdiliaError,
 		result::OdiliaResult,
+		settings::{
+			announcements::{TextChangeTemplates, TypingEcho},
+			priority::{EventCategory, SpeechPriority},
+		},
 		types::{AriaAtomic, AriaLive},
 	};
 	use ssip_client_async::Priority;
-	use std::collections::HashMap;
+	use std::{
+		collections::HashMap,
+		time::{Duration, Instant},
+	};
 
 	#[tracing::instrument(level = "trace")]
 	pub fn update_string_insert(
@@ -110,11 +145,40 @@ mod text_changed {
 	/// if the aria-live attribute is set to "assertive", then set the priority of the message to speak immediately, stop all other messages, and do not interrupt that piece of speech
 	/// otherwise, do not continue
 	pub fn live_to_priority(live_str: &AriaLive) -> Priority {
-		match live_str {
-			AriaLive::Assertive => Priority::Important,
-			AriaLive::Polite => Priority::Notification,
-			_ => Priority::Message,
+		ssip_priority(SpeechPriority::from_live(live_str))
+	}
+
+	/// The attributes which say how changes to a live region are read; see [`get_live_state`] and [`get_atomic_state`].
+	pub const LIVE_ATTRIBUTES: [&str; 2] = ["live", "atomic"];
+
+	/// Remember the live region attributes of `item`, out of all of its `attributes`.
+	/// Items which are not live regions are remembered too, with no attributes, so that they are not asked again either.
+	pub fn update_live_attributes(
+		cache: &mut HashMap<AccessiblePrimitive, HashMap<String, String>>,
+		item: AccessiblePrimitive,
+		attributes: &HashMap<String, String>,
+	) -> HashMap<String, String> {
+		let live: HashMap<String, String> = attributes
+			.iter()
+			.filter(|(key, _)| LIVE_ATTRIBUTES.contains(&key.as_str()))
+			.map(|(key, value)| (key.clone(), value.clone()))
+			.collect();
+		cache.insert(item, live.clone());
+		live
+	}
+
+	/// The live region attributes of `item`, asking the application only the first time.
+	async fn live_attributes(
+		state: &ScreenReaderState,
+		item: &AccessiblePrimitive,
+		accessible: &AccessibleProxy<'_>,
+	) -> eyre::Result<HashMap<String, String>> {
+		if let Some(cached) = state.live_attributes.lock().await.get(item) {
+			return Ok(cached.clone());
 		}
+		let attributes = accessible.get_attributes().await?;
+		let mut cache = state.live_attributes.lock().await;
+		Ok(update_live_attributes(&mut cache, item.clone(), &attributes))
 	}
 
 	#[tracing::instrument(level = "trace", ret)]
@@ -155,6 +219,113 @@ mod text_changed {
 		}
 	}
 
+	/// Find the word which was just completed by inserting `inserted` at `start_pos` in `original`.
+	/// A word is completed when a single non-alphanumeric character (like a space or a comma) is typed directly after it.
+	/// Returns `None` if no word was completed.
+	pub fn completed_word(original: &str, inserted: &str, start_pos: usize) -> Option<String> {
+		let mut inserted_chars = inserted.chars();
+		let boundary = inserted_chars.next()?;
+		if inserted_chars.next().is_some() || boundary.is_alphanumeric() {
+			return None;
+		}
+		let before: Vec<char> = original.chars().take(start_pos).collect();
+		let word_start = before
+			.iter()
+			.rposition(|chr| !chr.is_alphanumeric())
+			.map_or(0, |idx| idx + 1);
+		let word: String = before[word_start..].iter().collect();
+		if word.is_empty() {
+			None
+		} else {
+			Some(word)
+		}
+	}
+
+	/// Decide what should be echoed back to the user after they typed `inserted` at `start_pos` in `original`.
+	/// Only single characters are echoed; larger insertions (like pasting) are left alone.
+	pub fn typing_echo(
+		echo: TypingEcho,
+		original: &str,
+		inserted: &str,
+		start_pos: usize,
+	) -> Option<String> {
+		if inserted.chars().count() != 1 {
+			return None;
+		}
+		let word = || completed_word(original, inserted, start_pos);
+		match echo {
+			TypingEcho::None => None,
+			TypingEcho::Char => Some(inserted.to_string()),
+			TypingEcho::Word => word(),
+			TypingEcho::Both => word().or_else(|| Some(inserted.to_string())),
+		}
+	}
+
+	/// Whether text was added to or taken out of an accessible.
+	#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+	pub enum TextChange {
+		Inserted,
+		Deleted,
+	}
+
+	/// Put text which was inserted or deleted into words, using the configured templates.
+	/// Text longer than the summary threshold is described by how long it is, instead of being read out.
+	/// Returns `None` when there is nothing to read: the text is only whitespace, or the template is empty.
+	pub fn text_change_announcement(
+		templates: &TextChangeTemplates,
+		change: TextChange,
+		text: &str,
+	) -> Option<String> {
+		if text.trim().is_empty() {
+			return None;
+		}
+		let count = text.chars().count();
+		let summarize = templates.summarize_over > 0 && count > templates.summarize_over;
+		let template = match (change, summarize) {
+			(TextChange::Inserted, false) => &templates.inserted,
+			(TextChange::Inserted, true) => &templates.inserted_summary,
+			(TextChange::Deleted, false) => &templates.deleted,
+			(TextChange::Deleted, true) => &templates.deleted_summary,
+		};
+		let announcement = template
+			.replace("{count}", &count.to_string())
+			.replace("{text}", text.trim());
+		(!announcement.trim().is_empty()).then_some(announcement)
+	}
+
+	/// A deletion followed by an insertion at the same place within this long is treated as a single replacement, like an autocorrection.
+	/// This is kept short so that a user quickly backspacing and retyping a character is not mistaken for one.
+	pub const REPLACEMENT_WINDOW: Duration = Duration::from_millis(50);
+
+	/// If `inserted` (at `start_pos`, at time `now`) completes a replacement of the pending deletion, describe the replacement.
+	pub fn replacement_announcement(
+		deletion: &PendingDeletion,
+		start_pos: usize,
+		inserted: &str,
+		now: Instant,
+	) -> Option<String> {
+		let deleted = deletion.text.trim();
+		let inserted = inserted.trim();
+		let is_replacement = deletion.start_pos == start_pos
+			&& now.saturating_duration_since(deletion.at) <= REPLACEMENT_WINDOW
+			&& !deleted.is_empty()
+			&& !inserted.is_empty()
+			&& deleted != inserted;
+		is_replacement.then(|| format!("changed {deleted} to {inserted}"))
+	}
+
+	/// Take the deletion made at `at` out of `pending`, unless an insertion has replaced it since, or a newer deletion has taken its place.
+	pub fn unreplaced_deletion(
+		pending: &mut HashMap<AccessiblePrimitive, PendingDeletion>,
+		object: &AccessiblePrimitive,
+		at: Instant,
+	) -> Option<PendingDeletion> {
+		if pending.get(object)?.at != at {
+			return None;
+		}
+		pending.remove(object)
+	}
+
 	#[tracing::instrument(level = "debug", skip(state), err)]
 	pub async fn dispatch(
 		state: &ScreenReaderState,
@@ -186,9 +357,20 @@ mod text_changed {
 		// if the atomic state is true, then read out the entite piece of text
 		// if atomic state is false, then only read out the portion which has been added
 		// otherwise, do not continue through this function
-		let text_to_say =
-			if atomic { cache_text.to_string() } else { (&event.text).into() };
-		let priority = live_to_priority(&live);
+		let text_to_say = if atomic {
+			Some(cache_text.to_string())
+		} else {
+			let inserted: String = (&event.text).into();
+			text_change_announcement(
+				&state.config.announcements.text_changes,
+				TextChange::Inserted,
+				&inserted,
+			)
+		};
+		let Some(text_to_say) = text_to_say else {
+			return Ok(());
+		};
+		let priority = state.priority(EventCategory::TextInserted, Some(&live));
 		state.say(priority, text_to_say).await;
 		Ok(())
 	}
@@ -208,12 +390,49 @@ mod text_changed {
 		let current_text = cache_item.text;
 		let (start_pos, update_length) =
 			(usize::try_from(event.start_pos)?, usize::try_from(event.length)?);
+		let now = Instant::now();
 		// if this is an insert, figure out if we shuld announce anything, then speak it;
 		// only after should we try to update the cache
 		if insert {
-			let attributes = accessible.get_attributes().await?;
-			let _: OdiliaResult<()> =
-				speak_insertion(state, event, &attributes, &current_text).await;
+			let pending =
+				state.pending_deletions.lock().await.remove(&cache_item.object);
+			let replacement = pending.and_then(|deletion| {
+				replacement_announcement(&deletion, start_pos, &updated_text, now)
+			});
+			if let Some(replacement) = replacement {
+				// a correction replaces the deletion, the typing echo and the inserted text alike
+				state.say(Priority::Text, replacement).await;
+			// "insert/system" is used for text the application adds itself, only echo what the user typed
+			} else if event.operation == "insert" {
+				if let Some(echo) = typing_echo(
+					state.config.announcements.echo,
+					&current_text,
+					&updated_text,
+					start_pos,
+				) {
+					state.say(Priority::Text, echo).await;
+				}
+			} else {
+				// only text the application inserts itself, like new text in a live region, is read here; typing is echoed above
+				let attributes =
+					live_attributes(state, &cache_item.object, &accessible)
+						.await?;
+				let _: OdiliaResult<()> =
+					speak_insertion(state, event, &attributes, &current_text)
+						.await;
+			}
+		} else {
+			let mut pending_deletions = state.pending_deletions.lock().await;
+			// forget deletions which were never followed by an insertion;
+			// the user's own are still waiting to be read for a moment after the window closes, so leave them be until then
+			pending_deletions.retain(|_, deletion| {
+				now.saturating_duration_since(deletion.at) <= REPLACEMENT_WINDOW * 2
+			});
+			pending_deletions.insert(
+				cache_item.object.clone(),
+				PendingDeletion { text: updated_text.clone(), start_pos, at: now },
+			);
+			drop(pending_deletions);
 		}
 
 		let text_selection_from_cache: String = current_text
@@ -240,16 +459,47 @@ mod text_changed {
 					.collect();
 			})?;
 		}
+		// only what the user deleted is read back, not text the application removes itself;
+		// wait to see whether it is replaced first, so that a correction is read once as a replacement
+		if !insert && event.operation == "delete" {
+			tokio::time::sleep(REPLACEMENT_WINDOW).await;
+			let unreplaced = unreplaced_deletion(
+				&mut state.pending_deletions.lock().await,
+				&cache_item.object,
+				now,
+			);
+			if let Some(deleted) = unreplaced.and_then(|deletion| {
+				text_change_announcement(
+					&state.config.announcements.text_changes,
+					TextChange::Deleted,
+					&deletion.text,
+				)
+			}) {
+				state.say(Priority::Text, deleted).await;
+			}
+		}
 		Ok(())
 	}
 }
 
-mod children_changed {
+pub(super) mod children_changed {
+	use crate::children_batch::{ChildChange, ChildChanges};
+	use crate::events::commands::STATUS_BAR_ROLES;
+	use crate::number_format::NumberFormat;
 	use crate::state::ScreenReaderState;
-	use atspi_common::events::object::ChildrenChangedEvent;
-	use odilia_cache::{AccessiblePrimitive, CacheItem};
-	use odilia_common::result::OdiliaResult;
-	use std::sync::Arc;
+	use atspi_common::{events::object::ChildrenChangedEvent, Role, State};
+	use odilia_cache::{accessible_to_cache_item, AccessiblePrimitive, CacheItem};
+	use odilia_common::{
+		result::OdiliaResult, settings::announcements::TooltipAnnouncement, types::AriaLive,
+	};
+	use ssip_client_async::Priority;
+	use std::{
+		sync::Arc,
+		time::{Duration, Instant},
+	};
+	use tokio::task::JoinSet;
+
+	use super::text_changed::live_to_priority;
 
 	#[tracing::instrument(level = "debug", skip(state), err)]
 	pub async fn dispatch(
@@ -257,11 +507,36 @@ mod children_changed {
 		event: &ChildrenChangedEvent,
 	) -> eyre::Result<()> {
 		// Dispatch based on kind
-		match event.operation.as_str() {
-			"remove" | "remove/system" => remove(state, event)?,
-			"add" | "add/system" => add(state, event).await?,
-			kind => tracing::debug!(kind, "Ignoring event with unknown kind"),
+		let change = match event.operation.as_str() {
+			"remove" | "remove/system" => ChildChange::Removed,
+			"add" | "add/system" => ChildChange::Added,
+			kind => {
+				tracing::debug!(kind, "Ignoring event with unknown kind");
+				return Ok(());
+			}
+		};
+		let parent = AccessiblePrimitive::from_event(event)?;
+		if state.config.cache.children_changed_window_ms == 0 {
+			match change {
+				ChildChange::Removed => remove(state, event).await?,
+				ChildChange::Added => add(state, event).await?,
+			}
+		} else {
+			let mut batch = state.children_batch.lock().await;
+			batch.push(
+				parent.clone(),
+				get_child_primitive(event),
+				change,
+				Instant::now(),
+			);
+			// a full batch is handled straight away; anything else waits for the window to pass
+			if batch.is_full() {
+				let changes = batch.take();
+				drop(batch);
+				apply(state, changes).await?;
+			}
 		}
+		autocomplete_options(state, &parent).await;
 		Ok(())
 	}
 	#[tracing::instrument(level = "debug", skip(state), err)]
@@ -272,54 +547,308 @@ mod children_changed {
 		let accessible = get_child_primitive(event)
 			.into_accessible(state.atspi.connection())
 			.await?;
-		let _: OdiliaResult<CacheItem> = state
+		let item: OdiliaResult<CacheItem> = state
 			.cache
 			.get_or_create(&accessible, Arc::downgrade(&Arc::clone(&state.cache)))
 			.await;
 		tracing::debug!("Add a single item to cache.");
+		if let Ok(item) = item {
+			announce_added(state, item).await;
+		}
+		Ok(())
+	}
+
+	/// Read a child which was just added, if it is a notification or a tooltip which should be.
+	async fn announce_added(state: &ScreenReaderState, item: CacheItem) {
+		if state.config.announcements.live_notifications
+			&& STATUS_BAR_ROLES.contains(&item.role)
+		{
+			// treat new notifications like an assertive live region
+			state.say(live_to_priority(&AriaLive::Assertive), item.text).await;
+		} else if item.states.contains(State::Showing) {
+			// tooltips which are added hidden are read once they are shown instead
+			announce_tooltip(state, &item).await;
+		}
+	}
+
+	/// Handle a batch of changed children in one pass: removed children are dropped from the cache together, and added children are fetched all at once and then cached together.
+	#[tracing::instrument(level = "debug", skip_all, fields(parents = changes.added.len(), removed = changes.removed.len()), err)]
+	pub async fn apply(state: &ScreenReaderState, changes: ChildChanges) -> eyre::Result<()> {
+		for prim in &changes.removed {
+			forget_removed_modal(state, prim).await;
+		}
+		forget_live_attributes(state, &changes.removed).await;
+		state.cache.remove_all(&changes.removed);
+		let mut items = Vec::new();
+		let mut fetches = JoinSet::new();
+		for child in changes.added.into_values().flatten() {
+			if let Some(item) = state.cache.get(&child) {
+				items.push(item);
+				continue;
+			}
+			let connection = state.atspi.connection().clone();
+			let cache = Arc::downgrade(&state.cache);
+			fetches.spawn(async move {
+				let accessible = child.into_accessible(&connection).await?;
+				accessible_to_cache_item(&accessible, cache).await
+			});
+		}
+		let mut fetched = Vec::new();
+		while let Some(result) = fetches.join_next().await {
+			match result {
+				Ok(Ok(item)) => fetched.push(item),
+				// the child may already be gone again by the time it is asked about
+				Ok(Err(e)) => {
+					tracing::debug!(error = %e, "Could not fetch an added child");
+				}
+				Err(e) => {
+					tracing::debug!(error = %e, "Fetching an added child did not finish");
+				}
+			}
+		}
+		state.cache.add_all(fetched.clone())?;
+		tracing::debug!(
+			count = items.len() + fetched.len(),
+			"Add a batch of items to cache."
+		);
+		for item in items.into_iter().chain(fetched) {
+			announce_added(state, item).await;
+		}
 		Ok(())
 	}
+
+	/// The priority to read a tooltip which just appeared with, or `None` if it is not read at all.
+	/// Anything which is not a tooltip is never read here.
+	pub fn tooltip_priority(role: Role, setting: TooltipAnnouncement) -> Option<Priority> {
+		if role != Role::ToolTip {
+			return None;
+		}
+		match setting {
+			// notifications wait for other speech to finish, and are dropped if it does not
+			TooltipAnnouncement::Polite => Some(Priority::Notification),
+			TooltipAnnouncement::Interrupt => Some(Priority::Message),
+			TooltipAnnouncement::Off => None,
+		}
+	}
+
+	/// Read `item` if it is a tooltip, as the user has configured.
+	pub async fn announce_tooltip(state: &ScreenReaderState, item: &CacheItem) {
+		let Some(priority) =
+			tooltip_priority(item.role, state.config.announcements.tooltips)
+		else {
+			return;
+		};
+		if item.text.trim().is_empty() {
+			return;
+		}
+		state.say(priority, item.text.clone()).await;
+	}
+	/// How long the count of options waits for typing to pause, so that each key pressed does not read a count of its own.
+	pub const AUTOCOMPLETE_DEBOUNCE: Duration = Duration::from_millis(300);
+
+	/// Roles of the lists an editable combo box offers its options in.
+	const AUTOCOMPLETE_LIST_ROLES: [Role; 3] = [Role::List, Role::ListBox, Role::Menu];
+
+	/// What is said when typing changes which options a combo box offers: how many are left, and the one which would be picked, like "5 options, Apple".
+	/// Returns `None` when there are as many options as `previous`, since typing which does not narrow the list down does not need repeating.
+	pub fn autocomplete_announcement(
+		format: &dyn NumberFormat,
+		previous: Option<usize>,
+		options: &[CacheItem],
+	) -> Option<String> {
+		let count = options.len();
+		if previous == Some(count) {
+			return None;
+		}
+		let text = format.count(count, "option", "options");
+		let active = options
+			.iter()
+			.find(|option| {
+				option.states.contains(State::Selected)
+					|| option.states.contains(State::Focused)
+			})
+			.or_else(|| options.first())
+			.map(|option| option.text.trim())
+			.filter(|name| !name.is_empty());
+		Some(match active {
+			Some(name) => format!("{text}, {name}"),
+			None => text,
+		})
+	}
+
+	/// The focused combo box, if the user is typing into one which has its list of options open.
+	/// The text entry of a combo box is often a child of it, and is what holds the focus then.
+	async fn typing_in_combo_box(state: &ScreenReaderState) -> Option<CacheItem> {
+		let focused = state.cache.get(&state.history_item(0).await?)?;
+		let combo_box = if focused.role == Role::ComboBox {
+			focused.clone()
+		} else {
+			state.cache
+				.parent_of(&focused.object)
+				.filter(|parent| parent.role == Role::ComboBox)?
+		};
+		let editable = focused.states.contains(State::Editable)
+			|| combo_box.states.contains(State::Editable);
+		(editable && combo_box.states.contains(State::Expanded)).then_some(combo_box)
+	}
+
+	/// Read how many options are left, and the one which would be picked, once typing into an editable combo box has changed the children of its list.
+	/// Changes which come in while typing are held back until it pauses, so only the last count is read.
+	async fn autocomplete_options(state: &ScreenReaderState, list: &AccessiblePrimitive) {
+		let is_list = state
+			.cache
+			.get(list)
+			.is_some_and(|item| AUTOCOMPLETE_LIST_ROLES.contains(&item.role));
+		if !is_list || typing_in_combo_box(state).await.is_none() {
+			return;
+		}
+		let now = Instant::now();
+		*state.pending_autocomplete.lock().await = Some(now);
+		tokio::time::sleep(AUTOCOMPLETE_DEBOUNCE).await;
+		{
+			let mut pending = state.pending_autocomplete.lock().await;
+			// another change came in while waiting; that one reads the count instead
+			if *pending != Some(now) {
+				return;
+			}
+			*pending = None;
+		}
+		let options = match list_options(state, list).await {
+			Ok(options) => options,
+			Err(e) => {
+				tracing::debug!(error = %e, "Could not fetch the options of a combo box");
+				return;
+			}
+		};
+		let mut last = state.autocomplete_count.lock().await;
+		let previous = last
+			.as_ref()
+			.filter(|(counted, _)| counted == list)
+			.map(|(_, count)| *count);
+		*last = Some((list.clone(), options.len()));
+		drop(last);
+		if let Some(text) =
+			autocomplete_announcement(state.number_format(), previous, &options)
+		{
+			state.say(Priority::Text, text).await;
+		}
+	}
+
+	/// The children of `list` as they are now, fetching any which are not cached yet.
+	/// The cached children of the list are brought up to date as well, since they are not changed as children come and go.
+	async fn list_options(
+		state: &ScreenReaderState,
+		list: &AccessiblePrimitive,
+	) -> eyre::Result<Vec<CacheItem>> {
+		let keys: Vec<AccessiblePrimitive> = list
+			.clone()
+			.into_accessible(state.atspi.connection())
+			.await?
+			.get_children()
+			.await?
+			.into_iter()
+			.map(AccessiblePrimitive::from)
+			.collect();
+		state.cache.reorder_children(list, keys.clone())?;
+		let mut options = Vec::with_capacity(keys.len());
+		for key in keys {
+			if let Some(item) = state.cache.get(&key) {
+				options.push(item);
+				continue;
+			}
+			let accessible = key.into_accessible(state.atspi.connection()).await?;
+			let item = state
+				.cache
+				.get_or_create(&accessible, Arc::downgrade(&state.cache))
+				.await?;
+			options.push(item);
+		}
+		Ok(options)
+	}
+
 	fn get_child_primitive(event: &ChildrenChangedEvent) -> AccessiblePrimitive {
 		event.child.clone().into()
 	}
 	#[tracing::instrument(level = "debug", skip(state), ret, err)]
-	pub fn remove(state: &ScreenReaderState, event: &ChildrenChangedEvent) -> eyre::Result<()> {
+	pub async fn remove(
+		state: &ScreenReaderState,
+		event: &ChildrenChangedEvent,
+	) -> eyre::Result<()> {
 		let prim = get_child_primitive(event);
+		forget_removed_modal(state, &prim).await;
+		forget_live_attributes(state, std::slice::from_ref(&prim)).await;
 		state.cache.remove(&prim);
 		tracing::debug!("Remove a single item from cache.");
 		Ok(())
 	}
+	/// Forget the live region attributes of removed children, which will not change again.
+	async fn forget_live_attributes(
+		state: &ScreenReaderState,
+		removed: &[AccessiblePrimitive],
+	) {
+		let mut live_attributes = state.live_attributes.lock().await;
+		for prim in removed {
+			live_attributes.remove(prim);
+		}
+	}
+	/// Forget the active modal dialog if `prim` is it, or holds it.
+	/// A dialog closes when it, or the window around it, is removed; this must be checked before the cache forgets its ancestors.
+	async fn forget_removed_modal(state: &ScreenReaderState, prim: &AccessiblePrimitive) {
+		let mut active_modal = state.active_modal.lock().await;
+		if active_modal
+			.as_ref()
+			.is_some_and(|modal| state.cache.is_within(modal, prim))
+		{
+			tracing::debug!("The active modal dialog was removed.");
+			*active_modal = None;
+		}
+	}
 }
 
 mod text_caret_moved {
+	use crate::earcons::{indent_tone_frequency, play_tone, INDENT_TONE_DURATION};
 	use crate::state::ScreenReaderState;
+	use crate::text_formatting::{announce_formatting, CaretContext};
 	use atspi_common::events::object::TextCaretMovedEvent;
 	use atspi_common::Granularity;
-	use odilia_cache::CacheItem;
-	use odilia_common::errors::{CacheError, OdiliaError};
-	use ssip_client_async::Priority;
+	use odilia_cache::{AccessiblePrimitive, CacheItem};
+	use odilia_common::{
+		errors::OdiliaError,
+		settings::{announcements::IndentationAnnouncement, priority::EventCategory},
+	};
 	use std::{
 		cmp::{max, min},
 		sync::atomic::Ordering,
 	};
 	use tracing::debug;
 
+	/// The offset of the character whose word is read as context after the caret moved by one character.
+	/// Moving forward, that is the character moved over, just before the caret; moving backward, it is the character the caret moved onto, just after it.
+	pub fn crossed_offset(old_position: usize, new_position: usize) -> usize {
+		if new_position > old_position {
+			new_position - 1
+		} else {
+			new_position
+		}
+	}
+
 	#[tracing::instrument(level = "debug", ret, err)]
 	pub async fn new_position(
 		new_item: CacheItem,
 		old_item: CacheItem,
 		new_position: usize,
 		old_position: usize,
+		granularity: Granularity,
 	) -> Result<String, OdiliaError> {
 		let new_id = new_item.object.clone();
 		let old_id = old_item.object.clone();
 
-		// if the user has moved into a new item, then also read a whole line.
+		// if the user has moved into a new item, then read a whole unit of the chosen granularity, a line unless it was changed.
 		debug!("{new_id:?},{old_id:?}");
 		debug!("{old_position},{new_position}");
 		if new_id != old_id {
 			return Ok(new_item
-				.get_string_at_offset(new_position, Granularity::Line)
+				.get_string_at_offset(new_position, granularity)
 				.await?
 				.0);
 		}
@@ -355,6 +884,123 @@ mod text_caret_moved {
 			.0)
 	}
 
+	/// Whether a line has nothing to read: it is empty or only whitespace.
+	pub fn is_blank_line(line: &str) -> bool {
+		line.trim().is_empty()
+	}
+	/// When the line holding the character at `offset` is blank, the offset of the start of the nearest line after it (`forward`) or before it which is not.
+	/// Returns `None` when the line is not blank, or when there are only blank lines left in that direction.
+	pub fn skip_blank_lines(text: &str, offset: usize, forward: bool) -> Option<usize> {
+		let mut start = 0;
+		let lines: Vec<(usize, &str)> = text
+			.split('\n')
+			.map(|line| {
+				let line_start = start;
+				start += line.chars().count() + 1;
+				(line_start, line)
+			})
+			.collect();
+		let current = lines.iter().rposition(|(line_start, _)| *line_start <= offset)?;
+		if !is_blank_line(lines[current].1) {
+			return None;
+		}
+		let not_blank = |&(line_start, line): &(usize, &str)| {
+			(!is_blank_line(line)).then_some(line_start)
+		};
+		if forward {
+			lines[current + 1..].iter().find_map(not_blank)
+		} else {
+			lines[..current].iter().rev().find_map(not_blank)
+		}
+	}
+	/// The word holding the character at `offset` (counted in characters), or `None` when that character is not part of a word.
+	pub fn word_at(text: &str, offset: usize) -> Option<&str> {
+		let is_word = |chr: char| chr.is_alphanumeric() || chr == '_' || chr == '\'';
+		let chars: Vec<(usize, char)> = text.char_indices().collect();
+		if !is_word(chars.get(offset)?.1) {
+			return None;
+		}
+		let start = chars[..offset]
+			.iter()
+			.rposition(|(_, chr)| !is_word(*chr))
+			.map_or(0, |position| position + 1);
+		let end = chars[offset..]
+			.iter()
+			.position(|(_, chr)| !is_word(*chr))
+			.map_or(text.len(), |position| chars[offset + position].0);
+		Some(&text[chars[start].0..end])
+	}
+	/// How many columns a tab character is worth when measuring indentation.
+	pub const TAB_WIDTH: usize = 4;
+
+	/// The width of the leading whitespace of a line, in columns.
+	/// Tabs are counted as moving to the next multiple of [`TAB_WIDTH`].
+	pub fn indentation_columns(line: &str) -> usize {
+		line.chars().take_while(|chr| *chr == ' ' || *chr == '\t').fold(
+			0,
+			|columns, chr| {
+				if chr == '\t' {
+					(columns / TAB_WIDTH + 1) * TAB_WIDTH
+				} else {
+					columns + 1
+				}
+			},
+		)
+	}
+	/// Whether the indentation of a line differs from that of the last line read in the same text.
+	/// The first line read is only worth mentioning if it is indented at all.
+	pub fn indentation_changed(previous: Option<usize>, current: usize) -> bool {
+		previous.map_or(current > 0, |previous| previous != current)
+	}
+	/// Announce the indentation of `line`, which was read in `accessible`, according to the user's configuration.
+	/// Blank lines are ignored, since their indentation means nothing to the reader.
+	async fn announce_indentation(
+		state: &ScreenReaderState,
+		accessible: &AccessiblePrimitive,
+		line: &str,
+	) {
+		if line.trim().is_empty() {
+			return;
+		}
+		let columns = indentation_columns(line);
+		let previous = state
+			.last_indentation
+			.lock()
+			.await
+			.insert(accessible.clone(), columns);
+		let changes_only = state.config.announcements.indentation_changes_only;
+		if changes_only && !indentation_changed(previous, columns) {
+			return;
+		}
+		match state.config.announcements.indentation {
+			IndentationAnnouncement::Off => {}
+			IndentationAnnouncement::Spoken => {
+				let text = if columns > 0 {
+					format!(
+						"indent {}",
+						state.number_format().cardinal(columns)
+					)
+				} else if changes_only {
+					// going back to the margin is a change as well, and would otherwise be silent
+					"no indent".to_string()
+				} else {
+					return;
+				};
+				state.say(state.priority(EventCategory::Caret, None), text).await;
+			}
+			IndentationAnnouncement::Tone => {
+				if let Err(e) = play_tone(
+					indent_tone_frequency(columns),
+					INDENT_TONE_DURATION,
+				)
+				.await
+				{
+					tracing::debug!(error = %e, "Could not play indentation tone");
+				}
+			}
+		}
+	}
+
 	/// this must be checked *before* writing an accessible to the hsitory.
 	/// if this is checked after writing, it may give inaccurate results.
 	/// that said, this is a *guess* and not a guarentee.
@@ -396,13 +1042,67 @@ mod text_caret_moved {
 			return Ok(());
 		}
 		let new_item = state.get_or_create_event_object_to_cache(event).await?;
+		if state.config.navigation.skip_blank_lines {
+			let new_pos: usize = event.position.try_into()?;
+			let old_pos = state.previous_caret_position.load(Ordering::Relaxed);
+			let same_item =
+				state.history_item(0).await.as_ref() == Some(&new_item.object);
+			// a move of a single character is typing or arrowing, not moving by line
+			if same_item && new_pos.abs_diff(old_pos) > 1 {
+				if let Some(start) =
+					skip_blank_lines(&new_item.text, new_pos, new_pos > old_pos)
+				{
+					// the line the caret lands on is read when its own caret event arrives
+					let text = new_item
+						.object
+						.clone()
+						.into_text(state.atspi.connection())
+						.await?;
+					let _: bool =
+						text.set_caret_offset(start.try_into()?).await?;
+					return Ok(());
+				}
+			}
+		}
 
 		let new_prim = new_item.object.clone();
+		let context_word = if state.speak_caret_context.load(Ordering::Relaxed) {
+			let new_pos: usize = event.position.try_into()?;
+			let old_pos = state.previous_caret_position.load(Ordering::Relaxed);
+			let same_item = state.history_item(0).await.as_ref() == Some(&new_prim);
+			// the word is only added when moving by character, since other moves already read whole words or lines
+			if same_item && new_pos.abs_diff(old_pos) == 1 {
+				// the word holding the character which is read, not whatever is after the caret
+				word_at(&new_item.text, crossed_offset(old_pos, new_pos))
+					.map(str::to_string)
+			} else {
+				None
+			}
+		} else {
+			None
+		};
+		let formatting_item = state
+			.config
+			.announcements
+			.reads_text_attributes()
+			.then(|| new_item.clone());
+		let current_line = if state.config.announcements.indentation
+			== IndentationAnnouncement::Off
+		{
+			None
+		} else {
+			new_item.get_string_at_offset(event.position.try_into()?, Granularity::Line)
+				.await
+				.ok()
+				.map(|(line, _, _)| line)
+		};
 		let text = match state.history_item(0).await {
 			Some(old_prim) => {
 				let old_pos = state.previous_caret_position.load(Ordering::Relaxed);
-				let old_item =
-					state.cache.get(&old_prim).ok_or(CacheError::NoItem)?;
+				let old_item = match state.cache.get(&old_prim) {
+					Some(old_item) => old_item,
+					None => state.get_or_create_cache_item(old_prim).await?,
+				};
 				let new_pos = event.position;
 				new_position(
 					new_item,
@@ -410,6 +1110,7 @@ mod text_caret_moved {
 					new_pos.try_into()
 						.expect("Can not convert between i32 and usize"),
 					old_pos,
+					*state.granularity.lock().await,
 				)
 				.await?
 			}
@@ -418,7 +1119,22 @@ mod text_caret_moved {
 				new_item.get_string_at_offset(0, Granularity::Paragraph).await?.0
 			}
 		};
-		state.say(Priority::Text, text).await;
+		// only convey indentation when a whole line is being read
+		if let Some(line) = current_line.filter(|line| *line == text) {
+			announce_indentation(state, &new_prim, &line).await;
+		}
+		let text = match context_word {
+			Some(word) if word != text => format!("{text}, {word}"),
+			_ => text,
+		};
+		state.say(state.priority(EventCategory::Caret, None), text).await;
+		if let Some(item) = formatting_item {
+			if let Err(e) =
+				announce_formatting(state, &item, CaretContext::CaretMoved).await
+			{
+				debug!(error = %e, "Could not read the formatting at the caret");
+			}
+		}
 		state.update_accessible(new_prim).await;
 		Ok(())
 	}
@@ -440,10 +1156,42 @@ mod text_caret_moved {
 	}
 } // end of text_caret_moved
 
-mod state_changed {
-	use crate::state::ScreenReaderState;
-	use atspi_common::{events::object::StateChangedEvent, State};
-	use odilia_cache::AccessiblePrimitive;
+pub(super) mod state_changed {
+	use crate::number_format::NumberFormat;
+	use crate::state::{PendingFocus, ScreenReaderState};
+	use crate::text_formatting::{announce_formatting, CaretContext};
+	use atspi_common::{
+		events::object::StateChangedEvent, Interface, RelationType, Role, State, StateSet,
+	};
+	use odilia_cache::{AccessiblePrimitive, CacheItem, Convertable};
+	use odilia_common::{
+		errors::OdiliaError,
+		settings::{presentation::CellPosition, priority::EventCategory},
+	};
+	use ssip_client_async::Priority;
+	use std::collections::HashMap;
+	use std::sync::atomic::{AtomicBool, Ordering};
+	use std::time::{Duration, Instant};
+
+	use super::children_changed::announce_tooltip;
+
+	/// The shortest time between two "loading" announcements, so a busy indicator which flickers does not flood speech.
+	pub const BUSY_ANNOUNCEMENT_INTERVAL: Duration = Duration::from_secs(3);
+
+	/// What, if anything, should be said when the busy state of a progress indicator changes.
+	/// Nothing is said unless the state actually changed, so repeated events are ignored.
+	pub fn busy_transition(was_busy: bool, now_busy: bool) -> Option<&'static str> {
+		match (was_busy, now_busy) {
+			(false, true) => Some("loading"),
+			(true, false) => Some("done"),
+			_ => None,
+		}
+	}
+
+	/// Whether enough time has passed since the last "loading" announcement to make another one.
+	pub fn busy_rate_limit_elapsed(last: Option<Instant>, now: Instant) -> bool {
+		last.map_or(true, |last| now.duration_since(last) >= BUSY_ANNOUNCEMENT_INTERVAL)
+	}
 
 	/// Update the state of an item in the cache using a `StateChanged` event and the `ScreenReaderState` as context.
 	/// This writes to the value in-place, and does not clone any values.
@@ -455,15 +1203,19 @@ mod state_changed {
 	) -> eyre::Result<bool> {
 		if active {
 			Ok(state.cache.modify_item(a11y, |cache_item| {
-				cache_item.states.remove(state_changed);
+				cache_item.states.insert(state_changed);
 			})?)
 		} else {
 			Ok(state.cache.modify_item(a11y, |cache_item| {
-				cache_item.states.insert(state_changed);
+				cache_item.states.remove(state_changed);
 			})?)
 		}
 	}
 
+	/// States whose changes are read as the state itself, like "expanded", and so are left unread when the user has silenced them.
+	/// Other changes, like an item gaining focus, read the item instead and are always handled.
+	const STATE_ANNOUNCEMENTS: [State; 3] = [State::Busy, State::Expanded, State::InvalidEntry];
+
 	#[tracing::instrument(level = "debug", skip(state), err)]
 	pub async fn dispatch(
 		state: &ScreenReaderState,
@@ -472,14 +1224,47 @@ mod state_changed {
 		let state_value = event.enabled == 1;
 		// update cache with state of item
 		let a11y_prim = AccessiblePrimitive::from_event(event)?;
+		// events are only sent on change, so if we have never seen the item, assume it was in the opposite state
+		let was_enabled = state
+			.cache
+			.get(&a11y_prim)
+			.map_or(!state_value, |item| item.states.contains(event.state));
 		if update_state(state, &a11y_prim, event.state, state_value)? {
 			tracing::trace!("Updating of the state was not successful! The item with id {:?} was not found in the cache.", a11y_prim.id);
 		} else {
 			tracing::trace!("Updated the state of accessible with ID {:?}, and state {:?} to {state_value}.", a11y_prim.id, event.state);
 		}
+		if STATE_ANNOUNCEMENTS.contains(&event.state)
+			&& !state.config.presentation.is_spoken(event.state)
+		{
+			tracing::trace!(state = ?event.state, "Not announcing a state the user has silenced");
+			return Ok(());
+		}
 		// enabled can only be 1 or 0, but is not a boolean over dbus
 		match (event.state, event.enabled == 1) {
 			(State::Focused, true) => focused(state, event).await?,
+			(State::Selected, enabled) => {
+				if enabled {
+					selected(state, event).await?;
+				}
+				if enabled != was_enabled {
+					selection_count(state, &a11y_prim).await;
+				}
+			}
+			(State::Busy, enabled) => busy(state, event, was_enabled, enabled).await?,
+			(State::Modal, enabled) => modal(state, event, enabled).await?,
+			// only cached items are looked at, since items are shown far too often to ask every one for its role
+			(State::Showing, true) if !was_enabled => {
+				if let Some(item) = state.cache.get(&a11y_prim) {
+					announce_tooltip(state, &item).await;
+				}
+			}
+			(State::Expanded, enabled) if enabled != was_enabled => {
+				expanded(state, event, enabled).await?;
+			}
+			(State::InvalidEntry, true) if !was_enabled => {
+				invalid_entry(state, &a11y_prim).await;
+			}
 			(state, enabled) => tracing::trace!(
 				"Ignoring state_changed event with unknown kind: {:?}/{}",
 				state,
@@ -490,53 +1275,1186 @@ mod state_changed {
 	}
 
 	#[tracing::instrument(level = "debug", skip(state), err)]
-	pub async fn focused(
+	pub async fn busy(
 		state: &ScreenReaderState,
 		event: &StateChangedEvent,
+		was_busy: bool,
+		now_busy: bool,
 	) -> eyre::Result<()> {
+		let Some(text) = busy_transition(was_busy, now_busy) else {
+			return Ok(());
+		};
 		let accessible = state.get_or_create_event_object_to_cache(event).await?;
-		if let Some(curr) = state.history_item(0).await {
-			if curr == accessible.object {
+		if accessible.role != Role::ProgressBar {
+			return Ok(());
+		}
+		if now_busy {
+			let now = Instant::now();
+			let mut last = state.last_busy_announcement.lock().await;
+			if !busy_rate_limit_elapsed(*last, now) {
+				tracing::trace!("Not announcing busy state; last announcement was too recent");
 				return Ok(());
 			}
+			*last = Some(now);
 		}
+		state.say(Priority::Message, text.to_string()).await;
+		Ok(())
+	}
 
-		let (name, description, relation) = tokio::try_join!(
-			accessible.name(),
-			accessible.description(),
-			accessible.get_relation_set(),
-		)?;
-		state.update_accessible(accessible.object.clone()).await;
-		tracing::debug!(
-			"Focus event received on: {:?} with role {}",
-			accessible.object.id,
-			accessible.role,
-		);
-		tracing::debug!("Relations: {:?}", relation);
-
-		state.say(
-			ssip_client_async::Priority::Text,
-			format!("{name}, {0}. {description}", accessible.role),
-		)
-		.await;
-
-		state.update_accessible(accessible.object).await;
+	/// Say whether a combo box was just opened or closed.
+	/// The options are read as the selection moves through them, from the selection events of the list that opened.
+	#[tracing::instrument(level = "debug", skip(state), err)]
+	pub async fn expanded(
+		state: &ScreenReaderState,
+		event: &StateChangedEvent,
+		now_expanded: bool,
+	) -> eyre::Result<()> {
+		let accessible = state.get_or_create_event_object_to_cache(event).await?;
+		if accessible.role != Role::ComboBox {
+			return Ok(());
+		}
+		// a list which opens again starts counting its options afresh
+		*state.autocomplete_count.lock().await = None;
+		let text = expansion_state(now_expanded);
+		state.say(state.priority(EventCategory::Focus, None), text.to_string())
+			.await;
 		Ok(())
 	}
-}
 
-#[cfg(test)]
-mod tests {
-	use crate::events::object::text_caret_moved::new_position;
-	use atspi_common::{Interface, InterfaceSet, Role, State, StateSet};
-	use atspi_connection::AccessibilityConnection;
-	use lazy_static::lazy_static;
-	use odilia_cache::{AccessiblePrimitive, Cache, CacheItem};
-	use std::sync::Arc;
-	use tokio_test::block_on;
+	/// Say when the focused form field fails validation while it is being filled in.
+	/// Fields which are not focused are left alone, since a page may check all of its fields at once.
+	pub async fn invalid_entry(state: &ScreenReaderState, field: &AccessiblePrimitive) {
+		if state.history_item(0).await.as_ref() != Some(field) {
+			return;
+		}
+		let Some(text) = form_field_states(StateSet::new(State::InvalidEntry)) else {
+			return;
+		};
+		state.say(state.priority(EventCategory::Focus, None), text).await;
+	}
 
-	static A11Y_PARAGRAPH_STRING: &str = "The AT-SPI (Assistive Technology Service Provider Interface) enables users of Linux to use their computer without sighted assistance. It was originally developed at Sun Microsystems, before they were purchased by Oracle.";
-	lazy_static! {
+	/// The level of a heading, from its `level` attribute, like 2 for a second-level heading.
+	/// `None` when the attribute is missing, or is not a level above 0.
+	pub fn heading_level(attributes: &HashMap<String, String>) -> Option<u32> {
+		attributes
+			.get("level")
+			.and_then(|level| level.trim().parse::<u32>().ok())
+			.filter(|level| *level > 0)
+	}
+
+	/// What is read for the role of a heading, like "heading level 2", or just the role when the level is not known.
+	pub fn heading_role_name(role_name: &str, level: Option<u32>) -> String {
+		match level {
+			Some(level) => format!("{role_name} level {level}"),
+			None => role_name.to_string(),
+		}
+	}
+
+	/// The word for whether an expandable item, like a combo box, is open.
+	pub fn expansion_state(expanded: bool) -> &'static str {
+		if expanded {
+			"expanded"
+		} else {
+			"collapsed"
+		}
+	}
+
+	/// What is read after the role of a combo box: its current value, and whether it is open.
+	/// Many combo boxes are named after their value, in which case the value is not repeated.
+	pub fn combo_box_announcement(name: &str, value: &str, states: StateSet) -> String {
+		let expansion = expansion_state(states.contains(State::Expanded));
+		match value.trim() {
+			"" => format!("no value, {expansion}"),
+			value if value == name.trim() => expansion.to_string(),
+			value => format!("{value}, {expansion}"),
+		}
+	}
+
+	/// Roles which are tracked as modal dialogs when they gain the modal state.
+	const MODAL_ROLES: [Role; 3] = [Role::Dialog, Role::Alert, Role::FileChooser];
+
+	/// Track the modal dialog which is open, and announce it when it appears.
+	#[tracing::instrument(level = "debug", skip(state), err)]
+	pub async fn modal(
+		state: &ScreenReaderState,
+		event: &StateChangedEvent,
+		now_modal: bool,
+	) -> eyre::Result<()> {
+		let a11y_prim = AccessiblePrimitive::from_event(event)?;
+		if !now_modal {
+			let mut active_modal = state.active_modal.lock().await;
+			if active_modal.as_ref() == Some(&a11y_prim) {
+				*active_modal = None;
+			}
+			return Ok(());
+		}
+		let accessible = state.get_or_create_event_object_to_cache(event).await?;
+		if !MODAL_ROLES.contains(&accessible.role) {
+			return Ok(());
+		}
+		*state.active_modal.lock().await = Some(a11y_prim);
+		let name = accessible.name().await?;
+		let role_name = state.config.presentation.role_name(accessible.role);
+		state.say(
+			state.priority(EventCategory::Focus, None),
+			format!("{role_name}, {name}"),
+		)
+		.await;
+		Ok(())
+	}
+
+	/// Whether a child which just became selected should be read as if it were focused.
+	/// This is the case when focus stays on a container (like a list) while arrowing through it only moves the selection.
+	pub fn should_read_selected(
+		follow_selection: bool,
+		selected_parent: &AccessiblePrimitive,
+		focused: Option<&AccessiblePrimitive>,
+	) -> bool {
+		follow_selection && focused == Some(selected_parent)
+	}
+
+	#[tracing::instrument(level = "debug", skip(state), err)]
+	pub async fn selected(
+		state: &ScreenReaderState,
+		event: &StateChangedEvent,
+	) -> eyre::Result<()> {
+		let accessible = state.get_or_create_event_object_to_cache(event).await?;
+		let focused = state.history_item(0).await;
+		if !should_read_selected(
+			state.config.navigation.selection_follows_focus,
+			&accessible.parent.key,
+			focused.as_ref(),
+		) {
+			return Ok(());
+		}
+		// focus stays on the container, so the history is left alone; otherwise the next selected sibling would not be read
+		speak_accessible(state, &accessible, None).await
+	}
+
+	/// How long the count of selected items is held back after a selection changes, so that selecting everything at once reads one count instead of one per item.
+	pub const SELECTION_COUNT_DEBOUNCE: Duration = Duration::from_millis(150);
+
+	/// How many of `children` are selected, out of how many there are.
+	pub fn selection_tally(children: &[CacheItem]) -> (usize, usize) {
+		let selected = children
+			.iter()
+			.filter(|child| child.states.contains(State::Selected))
+			.count();
+		(selected, children.len())
+	}
+
+	/// Read how many items of a multi-select container are selected, like "3 of 10 selected", after one of its children was selected or unselected.
+	/// Only containers which allow selecting several items, and which hold the focus, are counted.
+	async fn selection_count(state: &ScreenReaderState, child: &AccessiblePrimitive) {
+		let Some(container) = state.cache.parent_of(child) else {
+			return;
+		};
+		if !container.states.contains(State::Multiselectable) {
+			return;
+		}
+		let in_container = state
+			.history_item(0)
+			.await
+			.is_some_and(|focused| state.cache.is_within(&focused, &container.object));
+		if !in_container {
+			return;
+		}
+		let now = Instant::now();
+		*state.pending_selection_count.lock().await = Some(now);
+		tokio::time::sleep(SELECTION_COUNT_DEBOUNCE).await;
+		{
+			let mut pending = state.pending_selection_count.lock().await;
+			// another selection change came in while waiting; that one reads the count instead
+			if *pending != Some(now) {
+				return;
+			}
+			*pending = None;
+		}
+		// the selected state of each child is kept up to date in the cache, so it can be counted without asking the application
+		let keys: Vec<AccessiblePrimitive> =
+			container.children.iter().map(|child| child.key.clone()).collect();
+		let children: Vec<CacheItem> =
+			state.cache.get_all(&keys).into_iter().flatten().collect();
+		let (selected, total) = selection_tally(&children);
+		let text = format!("{} selected", state.number_format().position(selected, total));
+		state.say(Priority::Text, text).await;
+	}
+
+	/// What to do with a newly focused item.
+	#[derive(Debug, PartialEq, Eq)]
+	pub enum FocusDebounce {
+		/// The same item is already waiting to be announced, so this event is dropped.
+		Duplicate,
+		/// Another item is still waiting to be announced; it is dropped in favour of this one.
+		Supersede,
+		/// Nothing else is waiting, so this item is announced once the settle delay has passed.
+		Announce,
+	}
+
+	/// Decide what to do with a focus event on `new`, given the item still waiting to be announced and how long it has waited, if any.
+	/// Focus announcements are held back for `settle`, so that when an application moves focus through several items at once only the last is spoken.
+	pub fn focus_debounce(
+		pending: Option<(&AccessiblePrimitive, Duration)>,
+		new: &AccessiblePrimitive,
+		settle: Duration,
+	) -> FocusDebounce {
+		match pending {
+			Some((pending, elapsed)) if elapsed < settle && pending == new => {
+				FocusDebounce::Duplicate
+			}
+			Some((_, elapsed)) if elapsed < settle => FocusDebounce::Supersede,
+			_ => FocusDebounce::Announce,
+		}
+	}
+
+	#[tracing::instrument(level = "debug", skip(state), err)]
+	pub async fn focused(
+		state: &ScreenReaderState,
+		event: &StateChangedEvent,
+	) -> eyre::Result<()> {
+		let accessible = state.get_or_create_event_object_to_cache(event).await?;
+		// the items around the focus are what the next command is most likely to need
+		state.cache.warm_ancestors_async(accessible.object.clone());
+		let settle = Duration::from_millis(state.config.navigation.focus_settle_ms);
+		let now = Instant::now();
+		{
+			let mut pending = state.pending_focus.lock().await;
+			let waiting = pending
+				.as_ref()
+				.map(|pending| (&pending.object, now.duration_since(pending.at)));
+			match focus_debounce(waiting, &accessible.object, settle) {
+				FocusDebounce::Duplicate => return Ok(()),
+				FocusDebounce::Supersede => {
+					tracing::trace!("Focus moved again before the last focus was announced");
+				}
+				FocusDebounce::Announce => {}
+			}
+			*pending =
+				Some(PendingFocus { object: accessible.object.clone(), at: now });
+		}
+		tokio::time::sleep(settle).await;
+		{
+			let mut pending = state.pending_focus.lock().await;
+			let still_pending = pending.as_ref().is_some_and(|pending| {
+				pending.at == now && pending.object == accessible.object
+			});
+			if !still_pending {
+				return Ok(());
+			}
+			*pending = None;
+		}
+		let previous = state.history_item(0).await;
+		if previous.as_ref() == Some(&accessible.object) {
+			return Ok(());
+		}
+		let application =
+			application_announcement(state, previous.as_ref(), &accessible).await;
+		let form = form_announcement(state, previous.as_ref(), &accessible.object);
+		let table = table_announcement(state, previous.as_ref(), &accessible.object).await;
+		let prefix: Vec<String> =
+			application.into_iter().chain(form).chain(table).collect();
+		let prefix = (!prefix.is_empty()).then(|| prefix.join(". "));
+		state.update_accessible(accessible.object.clone()).await;
+		speak_accessible(state, &accessible, prefix.as_deref()).await?;
+		if let Err(e) =
+			announce_formatting(state, &accessible, CaretContext::FocusEntered).await
+		{
+			tracing::debug!(error = %e, "Could not read the formatting at the caret");
+		}
+		state.update_accessible(accessible.object).await;
+		Ok(())
+	}
+
+	/// Read out the name, role and description of an accessible.
+	/// Any `prefix` (like "entering form") is spoken first, as part of the same message so that neither interrupts the other.
+	#[tracing::instrument(level = "debug", skip(state), err)]
+	pub async fn speak_accessible(
+		state: &ScreenReaderState,
+		accessible: &CacheItem,
+		prefix: Option<&str>,
+	) -> eyre::Result<()> {
+		let (name, description, relation) = tokio::try_join!(
+			accessible.name(),
+			accessible.description(),
+			accessible.get_relation_set(),
+		)?;
+		tracing::debug!(
+			"Speaking: {:?} with role {}",
+			accessible.object.id,
+			accessible.role,
+		);
+		tracing::debug!("Relations: {:?}", relation);
+
+		let mut role_name =
+			state.config.presentation.role_name(accessible.role).to_string();
+		if VALUE_ROLES.contains(&accessible.role) {
+			match spoken_value(state, accessible).await {
+				Ok(value) => role_name = format!("{role_name}, {value}"),
+				Err(e) => tracing::debug!(error = %e, "Could not read the value"),
+			}
+		}
+		if accessible.role == Role::Heading {
+			match accessible.get_attributes().await {
+				Ok(attributes) => {
+					role_name = heading_role_name(
+						&role_name,
+						heading_level(&attributes),
+					);
+				}
+				Err(e) => {
+					tracing::debug!(error = %e, "Could not read the heading level")
+				}
+			}
+		}
+		if accessible.role == Role::ComboBox {
+			let combo_box =
+				combo_box_announcement(&name, &accessible.text, accessible.states);
+			role_name = format!("{role_name}, {combo_box}");
+		}
+		// states the user has silenced are left out of everything read about them below
+		let states = state.config.presentation.spoken_states(accessible.states);
+		if state.config.announcements.visited_links
+			&& is_visited_link(accessible.role, states)
+		{
+			role_name.push_str(", visited");
+		}
+		let disabled = state.config.presentation.disabled.trim();
+		if !disabled.is_empty() && is_disabled_control(accessible.role, accessible.states) {
+			role_name = format!("{role_name}, {disabled}");
+		}
+		if state.config.presentation.cell_position != CellPosition::Off
+			&& accessible.interfaces.contains(Interface::TableCell)
+		{
+			match spoken_cell_position(state, accessible).await {
+				Ok(Some(position)) => {
+					role_name = format!("{role_name}, {position}")
+				}
+				Ok(None) => {}
+				Err(e) => {
+					tracing::debug!(error = %e, "Could not read the cell position")
+				}
+			}
+		}
+		if let Some(form_field) = form_field_states(states) {
+			role_name = format!("{role_name}, {form_field}");
+		}
+		if GROUP_MEMBER_ROLES.contains(&accessible.role) {
+			let parent = state.cache.parent_of(&accessible.object);
+			let siblings = parent
+				.as_ref()
+				.and_then(|parent| parent.get_children().ok())
+				.unwrap_or_default();
+			let members = group_members(accessible, &relation, siblings);
+			let group_name = match &parent {
+				Some(parent) => parent.name().await.unwrap_or_default(),
+				None => String::new(),
+			};
+			if let Some(group) = group_announcement(
+				state.number_format(),
+				accessible,
+				&members,
+				&group_name,
+			) {
+				role_name = format!("{role_name}, {group}");
+			}
+		}
+		if take_verbosity(&state.verbose_next) == Verbosity::High {
+			let position = accessible.index.zip(state
+				.cache
+				.parent_of(&accessible.object)
+				.and_then(|parent| parent.children_num));
+			let details =
+				detailed_announcement(state.number_format(), states, position);
+			if !details.is_empty() {
+				role_name = format!("{role_name}, {details}");
+			}
+		}
+		let label = focus_label(
+			state.read_display_text.load(Ordering::Relaxed),
+			&name,
+			&accessible.text,
+		);
+		let announcement = focus_announcement(label, &role_name, &description);
+		let text = match prefix {
+			Some(prefix) => format!("{prefix}. {announcement}"),
+			None => announcement,
+		};
+		state.say(state.priority(EventCategory::Focus, None), text).await;
+		Ok(())
+	}
+
+	/// Roles which are read along with their place in the group of options they belong to.
+	const GROUP_MEMBER_ROLES: [Role; 2] = [Role::RadioButton, Role::CheckBox];
+
+	/// The options in the same group as `item`, including itself.
+	/// Applications list them in a [`RelationType::MemberOf`] relation; without one, the siblings of `item` with the same role are used.
+	pub fn group_members(
+		item: &CacheItem,
+		relations: &[(RelationType, Vec<CacheItem>)],
+		siblings: Vec<CacheItem>,
+	) -> Vec<CacheItem> {
+		if let Some((_, members)) = relations
+			.iter()
+			.find(|(relation, _)| *relation == RelationType::MemberOf)
+		{
+			return members.clone();
+		}
+		siblings.into_iter()
+			.filter(|sibling| sibling.role == item.role)
+			.collect()
+	}
+
+	/// Where `item` is in its group of options, whether it is chosen, and the group's name, like "option 2 of 4, selected, Color group".
+	/// Returns `None` for an option which is alone, since there is no group to speak of.
+	pub fn group_announcement(
+		format: &dyn NumberFormat,
+		item: &CacheItem,
+		members: &[CacheItem],
+		group_name: &str,
+	) -> Option<String> {
+		if members.len() < 2 {
+			return None;
+		}
+		let index = members.iter().position(|member| member.object == item.object)?;
+		let checked = item.states.contains(State::Checked);
+		let selection = match (item.role, checked) {
+			(Role::RadioButton, true) => "selected",
+			(Role::RadioButton, false) => "not selected",
+			(_, true) => "checked",
+			(_, false) => "not checked",
+		};
+		let mut parts = vec![
+			format!("option {}", format.position(index + 1, members.len())),
+			selection.to_string(),
+		];
+		if !group_name.trim().is_empty() {
+			parts.push(format!("{} group", group_name.trim()));
+		}
+		Some(parts.join(", "))
+	}
+
+	/// Whether an item is a link which the user has already followed.
+	pub fn is_visited_link(role: Role, states: StateSet) -> bool {
+		role == Role::Link && states.contains(State::Visited)
+	}
+
+	/// Roles which the user interacts with, and so are worth calling out when they can not be used.
+	const CONTROL_ROLES: [Role; 15] = [
+		Role::PushButton,
+		Role::ToggleButton,
+		Role::CheckBox,
+		Role::RadioButton,
+		Role::ComboBox,
+		Role::Entry,
+		Role::PasswordText,
+		Role::Slider,
+		Role::SpinButton,
+		Role::MenuItem,
+		Role::CheckMenuItem,
+		Role::RadioMenuItem,
+		Role::PageTab,
+		Role::Link,
+		Role::ListItem,
+	];
+
+	/// Whether an item is a control which does not respond to the user right now.
+	/// Toolkits mark usable controls as both enabled and sensitive, so missing either one means it is disabled.
+	pub fn is_disabled_control(role: Role, states: StateSet) -> bool {
+		CONTROL_ROLES.contains(&role)
+			&& !(states.contains(State::Enabled) && states.contains(State::Sensitive))
+	}
+
+	/// States of a form field which are read every time it is focused, and what is said for each.
+	const FORM_FIELD_STATES: [(State, &str); 2] =
+		[(State::Required, "required"), (State::InvalidEntry, "invalid entry")];
+
+	/// What is said about whether a form field must be filled in and whether what is in it was rejected, like "required, invalid entry".
+	/// `None` when it is neither.
+	pub fn form_field_states(states: StateSet) -> Option<String> {
+		let phrases: Vec<&str> = FORM_FIELD_STATES
+			.iter()
+			.filter(|(state, _)| states.contains(*state))
+			.map(|(_, phrase)| *phrase)
+			.collect();
+		(!phrases.is_empty()).then(|| phrases.join(", "))
+	}
+
+	/// How much detail a focus announcement goes into.
+	#[derive(Debug, PartialEq, Eq)]
+	pub enum Verbosity {
+		Normal,
+		/// Also read the states and position which are normally left out.
+		High,
+	}
+
+	/// The verbosity of the focus announcement about to be made.
+	/// A request for the next item in full detail is used up by this announcement, so the one after is back to normal.
+	pub fn take_verbosity(verbose_next: &AtomicBool) -> Verbosity {
+		if verbose_next.swap(false, Ordering::Relaxed) {
+			Verbosity::High
+		} else {
+			Verbosity::Normal
+		}
+	}
+
+	/// States which are only read when an item is read in full detail.
+	/// Whether a field is required is read every time, by [`form_field_states`].
+	const DETAIL_STATES: [(State, &str); 3] = [
+		(State::ReadOnly, "read only"),
+		(State::HasPopup, "has popup"),
+		(State::Multiselectable, "multi select"),
+	];
+
+	/// The extra details read in full detail: the states above, then where the item is among its siblings.
+	/// `position` is the index of the item in its parent, counting from 0, and how many children the parent has.
+	pub fn detailed_announcement(
+		format: &dyn NumberFormat,
+		states: StateSet,
+		position: Option<(usize, usize)>,
+	) -> String {
+		let mut details: Vec<String> = DETAIL_STATES
+			.iter()
+			.filter(|(state, _)| states.contains(*state))
+			.map(|(_, name)| (*name).to_string())
+			.collect();
+		if let Some((index, total)) = position.filter(|(index, total)| index < total) {
+			details.push(format.position(index + 1, total));
+		}
+		details.join(", ")
+	}
+
+	/// Roles whose current value is read along with them, from the `Value` interface.
+	pub const VALUE_ROLES: [Role; 3] = [Role::Slider, Role::SpinButton, Role::ScrollBar];
+
+	/// Put together what is said for the value of a slider, spin button or scroll bar.
+	/// Scroll bars are read as how far through their range they are; anything else is read with its range, like "50, range 0 to 100".
+	/// Applications without a known range give a maximum no bigger than the minimum, in which case only the value is read.
+	pub fn value_announcement(
+		format: &dyn NumberFormat,
+		role: Role,
+		current: f64,
+		minimum: f64,
+		maximum: f64,
+	) -> String {
+		if maximum <= minimum {
+			return current.to_string();
+		}
+		if role == Role::ScrollBar {
+			return format
+				.percentage((current - minimum) / (maximum - minimum) * 100.0);
+		}
+		format!("{current}, range {minimum} to {maximum}")
+	}
+
+	/// Fetch the current value of an accessible, worded by [`value_announcement`].
+	pub async fn spoken_value(
+		state: &ScreenReaderState,
+		accessible: &CacheItem,
+	) -> eyre::Result<String> {
+		let proxy = accessible
+			.object
+			.clone()
+			.into_accessible(state.atspi.connection())
+			.await?;
+		let value = proxy.to_value().await?;
+		let (current, minimum, maximum) = tokio::try_join!(
+			value.current_value(),
+			value.minimum_value(),
+			value.maximum_value(),
+		)?;
+		Ok(value_announcement(
+			state.number_format(),
+			accessible.role,
+			current,
+			minimum,
+			maximum,
+		))
+	}
+
+	/// Roles which count as fields when saying how many fields a form has.
+	const FORM_FIELD_ROLES: [Role; 9] = [
+		Role::Entry,
+		Role::PasswordText,
+		Role::CheckBox,
+		Role::RadioButton,
+		Role::ComboBox,
+		Role::SpinButton,
+		Role::Slider,
+		Role::ToggleButton,
+		Role::ListBox,
+	];
+
+	/// Moving focus into or out of a form.
+	#[derive(Debug, PartialEq, Eq)]
+	pub enum FormCrossing {
+		/// Focus moved into this form, possibly from an enclosing form.
+		Entered(AccessiblePrimitive),
+		/// Focus moved out of the innermost form it was in.
+		Left,
+	}
+
+	/// Work out whether focus moving from an item with `previous_ancestors` to one with `current_ancestors` crosses the boundary of a form.
+	/// Only the innermost form around each item matters, so moving between two fields of the same nested form is not a crossing.
+	pub fn form_crossing(
+		previous_ancestors: &[CacheItem],
+		current_ancestors: &[CacheItem],
+	) -> Option<FormCrossing> {
+		let forms = |ancestors: &[CacheItem]| -> Vec<AccessiblePrimitive> {
+			ancestors
+				.iter()
+				.filter(|item| item.role == Role::Form)
+				.map(AccessiblePrimitive::from)
+				.collect()
+		};
+		let previous_forms = forms(previous_ancestors);
+		let current_forms = forms(current_ancestors);
+		if previous_forms.first() == current_forms.first() {
+			return None;
+		}
+		match current_forms.first() {
+			Some(form) if !previous_forms.contains(form) => {
+				Some(FormCrossing::Entered(form.clone()))
+			}
+			_ => Some(FormCrossing::Left),
+		}
+	}
+
+	/// Count the fields inside a form, as far as the cache knows about them.
+	fn count_form_fields(state: &ScreenReaderState, form: &AccessiblePrimitive) -> usize {
+		let mut fields = 0;
+		let mut to_visit = vec![form.clone()];
+		while let Some(key) = to_visit.pop() {
+			let Some(item) = state.cache.get(&key) else {
+				continue;
+			};
+			if FORM_FIELD_ROLES.contains(&item.role) {
+				fields += 1;
+			}
+			to_visit.extend(item.children.into_iter().map(|child| child.key));
+		}
+		fields
+	}
+
+	/// Whether focus has moved into a different application than the one it was in.
+	/// Focus with no known application before it, like the first focus after starting, counts as entering one.
+	pub fn entered_application(
+		previous_app: Option<&AccessiblePrimitive>,
+		current_app: &AccessiblePrimitive,
+	) -> bool {
+		previous_app != Some(current_app)
+	}
+
+	/// The name of the application focus just moved into, if the user wants it read and focus came from another application.
+	async fn application_announcement(
+		state: &ScreenReaderState,
+		previous: Option<&AccessiblePrimitive>,
+		current: &CacheItem,
+	) -> Option<String> {
+		if !state.config.announcements.application_changes {
+			return None;
+		}
+		let previous_app = previous
+			.and_then(|previous| state.cache.get(previous))
+			.map(|item| item.app);
+		if !entered_application(previous_app.as_ref(), &current.app) {
+			return None;
+		}
+		let name = match current.app.clone().into_accessible(state.atspi.connection()).await
+		{
+			Ok(app) => app.name().await,
+			Err(e) => Err(e),
+		};
+		match name {
+			Ok(name) if !name.trim().is_empty() => Some(name.trim().to_string()),
+			Ok(_) => None,
+			Err(e) => {
+				tracing::debug!(error = %e, "Could not get the name of the focused application");
+				None
+			}
+		}
+	}
+
+	/// What to say, if anything, because focus moved from `previous` to `current`.
+	fn form_announcement(
+		state: &ScreenReaderState,
+		previous: Option<&AccessiblePrimitive>,
+		current: &AccessiblePrimitive,
+	) -> Option<String> {
+		if !state.config.announcements.form_boundaries {
+			return None;
+		}
+		let previous_ancestors = previous
+			.map(|previous| state.cache.ancestors(previous))
+			.unwrap_or_default();
+		let current_ancestors = state.cache.ancestors(current);
+		match form_crossing(&previous_ancestors, &current_ancestors)? {
+			FormCrossing::Entered(form) => {
+				let fields = count_form_fields(state, &form);
+				let fields = state.number_format().count(fields, "field", "fields");
+				Some(format!("entering form, {fields}"))
+			}
+			FormCrossing::Left => Some("leaving form".to_string()),
+		}
+	}
+
+	/// Roles of items holding rows and columns of cells.
+	const TABLE_ROLES: [Role; 2] = [Role::Table, Role::TreeTable];
+
+	/// The table focus just moved into, if it moved from outside of it into one of its cells.
+	/// Only the innermost table around each item matters, so moving between the cells of one table is not entering it.
+	pub fn entered_table(
+		previous_ancestors: &[CacheItem],
+		current_ancestors: &[CacheItem],
+	) -> Option<AccessiblePrimitive> {
+		let innermost = |ancestors: &[CacheItem]| {
+			ancestors
+				.iter()
+				.find(|item| TABLE_ROLES.contains(&item.role))
+				.map(AccessiblePrimitive::from)
+		};
+		let current = innermost(current_ancestors)?;
+		(innermost(previous_ancestors).as_ref() != Some(&current)).then_some(current)
+	}
+
+	/// Put together what is said about the size of a table, like "table, 5 columns, 20 rows".
+	/// A count the application does not know, which it gives as a negative number, is left out.
+	pub fn table_dimensions(format: &dyn NumberFormat, columns: i32, rows: i32) -> String {
+		let mut parts = vec!["table".to_string()];
+		if let Ok(columns) = usize::try_from(columns) {
+			parts.push(format.count(columns, "column", "columns"));
+		}
+		if let Ok(rows) = usize::try_from(rows) {
+			parts.push(format.count(rows, "row", "rows"));
+		}
+		parts.join(", ")
+	}
+
+	/// What to say, if anything, because focus moved from `previous` into a table around `current`.
+	async fn table_announcement(
+		state: &ScreenReaderState,
+		previous: Option<&AccessiblePrimitive>,
+		current: &AccessiblePrimitive,
+	) -> Option<String> {
+		if !state.config.announcements.table_dimensions {
+			return None;
+		}
+		let previous_ancestors = previous
+			.map(|previous| state.cache.ancestors(previous))
+			.unwrap_or_default();
+		let current_ancestors = state.cache.ancestors(current);
+		let table = entered_table(&previous_ancestors, &current_ancestors)?;
+		let size = async {
+			let table = table.into_accessible(state.atspi.connection()).await?;
+			let table = table.to_table().await?;
+			Ok::<_, OdiliaError>((table.ncolumns().await?, table.nrows().await?))
+		};
+		match size.await {
+			Ok((columns, rows)) => {
+				Some(table_dimensions(state.number_format(), columns, rows))
+			}
+			Err(e) => {
+				tracing::debug!(error = %e, "Could not get the size of the table");
+				None
+			}
+		}
+	}
+
+	/// The letters a spreadsheet uses for a column, counting from 1: A to Z, then AA, AB and so on.
+	/// Column 0 has no letters.
+	pub fn column_letters(column: usize) -> String {
+		let mut letters = Vec::new();
+		let mut rest = column;
+		while rest > 0 {
+			let letter = (rest - 1) % 26;
+			letters.push(char::from(b'A' + u8::try_from(letter).unwrap_or_default()));
+			rest = (rest - 1) / 26;
+		}
+		letters.iter().rev().collect()
+	}
+
+	/// What is said for the position of a table cell, from its row and column counting from 0, like "row 7, column 2" or "B7".
+	/// `None` when positions are not read.
+	pub fn cell_position_announcement(
+		format: &dyn NumberFormat,
+		mode: CellPosition,
+		row: usize,
+		column: usize,
+	) -> Option<String> {
+		let (row, column) = (row.checked_add(1)?, column.checked_add(1)?);
+		match mode {
+			CellPosition::Off => None,
+			CellPosition::RowAndColumn => Some(format!(
+				"row {}, column {}",
+				format.cardinal(row),
+				format.cardinal(column)
+			)),
+			CellPosition::Reference => {
+				Some(format!("{}{}", column_letters(column), format.cardinal(row)))
+			}
+		}
+	}
+
+	/// The position of a focused table cell, worded as the user chose.
+	async fn spoken_cell_position(
+		state: &ScreenReaderState,
+		accessible: &CacheItem,
+	) -> eyre::Result<Option<String>> {
+		let proxy = accessible
+			.object
+			.clone()
+			.into_accessible(state.atspi.connection())
+			.await?;
+		let (row, column) = proxy.to_table_cell().await?.position().await?;
+		Ok(cell_position_announcement(
+			state.number_format(),
+			state.config.presentation.cell_position,
+			usize::try_from(row)?,
+			usize::try_from(column)?,
+		))
+	}
+
+	/// What a focused item is called when it is read: its accessible name, or the text it shows when `display_text` is on.
+	/// Items which show no text are read by their name either way.
+	pub fn focus_label<'a>(display_text: bool, name: &'a str, text: &'a str) -> &'a str {
+		if display_text && !text.trim().is_empty() {
+			text
+		} else {
+			name
+		}
+	}
+
+	/// Put together what is said when an item is focused.
+	pub fn focus_announcement(name: &str, role_name: &str, description: &str) -> String {
+		format!("{name}, {role_name}. {description}")
+	}
+}
+
+mod active_descendant_changed {
+	use crate::state::ScreenReaderState;
+	use atspi_common::events::object::ActiveDescendantChangedEvent;
+	use odilia_cache::AccessiblePrimitive;
+
+	use super::state_changed::speak_accessible;
+
+	/// The newly active item inside a composite widget, like the current cell of a grid.
+	/// Returns `None` when the widget no longer has an active item, which is signalled with the null object.
+	pub fn active_descendant(
+		event: &ActiveDescendantChangedEvent,
+	) -> Option<AccessiblePrimitive> {
+		if event.child.path.as_str().ends_with("/null") {
+			return None;
+		}
+		Some(event.child.clone().into())
+	}
+
+	#[tracing::instrument(level = "debug", skip(state), err)]
+	pub async fn dispatch(
+		state: &ScreenReaderState,
+		event: &ActiveDescendantChangedEvent,
+	) -> eyre::Result<()> {
+		let Some(descendant) = active_descendant(event) else {
+			tracing::trace!("Active descendant was cleared; nothing to announce.");
+			return Ok(());
+		};
+		if state.history_item(0).await.as_ref() == Some(&descendant) {
+			return Ok(());
+		}
+		let accessible = state.get_or_create_cache_item(descendant).await?;
+		speak_accessible(state, &accessible, None).await?;
+		// the container keeps real focus, but the active item is what the user is interacting with
+		state.update_accessible(accessible.object).await;
+		Ok(())
+	}
+}
+
+mod property_change {
+	use crate::state::ScreenReaderState;
+	use atspi_common::events::object::PropertyChangeEvent;
+	use odilia_cache::AccessiblePrimitive;
+	use ssip_client_async::Priority;
+	use std::time::{Duration, Instant};
+
+	use super::state_changed::{spoken_value, VALUE_ROLES};
+
+	/// The shortest time between two value announcements, so dragging a slider does not queue up every step along the way.
+	pub const VALUE_ANNOUNCEMENT_INTERVAL: Duration = Duration::from_millis(250);
+
+	/// How much longer a value change at `now` has to wait before it can be read; zero when it can be read straight away.
+	pub fn value_rate_limit_remaining(last: Option<Instant>, now: Instant) -> Duration {
+		last.map_or(Duration::ZERO, |last| {
+			VALUE_ANNOUNCEMENT_INTERVAL
+				.saturating_sub(now.saturating_duration_since(last))
+		})
+	}
+
+	#[tracing::instrument(level = "debug", skip(state), err)]
+	pub async fn dispatch(
+		state: &ScreenReaderState,
+		event: &PropertyChangeEvent,
+	) -> eyre::Result<()> {
+		match event.property.as_str() {
+			"accessible-value" => value_changed(state, event).await?,
+			property => tracing::trace!(property, "Ignoring change of property"),
+		}
+		Ok(())
+	}
+
+	/// Read the new value of the focused slider, spin button or scroll bar.
+	#[tracing::instrument(level = "debug", skip(state), err)]
+	pub async fn value_changed(
+		state: &ScreenReaderState,
+		event: &PropertyChangeEvent,
+	) -> eyre::Result<()> {
+		// values change all the time elsewhere, like in progress bars; only the control being used is read
+		let a11y_prim = AccessiblePrimitive::from_event(event)?;
+		if state.history_item(0).await.as_ref() != Some(&a11y_prim) {
+			return Ok(());
+		}
+		let accessible = state.get_or_create_event_object_to_cache(event).await?;
+		if !VALUE_ROLES.contains(&accessible.role) {
+			return Ok(());
+		}
+		let now = Instant::now();
+		let remaining = value_rate_limit_remaining(
+			*state.last_value_announcement.lock().await,
+			now,
+		);
+		if remaining.is_zero() {
+			*state.pending_value.lock().await = None;
+		} else {
+			// the value the user stops on must still be read, so the newest change held back is read once the interval is over
+			*state.pending_value.lock().await = Some(now);
+			tracing::trace!("Holding back value; last announcement was too recent");
+			tokio::time::sleep(remaining).await;
+			let mut pending = state.pending_value.lock().await;
+			// a newer change came in while waiting; that one reads the value instead
+			if *pending != Some(now) {
+				return Ok(());
+			}
+			*pending = None;
+		}
+		*state.last_value_announcement.lock().await = Some(Instant::now());
+		state.say(Priority::Text, spoken_value(state, &accessible).await?)
+			.await;
+		Ok(())
+	}
+}
+
+mod selection_changed {
+	use crate::state::ScreenReaderState;
+	use atspi_common::{events::object::SelectionChangedEvent, Role};
+	use odilia_cache::{AccessiblePrimitive, Convertable};
+
+	use super::state_changed::speak_accessible;
+
+	/// Containers whose newly selected child is read as if it had been focused.
+	/// Other containers, like lists, are handled through the selected state of their items instead.
+	const SELECTION_CONTAINER_ROLES: [Role; 4] =
+		[Role::Menu, Role::MenuBar, Role::PageTabList, Role::ComboBox];
+
+	/// Whether a change of selection inside a container with this role should be announced.
+	pub fn announces_selection(role: Role) -> bool {
+		SELECTION_CONTAINER_ROLES.contains(&role)
+	}
+
+	#[tracing::instrument(level = "debug", skip(state), err)]
+	pub async fn dispatch(
+		state: &ScreenReaderState,
+		event: &SelectionChangedEvent,
+	) -> eyre::Result<()> {
+		let container = state.get_or_create_event_object_to_cache(event).await?;
+		if !announces_selection(container.role) {
+			return Ok(());
+		}
+		let container = container.object.into_accessible(state.atspi.connection()).await?;
+		let selection = container.to_selection().await?;
+		if selection.nselected_children().await? == 0 {
+			tracing::trace!("Selection was cleared; nothing to announce.");
+			return Ok(());
+		}
+		let selected: AccessiblePrimitive = selection.get_selected_child(0).await?.into();
+		if state.history_item(0).await.as_ref() == Some(&selected) {
+			return Ok(());
+		}
+		let accessible = state.get_or_create_cache_item(selected).await?;
+		speak_accessible(state, &accessible, None).await?;
+		state.update_accessible(accessible.object).await;
+		Ok(())
+	}
+}
+
+mod text_attributes_changed {
+	use crate::state::ScreenReaderState;
+	use crate::text_formatting::{announce_formatting, CaretContext};
+	use atspi_common::events::object::TextAttributesChangedEvent;
+	use odilia_cache::AccessiblePrimitive;
+
+	/// Whether a change of text attributes should be read: only formatting in the focused item is, and only when formatting is read at all.
+	pub fn announces_attribute_change(
+		enabled: bool,
+		changed: &AccessiblePrimitive,
+		focused: Option<&AccessiblePrimitive>,
+	) -> bool {
+		enabled && focused == Some(changed)
+	}
+
+	#[tracing::instrument(level = "debug", skip(state), err)]
+	pub async fn dispatch(
+		state: &ScreenReaderState,
+		event: &TextAttributesChangedEvent,
+	) -> eyre::Result<()> {
+		let changed = AccessiblePrimitive::from_event(event)?;
+		let focused = state.history_item(0).await;
+		let enabled = state.config.announcements.reads_text_attributes();
+		if !announces_attribute_change(enabled, &changed, focused.as_ref()) {
+			return Ok(());
+		}
+		let item = state.get_or_create_event_object_to_cache(event).await?;
+		announce_formatting(state, &item, CaretContext::AttributesChanged).await?;
+		Ok(())
+	}
+}
+
+mod attributes_changed {
+	use crate::events::object::text_changed::update_live_attributes;
+	use crate::state::ScreenReaderState;
+	use atspi_common::events::object::AttributesChangedEvent;
+	use odilia_cache::AccessiblePrimitive;
+	use std::collections::HashMap;
+
+	/// Whether the attributes of `changed` need fetching again: only those of items whose live region attributes are remembered do.
+	pub fn refreshes_attributes(
+		cached: &HashMap<AccessiblePrimitive, HashMap<String, String>>,
+		changed: &AccessiblePrimitive,
+	) -> bool {
+		cached.contains_key(changed)
+	}
+
+	/// Fetch the attributes of an item again when they change, so that a region whose `aria-live` was just turned on, off, or made more urgent is read the new way.
+	#[tracing::instrument(level = "debug", skip(state), err)]
+	pub async fn dispatch(
+		state: &ScreenReaderState,
+		event: &AttributesChangedEvent,
+	) -> eyre::Result<()> {
+		let changed = AccessiblePrimitive::from_event(event)?;
+		if !refreshes_attributes(&state.live_attributes.lock().await, &changed) {
+			return Ok(());
+		}
+		let attributes = changed
+			.clone()
+			.into_accessible(state.atspi.connection())
+			.await?
+			.get_attributes()
+			.await?;
+		let live = update_live_attributes(
+			&mut state.live_attributes.lock().await,
+			changed,
+			&attributes,
+		);
+		tracing::debug!(?live, "Refreshed live region attributes");
+		Ok(())
+	}
+}
+
+mod table_reordered {
+	use crate::events::commands::{object_info, TableLine};
+	use crate::state::ScreenReaderState;
+	use atspi_common::EventProperties;
+	use odilia_cache::AccessiblePrimitive;
+	use ssip_client_async::Priority;
+
+	/// What is said when the rows or columns of the table the user is in are reordered.
+	pub fn reorder_announcement(line: TableLine) -> &'static str {
+		match line {
+			TableLine::Row => "rows reordered",
+			TableLine::Column => "columns reordered",
+		}
+	}
+
+	/// Fetch the new order of the table's children, so sibling navigation follows it, and tell the user if they are in the table.
+	#[tracing::instrument(level = "debug", skip(state, event), err)]
+	pub async fn dispatch<E: EventProperties>(
+		state: &ScreenReaderState,
+		event: &E,
+		line: TableLine,
+	) -> eyre::Result<()> {
+		let table = AccessiblePrimitive::from_event(event)?;
+		let children: Vec<AccessiblePrimitive> = table
+			.clone()
+			.into_accessible(state.atspi.connection())
+			.await?
+			.get_children()
+			.await?
+			.into_iter()
+			.map(AccessiblePrimitive::from)
+			.collect();
+		if !state.cache.reorder_children(&table, children)? {
+			tracing::trace!("The reordered table is not cached; nothing to update.");
+			return Ok(());
+		}
+		let in_table = state
+			.history_item(0)
+			.await
+			.is_some_and(|focused| state.cache.is_within(&focused, &table));
+		if in_table {
+			// low priority, since the user did not ask for this and is probably in the middle of reading
+			state.say(Priority::Notification, reorder_announcement(line).to_string())
+				.await;
+		}
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::events::commands::TableLine;
+	use crate::events::object::active_descendant_changed::active_descendant;
+	use crate::events::object::attributes_changed::refreshes_attributes;
+	use crate::events::object::children_changed::{
+		autocomplete_announcement, tooltip_priority,
+	};
+	use crate::events::object::property_change::{
+		value_rate_limit_remaining, VALUE_ANNOUNCEMENT_INTERVAL,
+	};
+	use crate::events::object::selection_changed::announces_selection;
+	use crate::events::object::state_changed::{
+		busy_rate_limit_elapsed, busy_transition, cell_position_announcement,
+		column_letters, combo_box_announcement, detailed_announcement, entered_application,
+		entered_table, focus_announcement, focus_debounce, focus_label, form_crossing,
+		form_field_states, group_announcement, group_members, heading_level,
+		heading_role_name, is_disabled_control, is_visited_link, selection_tally,
+		should_read_selected, table_dimensions, take_verbosity, value_announcement,
+		FocusDebounce, FormCrossing, Verbosity, BUSY_ANNOUNCEMENT_INTERVAL,
+	};
+	use crate::events::object::table_reordered::reorder_announcement;
+	use crate::events::object::text_attributes_changed::announces_attribute_change;
+	use crate::events::object::text_caret_moved::{
+		crossed_offset, indentation_changed, indentation_columns, is_blank_line,
+		new_position, skip_blank_lines, word_at,
+	};
+	use crate::events::object::text_changed::{
+		completed_word, replacement_announcement, text_change_announcement, typing_echo,
+		unreplaced_deletion, update_live_attributes, TextChange, REPLACEMENT_WINDOW,
+	};
+	use crate::number_format::English;
+	use crate::state::PendingDeletion;
+	use atspi_common::{Granularity, Interface, InterfaceSet, Role, State, StateSet};
+	use atspi_connection::AccessibilityConnection;
+	use lazy_static::lazy_static;
+	use odilia_cache::{AccessiblePrimitive, Cache, CacheItem};
+	use odilia_common::settings::{
+		announcements::{TextChangeTemplates, TooltipAnnouncement, TypingEcho},
+		presentation::CellPosition,
+	};
+	use ssip_client_async::Priority;
+	use std::collections::HashMap;
+	use std::sync::atomic::{AtomicBool, Ordering};
+	use std::sync::Arc;
+	use std::time::{Duration, Instant};
+	use tokio_test::block_on;
+
+	static A11Y_PARAGRAPH_STRING: &str = "The AT-SPI (Assistive Technology Service Provider Interface) enables users of Linux to use their computer without sighted assistance. It was originally developed at Sun Microsystems, before they were purchased by Oracle.";
+	lazy_static! {
 		static ref ZBUS_CONN: AccessibilityConnection =
 			#[allow(clippy::unwrap_used)]
 			block_on(AccessibilityConnection::new()).unwrap();
@@ -623,6 +2541,7 @@ mod tests {
 					ANSWER_VALUES[$idx].1.clone(),
 					ANSWER_VALUES[$idx].2.try_into().unwrap(),
 					ANSWER_VALUES[$idx].3.try_into().unwrap(),
+					Granularity::Line,
 				))
 				.unwrap(),
 				ANSWER_VALUES[$idx].4.to_string(),
@@ -666,4 +2585,783 @@ mod tests {
 	fn test_text_navigation_full_item_back_to_front() {
 		check_answer_values!(8);
 	}
+	#[test]
+	fn test_completed_word_on_space() {
+		assert_eq!(completed_word("Hello world", " ", 11), Some("world".to_string()));
+	}
+	#[test]
+	fn test_completed_word_on_punctuation_mid_text() {
+		assert_eq!(completed_word("one two three", ",", 7), Some("two".to_string()));
+	}
+	#[test]
+	fn test_completed_word_not_at_boundary() {
+		assert_eq!(completed_word("Hello", "o", 5), None);
+		assert_eq!(completed_word("Hello ", " ", 6), None);
+		assert_eq!(completed_word("", " ", 0), None);
+	}
+	#[test]
+	fn test_text_change_announcement() {
+		let templates = TextChangeTemplates::default();
+		let long = "x".repeat(200);
+		let medium = "x".repeat(100);
+		assert_eq!(
+			text_change_announcement(&templates, TextChange::Inserted, "New message"),
+			Some("New message".to_string())
+		);
+		assert_eq!(
+			text_change_announcement(&templates, TextChange::Deleted, "foo"),
+			Some("deleted foo".to_string())
+		);
+		assert_eq!(text_change_announcement(&templates, TextChange::Deleted, " \n"), None);
+		// nothing is summarized by default
+		assert_eq!(
+			text_change_announcement(&templates, TextChange::Inserted, &long),
+			Some(long.clone())
+		);
+		let summarized = TextChangeTemplates {
+			summarize_over: 100,
+			..TextChangeTemplates::default()
+		};
+		assert_eq!(
+			text_change_announcement(&summarized, TextChange::Inserted, &long),
+			Some("inserted 200 characters".to_string())
+		);
+		assert_eq!(
+			text_change_announcement(&summarized, TextChange::Deleted, &long),
+			Some("deleted 200 characters".to_string())
+		);
+		assert_eq!(
+			text_change_announcement(&summarized, TextChange::Deleted, &medium),
+			Some(format!("deleted {medium}"))
+		);
+		let silent = TextChangeTemplates {
+			deleted: String::new(),
+			..TextChangeTemplates::default()
+		};
+		assert_eq!(text_change_announcement(&silent, TextChange::Deleted, "foo"), None);
+	}
+	#[test]
+	fn test_typing_echo_modes() {
+		assert_eq!(typing_echo(TypingEcho::None, "Hi", " ", 2), None);
+		assert_eq!(typing_echo(TypingEcho::Char, "Hi", " ", 2), Some(" ".to_string()));
+		assert_eq!(typing_echo(TypingEcho::Word, "Hi", "t", 2), None);
+		assert_eq!(typing_echo(TypingEcho::Word, "Hi", " ", 2), Some("Hi".to_string()));
+		assert_eq!(typing_echo(TypingEcho::Both, "Hi", "t", 2), Some("t".to_string()));
+		assert_eq!(typing_echo(TypingEcho::Both, "Hi", " ", 2), Some("Hi".to_string()));
+	}
+	#[test]
+	fn test_typing_echo_ignores_pasted_text() {
+		assert_eq!(typing_echo(TypingEcho::Both, "Hi", " there", 2), None);
+	}
+	#[test]
+	fn test_busy_transition() {
+		assert_eq!(busy_transition(false, true), Some("loading"));
+		assert_eq!(busy_transition(true, false), Some("done"));
+		assert_eq!(busy_transition(true, true), None);
+		assert_eq!(busy_transition(false, false), None);
+	}
+	#[test]
+	fn test_busy_rate_limit() {
+		let now = Instant::now();
+		assert!(busy_rate_limit_elapsed(None, now));
+		assert!(!busy_rate_limit_elapsed(Some(now), now + Duration::from_millis(500)));
+		assert!(busy_rate_limit_elapsed(Some(now), now + BUSY_ANNOUNCEMENT_INTERVAL));
+	}
+	#[test]
+	fn test_value_announcement() {
+		assert_eq!(
+			value_announcement(&English, Role::Slider, 50.0, 0.0, 100.0),
+			"50, range 0 to 100"
+		);
+		assert_eq!(
+			value_announcement(&English, Role::SpinButton, 2.5, -10.0, 10.0),
+			"2.5, range -10 to 10"
+		);
+		assert_eq!(
+			value_announcement(&English, Role::ScrollBar, 150.0, 100.0, 300.0),
+			"25 percent"
+		);
+		assert_eq!(value_announcement(&English, Role::SpinButton, 7.0, 0.0, 0.0), "7");
+		assert_eq!(value_announcement(&English, Role::ScrollBar, 7.0, 5.0, 5.0), "7");
+	}
+	#[test]
+	fn test_value_rate_limit() {
+		let now = Instant::now();
+		assert_eq!(value_rate_limit_remaining(None, now), Duration::ZERO);
+		assert_eq!(
+			value_rate_limit_remaining(Some(now), now + Duration::from_millis(100)),
+			VALUE_ANNOUNCEMENT_INTERVAL - Duration::from_millis(100)
+		);
+		assert_eq!(
+			value_rate_limit_remaining(Some(now), now + VALUE_ANNOUNCEMENT_INTERVAL),
+			Duration::ZERO
+		);
+	}
+	#[test]
+	fn test_indentation_columns() {
+		assert_eq!(indentation_columns("fn main() {"), 0);
+		assert_eq!(indentation_columns("    let x = 1;"), 4);
+		assert_eq!(indentation_columns("\t\tx"), 8);
+		assert_eq!(indentation_columns("  \tx"), 4);
+	}
+	#[test]
+	fn test_blank_lines() {
+		assert!(is_blank_line(""));
+		assert!(is_blank_line(" \t "));
+		assert!(!is_blank_line("  x"));
+	}
+	#[test]
+	fn test_skip_blank_lines() {
+		// lines start at 0, 6, 7, 10, 11 and 18
+		let text = "first\n\n  \n\nsecond\n";
+		assert_eq!(skip_blank_lines(text, 6, true), Some(11));
+		assert_eq!(skip_blank_lines(text, 8, true), Some(11));
+		assert_eq!(skip_blank_lines(text, 10, false), Some(0));
+		assert_eq!(skip_blank_lines(text, 7, false), Some(0));
+		// lines with text are not skipped
+		assert_eq!(skip_blank_lines(text, 0, true), None);
+		assert_eq!(skip_blank_lines(text, 13, false), None);
+		// nothing but blank lines past the end, so the caret stays
+		assert_eq!(skip_blank_lines(text, 18, true), None);
+		assert_eq!(skip_blank_lines("\n\nonly", 0, false), None);
+	}
+	#[test]
+	fn test_indentation_changed() {
+		let levels = [0, 4, 8, 8, 4, 4, 0, 0];
+		let mut previous = None;
+		let changes: Vec<bool> = levels
+			.into_iter()
+			.map(|level| {
+				let changed = indentation_changed(previous, level);
+				previous = Some(level);
+				changed
+			})
+			.collect();
+		assert_eq!(changes, [false, true, true, false, true, false, true, false]);
+		assert!(indentation_changed(None, 4));
+	}
+	#[test]
+	fn test_should_read_selected() {
+		let list = AccessiblePrimitive {
+			id: "/org/a11y/atspi/accessible/10".to_string(),
+			sender: ":1.2".into(),
+		};
+		let other = AccessiblePrimitive {
+			id: "/org/a11y/atspi/accessible/20".to_string(),
+			sender: ":1.2".into(),
+		};
+		assert!(should_read_selected(true, &list, Some(&list)));
+		assert!(!should_read_selected(false, &list, Some(&list)));
+		assert!(!should_read_selected(true, &list, Some(&other)));
+		assert!(!should_read_selected(true, &list, None));
+	}
+	#[test]
+	fn test_delete_then_insert_is_a_replacement() {
+		let now = Instant::now();
+		let deletion = PendingDeletion { text: "teh".to_string(), start_pos: 4, at: now };
+		assert_eq!(
+			replacement_announcement(
+				&deletion,
+				4,
+				"the",
+				now + Duration::from_millis(5)
+			),
+			Some("changed teh to the".to_string())
+		);
+	}
+	#[test]
+	fn test_unrelated_insert_is_not_a_replacement() {
+		let now = Instant::now();
+		let deletion = PendingDeletion { text: "teh".to_string(), start_pos: 4, at: now };
+		// somewhere else in the text
+		assert_eq!(replacement_announcement(&deletion, 9, "the", now), None);
+		// too long after the deletion
+		assert_eq!(
+			replacement_announcement(
+				&deletion,
+				4,
+				"the",
+				now + REPLACEMENT_WINDOW + Duration::from_millis(1)
+			),
+			None
+		);
+		// the same text put back
+		assert_eq!(replacement_announcement(&deletion, 4, "teh", now), None);
+	}
+	#[test]
+	fn test_unreplaced_deletion() {
+		let object = AccessiblePrimitive {
+			id: "/org/a11y/atspi/accessible/1".to_string(),
+			sender: ":1.2".into(),
+		};
+		let now = Instant::now();
+		let later = now + Duration::from_millis(5);
+		let mut pending = HashMap::new();
+		// replaced by an insertion, which took it out already
+		assert!(unreplaced_deletion(&mut pending, &object, now).is_none());
+		// a newer deletion is read by its own event
+		pending.insert(
+			object.clone(),
+			PendingDeletion { text: "a".to_string(), start_pos: 1, at: later },
+		);
+		assert!(unreplaced_deletion(&mut pending, &object, now).is_none());
+		assert_eq!(
+			unreplaced_deletion(&mut pending, &object, later)
+				.map(|deletion| deletion.text),
+			Some("a".to_string())
+		);
+		assert!(pending.is_empty());
+	}
+	#[test]
+	fn test_focus_debounce() {
+		let menu = AccessiblePrimitive {
+			id: "/org/a11y/atspi/accessible/1".to_string(),
+			sender: ":1.2".into(),
+		};
+		let menu_item = AccessiblePrimitive {
+			id: "/org/a11y/atspi/accessible/2".to_string(),
+			sender: ":1.2".into(),
+		};
+		let settle = Duration::from_millis(50);
+		let soon = Duration::from_millis(10);
+		assert_eq!(focus_debounce(None, &menu, settle), FocusDebounce::Announce);
+		assert_eq!(
+			focus_debounce(Some((&menu, soon)), &menu_item, settle),
+			FocusDebounce::Supersede
+		);
+		assert_eq!(
+			focus_debounce(Some((&menu, soon)), &menu, settle),
+			FocusDebounce::Duplicate
+		);
+		assert_eq!(
+			focus_debounce(Some((&menu, settle)), &menu_item, settle),
+			FocusDebounce::Announce
+		);
+		assert_eq!(
+			focus_debounce(Some((&menu, settle)), &menu, settle),
+			FocusDebounce::Announce
+		);
+	}
+	#[test]
+	fn test_focus_settle_delay_is_configurable() {
+		let menu = AccessiblePrimitive {
+			id: "/org/a11y/atspi/accessible/1".to_string(),
+			sender: ":1.2".into(),
+		};
+		let menu_item = AccessiblePrimitive {
+			id: "/org/a11y/atspi/accessible/2".to_string(),
+			sender: ":1.2".into(),
+		};
+		let elapsed = Duration::from_millis(120);
+		// a slow application still has its correction caught with a longer delay
+		assert_eq!(
+			focus_debounce(
+				Some((&menu, elapsed)),
+				&menu_item,
+				Duration::from_millis(300)
+			),
+			FocusDebounce::Supersede
+		);
+		assert_eq!(
+			focus_debounce(
+				Some((&menu, elapsed)),
+				&menu_item,
+				Duration::from_millis(50)
+			),
+			FocusDebounce::Announce
+		);
+		// without a delay, every focus is read straight away
+		assert_eq!(
+			focus_debounce(Some((&menu, Duration::ZERO)), &menu, Duration::ZERO),
+			FocusDebounce::Announce
+		);
+	}
+	#[test]
+	fn test_combo_box_announcement() {
+		let collapsed = StateSet::new(State::Focused | State::Expandable);
+		let expanded = StateSet::new(State::Focused | State::Expandable | State::Expanded);
+		assert_eq!(combo_box_announcement("Fruit", "Apple", collapsed), "Apple, collapsed");
+		assert_eq!(combo_box_announcement("Fruit", "Apple", expanded), "Apple, expanded");
+		assert_eq!(combo_box_announcement("Apple", "Apple", collapsed), "collapsed");
+		assert_eq!(combo_box_announcement("Fruit", " ", expanded), "no value, expanded");
+	}
+	#[test]
+	fn test_verbose_next_is_used_up_by_one_announcement() {
+		let verbose_next = AtomicBool::new(false);
+		assert_eq!(take_verbosity(&verbose_next), Verbosity::Normal);
+		verbose_next.store(true, Ordering::Relaxed);
+		assert_eq!(take_verbosity(&verbose_next), Verbosity::High);
+		assert_eq!(take_verbosity(&verbose_next), Verbosity::Normal);
+	}
+	#[test]
+	fn test_detailed_announcement() {
+		let states = StateSet::new(State::Focused | State::ReadOnly | State::HasPopup);
+		assert_eq!(
+			detailed_announcement(&English, states, Some((2, 7))),
+			"read only, has popup, 3 of 7"
+		);
+		// required fields are already read without asking for detail
+		let states = StateSet::new(State::Focused | State::Required);
+		assert_eq!(detailed_announcement(&English, states, None), "");
+		assert_eq!(
+			detailed_announcement(&English, StateSet::empty(), Some((0, 1))),
+			"1 of 1"
+		);
+		assert_eq!(detailed_announcement(&English, StateSet::empty(), Some((4, 2))), "");
+	}
+	#[test]
+	fn test_selection_tally() {
+		let mut children: Vec<CacheItem> = (0..10)
+			.map(|index| tree_item(&format!("option-{index}"), Role::ListItem))
+			.collect();
+		assert_eq!(selection_tally(&children), (0, 10));
+		for index in [1, 4, 7] {
+			children[index].states.insert(State::Selected);
+		}
+		// items which can be selected, but are not, do not count
+		children[2].states.insert(State::Selectable);
+		assert_eq!(selection_tally(&children), (3, 10));
+		for child in &mut children {
+			child.states.insert(State::Selected);
+		}
+		assert_eq!(selection_tally(&children), (10, 10));
+		assert_eq!(selection_tally(&[]), (0, 0));
+	}
+	#[test]
+	fn test_autocomplete_announcement() {
+		let mut options: Vec<CacheItem> = ["Apple", "Apricot", "Avocado"]
+			.iter()
+			.map(|name| {
+				let mut option =
+					tree_item(&format!("option-{name}"), Role::ListItem);
+				option.text = (*name).to_string();
+				option
+			})
+			.collect();
+		// nothing is picked yet, so the first option would be
+		assert_eq!(
+			autocomplete_announcement(&English, None, &options),
+			Some("3 options, Apple".to_string())
+		);
+		options[1].states.insert(State::Selected);
+		assert_eq!(
+			autocomplete_announcement(&English, Some(5), &options),
+			Some("3 options, Apricot".to_string())
+		);
+		// typing which leaves as many options is not read again
+		assert_eq!(autocomplete_announcement(&English, Some(3), &options), None);
+		options.truncate(1);
+		assert_eq!(
+			autocomplete_announcement(&English, Some(3), &options),
+			Some("1 option, Apple".to_string())
+		);
+		assert_eq!(
+			autocomplete_announcement(&English, Some(1), &[]),
+			Some("0 options".to_string())
+		);
+	}
+	#[test]
+	fn test_is_visited_link() {
+		let visited = StateSet::new(State::Focused | State::Visited);
+		let unvisited = StateSet::new(State::Focused | State::Focusable);
+		assert!(is_visited_link(Role::Link, visited));
+		assert!(!is_visited_link(Role::Link, unvisited));
+		assert!(!is_visited_link(Role::PushButton, visited));
+		assert!(!is_visited_link(Role::Paragraph, unvisited));
+	}
+	#[test]
+	fn test_object_info() {
+		assert_eq!(
+			object_info(&A11Y_PARAGRAPH_ITEM),
+			"sender :1.2; path /org/a11y/atspi/accessible/1; role paragraph; \
+			states Enabled, Opaque, Showing, Visible; \
+			interfaces Accessible, Collection, Component, Hyperlink, Hypertext, Text"
+		);
+		let bare = CacheItem {
+			states: StateSet::empty(),
+			interfaces: InterfaceSet::empty(),
+			..A11Y_PARAGRAPH_ITEM.clone()
+		};
+		assert!(object_info(&bare).ends_with("states none; interfaces none"));
+	}
+	#[test]
+	fn test_form_field_states() {
+		assert_eq!(
+			form_field_states(StateSet::new(State::Focused | State::Editable)),
+			None
+		);
+		assert_eq!(
+			form_field_states(StateSet::new(State::Focused | State::Required))
+				.as_deref(),
+			Some("required")
+		);
+		assert_eq!(
+			form_field_states(StateSet::new(State::InvalidEntry)).as_deref(),
+			Some("invalid entry")
+		);
+		assert_eq!(
+			form_field_states(StateSet::new(State::Required | State::InvalidEntry))
+				.as_deref(),
+			Some("required, invalid entry")
+		);
+	}
+	#[test]
+	fn test_is_disabled_control() {
+		let enabled = StateSet::new(State::Focused | State::Enabled | State::Sensitive);
+		let insensitive = StateSet::new(State::Focused | State::Enabled);
+		let not_enabled = StateSet::new(State::Focused | State::Sensitive);
+		let neither = StateSet::new(State::Focused);
+		assert!(!is_disabled_control(Role::PushButton, enabled));
+		assert!(is_disabled_control(Role::PushButton, insensitive));
+		assert!(is_disabled_control(Role::Entry, not_enabled));
+		assert!(is_disabled_control(Role::CheckBox, neither));
+		assert!(!is_disabled_control(Role::Paragraph, neither));
+		assert!(!is_disabled_control(Role::Label, insensitive));
+	}
+	#[test]
+	fn test_focus_announcement_uses_role_override() {
+		let presentation =
+			odilia_common::settings::presentation::PresentationSettings::default();
+		assert_eq!(
+			focus_announcement(
+				"OK",
+				presentation.role_name(Role::PushButton),
+				"Saves the file"
+			),
+			"OK, button. Saves the file"
+		);
+	}
+	#[test]
+	fn test_active_descendant_is_taken_from_the_child() {
+		use atspi_common::{events::object::ActiveDescendantChangedEvent, ObjectRef};
+		let object_ref = |path: &str| ObjectRef {
+			name: ":1.2".try_into().expect("valid unique name"),
+			path: path.try_into().expect("valid object path"),
+		};
+		let event = ActiveDescendantChangedEvent {
+			item: object_ref("/org/a11y/atspi/accessible/10"),
+			child: object_ref("/org/a11y/atspi/accessible/11"),
+		};
+		assert_eq!(
+			active_descendant(&event),
+			Some(AccessiblePrimitive {
+				id: "/org/a11y/atspi/accessible/11".to_string(),
+				sender: ":1.2".into(),
+			})
+		);
+		let cleared = ActiveDescendantChangedEvent {
+			item: object_ref("/org/a11y/atspi/accessible/10"),
+			child: object_ref("/org/a11y/atspi/accessible/null"),
+		};
+		assert_eq!(active_descendant(&cleared), None);
+	}
+	fn tree_item(id: &str, role: Role) -> CacheItem {
+		let mut item = A11Y_PARAGRAPH_ITEM.clone();
+		item.object.id = format!("/org/a11y/atspi/accessible/{id}");
+		item.role = role;
+		item
+	}
+	#[test]
+	fn test_entered_application() {
+		let firefox = AccessiblePrimitive {
+			id: "/org/a11y/atspi/accessible/root".to_string(),
+			sender: ":1.2".into(),
+		};
+		let terminal = AccessiblePrimitive {
+			id: "/org/a11y/atspi/accessible/root".to_string(),
+			sender: ":1.7".into(),
+		};
+		assert!(entered_application(Some(&firefox), &terminal));
+		assert!(entered_application(None, &firefox));
+		assert!(!entered_application(Some(&firefox), &firefox));
+		assert!(!entered_application(Some(&terminal), &terminal));
+	}
+	#[test]
+	fn test_form_crossing_entering_and_leaving() {
+		let form = tree_item("form", Role::Form);
+		let outside = [tree_item("section", Role::Section), tree_item("root", Role::Frame)];
+		let inside = [form.clone(), tree_item("root", Role::Frame)];
+		assert_eq!(
+			form_crossing(&outside, &inside),
+			Some(FormCrossing::Entered(form.object))
+		);
+		assert_eq!(form_crossing(&inside, &outside), Some(FormCrossing::Left));
+		assert!(form_crossing(&[], &inside).is_some());
+	}
+	#[test]
+	fn test_entered_table() {
+		let table = tree_item("table", Role::Table);
+		let in_table = [tree_item("row-1", Role::TableRow), table.clone()];
+		let in_other_row = [tree_item("row-2", Role::TableRow), table.clone()];
+		let outside = [tree_item("section", Role::Section)];
+		assert_eq!(entered_table(&outside, &in_table), Some(table.object.clone()));
+		assert_eq!(entered_table(&[], &in_table), Some(table.object.clone()));
+		assert_eq!(entered_table(&in_table, &in_other_row), None);
+		assert_eq!(entered_table(&in_table, &outside), None);
+		let nested = tree_item("nested", Role::Table);
+		let in_nested = [nested.clone(), tree_item("cell", Role::TableCell), table.clone()];
+		assert_eq!(entered_table(&in_table, &in_nested), Some(nested.object));
+		assert_eq!(entered_table(&in_nested, &in_table), Some(table.object));
+	}
+	#[test]
+	fn test_live_attributes_are_remembered_and_refreshed() {
+		let region = tree_item("status", Role::Section).object;
+		let mut cache = HashMap::new();
+		assert!(!refreshes_attributes(&cache, &region));
+		// as Firefox exposes <div aria-live="polite">
+		let polite: HashMap<String, String> = [
+			("live", "polite"),
+			("container-live", "polite"),
+			("tag", "div"),
+			("display", "block"),
+		]
+		.into_iter()
+		.map(|(key, value)| (key.to_string(), value.to_string()))
+		.collect();
+		let live = update_live_attributes(&mut cache, region.clone(), &polite);
+		assert_eq!(live, HashMap::from([("live".to_string(), "polite".to_string())]));
+		assert!(refreshes_attributes(&cache, &region));
+		// aria-live is made assertive, and aria-atomic is added
+		let assertive: HashMap<String, String> =
+			[("live", "assertive"), ("atomic", "true"), ("tag", "div")]
+				.into_iter()
+				.map(|(key, value)| (key.to_string(), value.to_string()))
+				.collect();
+		update_live_attributes(&mut cache, region.clone(), &assertive);
+		assert_eq!(cache[&region].get("live").map(String::as_str), Some("assertive"));
+		assert_eq!(cache[&region].get("atomic").map(String::as_str), Some("true"));
+		// and then removed again, which leaves nothing behind
+		update_live_attributes(&mut cache, region.clone(), &HashMap::new());
+		assert!(cache[&region].is_empty());
+		assert!(refreshes_attributes(&cache, &region));
+		assert!(!refreshes_attributes(&cache, &tree_item("other", Role::Section).object));
+	}
+	#[test]
+	fn test_focus_label() {
+		// an aria-label which differs from what the button shows
+		assert_eq!(focus_label(false, "Close dialog", "X"), "Close dialog");
+		assert_eq!(focus_label(true, "Close dialog", "X"), "X");
+		assert_eq!(focus_label(true, "Search", "  "), "Search");
+		assert_eq!(focus_label(false, "", "Submit"), "");
+	}
+	#[test]
+	fn test_heading_level() {
+		let attributes =
+			|level: &str| HashMap::from([("level".to_string(), level.to_string())]);
+		assert_eq!(heading_level(&attributes("2")), Some(2));
+		assert_eq!(heading_level(&attributes(" 3 ")), Some(3));
+		assert_eq!(heading_level(&attributes("0")), None);
+		assert_eq!(heading_level(&attributes("-1")), None);
+		assert_eq!(heading_level(&attributes("two")), None);
+		assert_eq!(heading_level(&attributes("")), None);
+		assert_eq!(heading_level(&HashMap::new()), None);
+		let other = HashMap::from([("tag".to_string(), "h2".to_string())]);
+		assert_eq!(heading_level(&other), None);
+	}
+	#[test]
+	fn test_heading_role_name() {
+		assert_eq!(heading_role_name("heading", Some(2)), "heading level 2");
+		assert_eq!(heading_role_name("heading", None), "heading");
+		assert_eq!(heading_role_name("titre", Some(1)), "titre level 1");
+	}
+	#[test]
+	fn test_column_letters() {
+		for (column, letters) in [
+			(0, ""),
+			(1, "A"),
+			(2, "B"),
+			(25, "Y"),
+			(26, "Z"),
+			(27, "AA"),
+			(28, "AB"),
+			(52, "AZ"),
+			(53, "BA"),
+			(701, "ZY"),
+			(702, "ZZ"),
+			(703, "AAA"),
+			(16_384, "XFD"),
+		] {
+			assert_eq!(column_letters(column), letters, "{column}");
+		}
+	}
+	#[test]
+	fn test_cell_position_announcement() {
+		assert_eq!(
+			cell_position_announcement(&English, CellPosition::Reference, 6, 1)
+				.as_deref(),
+			Some("B7")
+		);
+		assert_eq!(
+			cell_position_announcement(&English, CellPosition::Reference, 0, 26)
+				.as_deref(),
+			Some("AA1")
+		);
+		assert_eq!(
+			cell_position_announcement(&English, CellPosition::RowAndColumn, 6, 1)
+				.as_deref(),
+			Some("row 7, column 2")
+		);
+		assert_eq!(cell_position_announcement(&English, CellPosition::Off, 6, 1), None);
+		assert_eq!(
+			cell_position_announcement(
+				&English,
+				CellPosition::Reference,
+				usize::MAX,
+				1
+			),
+			None
+		);
+	}
+	#[test]
+	fn test_table_dimensions() {
+		assert_eq!(table_dimensions(&English, 5, 20), "table, 5 columns, 20 rows");
+		assert_eq!(table_dimensions(&English, 1, 1), "table, 1 column, 1 row");
+		assert_eq!(table_dimensions(&English, 3, -1), "table, 3 columns");
+	}
+	#[test]
+	fn test_form_crossing_within_the_same_form() {
+		let inside_a = [tree_item("panel-a", Role::Panel), tree_item("form", Role::Form)];
+		let inside_b = [tree_item("panel-b", Role::Panel), tree_item("form", Role::Form)];
+		assert_eq!(form_crossing(&inside_a, &inside_b), None);
+		assert_eq!(form_crossing(&[], &[]), None);
+	}
+	#[test]
+	fn test_form_crossing_nested_forms() {
+		let outer = tree_item("outer", Role::Form);
+		let inner = tree_item("inner", Role::Form);
+		let in_outer = [outer.clone()];
+		let in_inner = [inner.clone(), outer.clone()];
+		assert_eq!(
+			form_crossing(&in_outer, &in_inner),
+			Some(FormCrossing::Entered(inner.object))
+		);
+		assert_eq!(form_crossing(&in_inner, &in_outer), Some(FormCrossing::Left));
+		let other = tree_item("other", Role::Form);
+		assert_eq!(
+			form_crossing(&in_inner, &[other.clone()]),
+			Some(FormCrossing::Entered(other.object))
+		);
+	}
+	#[test]
+	fn test_selection_is_announced_in_menus_and_tabs() {
+		for role in [Role::Menu, Role::MenuBar, Role::PageTabList, Role::ComboBox] {
+			assert!(announces_selection(role), "{role:?}");
+		}
+		for role in [Role::List, Role::Table, Role::Text] {
+			assert!(!announces_selection(role), "{role:?}");
+		}
+	}
+	#[test]
+	fn test_attribute_changes_are_read_in_the_focused_item() {
+		let editor = AccessiblePrimitive {
+			id: "/org/a11y/atspi/accessible/5".to_string(),
+			sender: ":1.2".into(),
+		};
+		let other = AccessiblePrimitive {
+			id: "/org/a11y/atspi/accessible/6".to_string(),
+			sender: ":1.2".into(),
+		};
+		assert!(announces_attribute_change(true, &editor, Some(&editor)));
+		assert!(!announces_attribute_change(true, &other, Some(&editor)));
+		assert!(!announces_attribute_change(true, &editor, None));
+		assert!(!announces_attribute_change(false, &editor, Some(&editor)));
+	}
+	fn radio_group() -> Vec<CacheItem> {
+		["red", "green", "blue", "yellow"]
+			.into_iter()
+			.map(|colour| tree_item(colour, Role::RadioButton))
+			.collect()
+	}
+	#[test]
+	fn test_group_members_come_from_the_member_of_relation() {
+		let group = radio_group();
+		let relations = vec![
+			(RelationType::LabelledBy, vec![tree_item("label", Role::Label)]),
+			(RelationType::MemberOf, group.clone()),
+		];
+		let members = group_members(&group[1], &relations, Vec::new());
+		assert_eq!(members.len(), 4);
+		assert_eq!(members[3].object, group[3].object);
+	}
+	#[test]
+	fn test_group_members_fall_back_to_siblings_with_the_same_role() {
+		let group = radio_group();
+		let mut siblings = vec![tree_item("label", Role::Label)];
+		siblings.extend(group.clone());
+		let members = group_members(&group[0], &[], siblings);
+		assert_eq!(members.len(), 4);
+		assert_eq!(members[0].object, group[0].object);
+	}
+	#[test]
+	fn test_group_announcement() {
+		let mut group = radio_group();
+		group[1].states.insert(State::Checked);
+		assert_eq!(
+			group_announcement(&English, &group[1], &group, "Color").as_deref(),
+			Some("option 2 of 4, selected, Color group")
+		);
+		assert_eq!(
+			group_announcement(&English, &group[3], &group, " ").as_deref(),
+			Some("option 4 of 4, not selected")
+		);
+		let check_boxes =
+			[tree_item("bold", Role::CheckBox), tree_item("italic", Role::CheckBox)];
+		assert_eq!(
+			group_announcement(&English, &check_boxes[0], &check_boxes, "Style")
+				.as_deref(),
+			Some("option 1 of 2, not checked, Style group")
+		);
+		// a lone option, or one missing from its own group, has no group to read
+		assert_eq!(group_announcement(&English, &group[0], &group[..1], "Color"), None);
+		let stray = tree_item("purple", Role::RadioButton);
+		assert_eq!(group_announcement(&English, &stray, &group, "Color"), None);
+	}
+	#[test]
+	fn test_tooltip_priority() {
+		assert_eq!(
+			tooltip_priority(Role::ToolTip, TooltipAnnouncement::Polite),
+			Some(Priority::Notification)
+		);
+		assert_eq!(
+			tooltip_priority(Role::ToolTip, TooltipAnnouncement::Interrupt),
+			Some(Priority::Message)
+		);
+		assert_eq!(tooltip_priority(Role::ToolTip, TooltipAnnouncement::Off), None);
+		assert_eq!(tooltip_priority(Role::Label, TooltipAnnouncement::Interrupt), None);
+	}
+	#[test]
+	fn test_crossed_offset() {
+		// in "hello world", moving right from before the "o" to before the space reads the "o" which was moved over
+		assert_eq!(crossed_offset(4, 5), 4);
+		// moving left from before the space to before the "o" reads the "o" which was moved onto
+		assert_eq!(crossed_offset(5, 4), 4);
+		assert_eq!(crossed_offset(0, 1), 0);
+		assert_eq!(crossed_offset(1, 0), 0);
+		assert_eq!(crossed_offset(7, 7), 7);
+		// the same caret position gives a different word depending on which way the caret came from
+		assert_eq!(word_at("hello world", crossed_offset(4, 5)), Some("hello"));
+		assert_eq!(word_at("hello world", crossed_offset(6, 5)), None);
+		assert_eq!(word_at("hello world", crossed_offset(7, 6)), Some("world"));
+		assert_eq!(word_at("hello world", crossed_offset(5, 6)), None);
+	}
+	#[test]
+	fn test_word_at() {
+		let text = "it's a snake_case name, déjà vu";
+		assert_eq!(word_at(text, 0), Some("it's"));
+		assert_eq!(word_at(text, 3), Some("it's"));
+		assert_eq!(word_at(text, 5), Some("a"));
+		assert_eq!(word_at(text, 12), Some("snake_case"));
+		assert_eq!(word_at(text, 27), Some("déjà"));
+		assert_eq!(word_at(text, 30), Some("vu"));
+		// spaces and punctuation are not part of a word, and the offset may be past the end
+		assert_eq!(word_at(text, 4), None);
+		assert_eq!(word_at(text, 22), None);
+		assert_eq!(word_at(text, 31), None);
+		assert_eq!(word_at("", 0), None);
+	}
+	#[test]
+	fn test_reorder_announcement() {
+		assert_eq!(reorder_announcement(TableLine::Row), "rows reordered");
+		assert_eq!(reorder_announcement(TableLine::Column), "columns reordered");
+	}
 }
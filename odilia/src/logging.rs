@@ -6,35 +6,40 @@
 use std::{env, io};
 
 use eyre::Context;
-use odilia_common::settings::{log::LoggingKind, ApplicationConfig};
+use odilia_common::settings::{
+	log::{LogFormat, LoggingKind},
+	ApplicationConfig,
+};
+use tracing::Subscriber;
 use tracing_error::ErrorLayer;
-use tracing_subscriber::{prelude::*, EnvFilter};
+use tracing_subscriber::{
+	fmt::{format::FmtSpan, MakeWriter},
+	prelude::*,
+	registry::LookupSpan,
+	EnvFilter, Layer,
+};
 use tracing_tree::HierarchicalLayer;
 
 /// Initialise the logging stack
 /// this requires an application configuration structure, so configuration must be initialized before logging is
-pub fn init(config: &ApplicationConfig) -> eyre::Result<()> {
-	let env_filter = match env::var("APP_LOG").or_else(|_| env::var("RUST_LOG")) {
-		Ok(s) => EnvFilter::from(s),
-		_ => EnvFilter::from(&config.log.level),
+/// a level given on the command line wins over the `APP_LOG` and `RUST_LOG` environment variables, which in turn win over the configured one
+pub fn init(config: &ApplicationConfig, level_override: Option<&str>) -> eyre::Result<()> {
+	let level = match level_override {
+		Some(level) => level.to_owned(),
+		None => env::var("APP_LOG")
+			.or_else(|_| env::var("RUST_LOG"))
+			.unwrap_or_else(|_| config.log.level.clone()),
 	};
-	let tree = HierarchicalLayer::new(4)
-		.with_bracketed_fields(true)
-		.with_targets(true)
-		.with_deferred_spans(true)
-		.with_span_retrace(true)
-		.with_indent_lines(true)
-		.with_ansi(false)
-		.with_wraparound(4);
+	let env_filter = EnvFilter::from(level);
 	//this requires boxing because the types returned by this match block would be incompatible otherwise, since we return different layers, or modifications to a layer depending on what we get from the configuration. It is possible to do it otherwise, hopefully, but for now this  would do
 	let final_layer = match &config.log.logger {
 		LoggingKind::File(path) => {
 			let file = std::fs::File::create(path).with_context(|| {
 				format!("creating log file '{}'", path.display())
 			})?;
-			tree.with_writer(file).boxed()
+			formatted_layer(config.log.format, file, false)
 		}
-		LoggingKind::Tty => tree.with_writer(io::stdout).with_ansi(true).boxed(),
+		LoggingKind::Tty => formatted_layer(config.log.format, io::stdout, true),
 		LoggingKind::Syslog => tracing_journald::Layer::new()?
 			.with_syslog_identifier("odilia".to_owned())
 			.boxed(),
@@ -46,3 +51,110 @@ pub fn init(config: &ApplicationConfig) -> eyre::Result<()> {
 		.init();
 	Ok(())
 }
+
+/// The layer which writes logs to `writer` in the given `format`.
+/// JSON lines carry the fields of every span the event happened in, and a line is also written when a span closes, with how long it was busy and idle, so the time spent on each event can be followed.
+fn formatted_layer<S, W>(
+	format: LogFormat,
+	writer: W,
+	ansi: bool,
+) -> Box<dyn Layer<S> + Send + Sync>
+where
+	S: Subscriber + for<'span> LookupSpan<'span>,
+	W: for<'writer> MakeWriter<'writer> + Send + Sync + 'static,
+{
+	match format {
+		LogFormat::Pretty => HierarchicalLayer::new(4)
+			.with_bracketed_fields(true)
+			.with_targets(true)
+			.with_deferred_spans(true)
+			.with_span_retrace(true)
+			.with_indent_lines(true)
+			.with_wraparound(4)
+			.with_writer(writer)
+			.with_ansi(ansi)
+			.boxed(),
+		LogFormat::Json => tracing_subscriber::fmt::layer()
+			.json()
+			.with_current_span(true)
+			.with_span_list(true)
+			.with_span_events(FmtSpan::CLOSE)
+			.with_writer(writer)
+			.with_ansi(false)
+			.boxed(),
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::formatted_layer;
+	use odilia_common::settings::log::LogFormat;
+	use std::{
+		io,
+		sync::{Arc, Mutex},
+	};
+	use tracing_subscriber::{prelude::*, Registry};
+
+	/// A writer which keeps everything written to it, shared between its clones.
+	#[derive(Clone, Default)]
+	struct Captured(Arc<Mutex<Vec<u8>>>);
+
+	impl io::Write for Captured {
+		fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+			self.0.lock()
+				.expect("the log buffer is never poisoned")
+				.extend_from_slice(buf);
+			Ok(buf.len())
+		}
+		fn flush(&mut self) -> io::Result<()> {
+			Ok(())
+		}
+	}
+
+	fn log_with(format: LogFormat) -> String {
+		let captured = Captured::default();
+		let writer = captured.clone();
+		let subscriber = Registry::default().with(formatted_layer(
+			format,
+			move || writer.clone(),
+			false,
+		));
+		tracing::subscriber::with_default(subscriber, || {
+			let span = tracing::info_span!(
+				"dispatch",
+				interface = "Object",
+				member = "StateChanged"
+			);
+			let _entered = span.enter();
+			tracing::info!(handled = true, "Event handled");
+		});
+		let bytes = captured.0.lock().expect("the log buffer is never poisoned").clone();
+		String::from_utf8(bytes).expect("logs are UTF-8")
+	}
+
+	#[test]
+	fn json_logs_carry_span_fields_and_timing() {
+		let logs = log_with(LogFormat::Json);
+		let lines: Vec<serde_json::Value> = logs
+			.lines()
+			.map(|line| serde_json::from_str(line).expect("every line is JSON"))
+			.collect();
+		assert_eq!(lines.len(), 2);
+		assert_eq!(lines[0]["fields"]["message"], "Event handled");
+		assert_eq!(lines[0]["fields"]["handled"], true);
+		assert_eq!(lines[0]["span"]["interface"], "Object");
+		assert_eq!(lines[0]["span"]["member"], "StateChanged");
+		assert_eq!(lines[0]["spans"][0]["name"], "dispatch");
+		// closing the span reports how long it took
+		assert!(lines[1]["fields"]["time.busy"].is_string());
+	}
+	#[test]
+	fn pretty_logs_are_not_json() {
+		let logs = log_with(LogFormat::Pretty);
+		assert!(logs.contains("Event handled"));
+		assert!(logs.contains("StateChanged"));
+		assert!(logs
+			.lines()
+			.all(|line| serde_json::from_str::<serde_json::Value>(line).is_err()));
+	}
+}
@@ -8,15 +8,23 @@
 )]
 #![allow(clippy::multiple_crate_versions)]
 
+mod children_batch;
 mod cli;
+mod earcons;
 mod events;
 mod logging;
+mod marks;
+mod message_history;
+mod number_format;
+mod speech_text;
 mod state;
+mod text_formatting;
+mod watchdog;
 
 use std::{fs, path::PathBuf, process::exit, sync::Arc, time::Duration};
 
 use crate::cli::Args;
-use crate::state::ScreenReaderState;
+use crate::state::{priority_voices, ScreenReaderState};
 use clap::Parser;
 use eyre::WrapErr;
 use figment::{
@@ -24,9 +32,10 @@ use figment::{
 	Figment,
 };
 use futures::{future::FutureExt, StreamExt};
-use odilia_common::settings::ApplicationConfig;
-use odilia_input::sr_event_receiver;
+use odilia_common::settings::{log::LogFormat, speech::SpeechOutput, ApplicationConfig};
+use odilia_input::{run_sources, InputSource, SocketSource};
 use odilia_notify::listen_to_dbus_notifications;
+use odilia_tts::CommandBackend;
 use ssip_client_async::Priority;
 use tokio::{
 	signal::unix::{signal, SignalKind},
@@ -35,7 +44,7 @@ use tokio::{
 };
 use tokio_util::{sync::CancellationToken, task::TaskTracker};
 
-use atspi_common::events::{document, object};
+use atspi_common::events::{document, mouse, object};
 use tracing::Instrument;
 #[tracing::instrument(skip(state, shutdown))]
 async fn notifications_monitor(
@@ -90,9 +99,17 @@ async fn main() -> eyre::Result<()> {
 	let tracker = TaskTracker::new();
 
 	//initializing configuration
-	let config = load_configuration(args.config)?;
+	let mut config = load_configuration(args.config)?;
+	if args.no_speech {
+		config.speech.output = SpeechOutput::Silent;
+	} else if args.print_speech {
+		config.speech.output = SpeechOutput::Stdout;
+	}
+	if args.json_logs {
+		config.log.format = LogFormat::Json;
+	}
 	//initialize logging, with the provided config
-	logging::init(&config)?;
+	logging::init(&config, args.log_level.as_deref())?;
 
 	tracing::info!(?config, "this configuration was used to prepair odilia");
 
@@ -111,9 +128,32 @@ async fn main() -> eyre::Result<()> {
 	// Although in the future, this may possibly be resolved through a proper cache, I think it still makes sense to separate SSIP's IO operations to a separate task.
 	// Like the channel above, it is very important that this is *never* full, since it can cause deadlocking if the other task sending the request is working with zbus.
 	let (ssip_req_tx, ssip_req_rx) = mpsc::channel::<ssip_client_async::Request>(128);
+	// with any other output, requests go to a command, are printed, or are dropped instead, so speech-dispatcher is never connected to
+	let mut ssip = if config.speech.output == SpeechOutput::SpeechDispatcher {
+		Some(odilia_tts::create_ssip_client().await?)
+	} else {
+		tracing::info!(output = ?config.speech.output, "Speech-dispatcher is not used; nothing will be spoken.");
+		None
+	};
+	if let Some(ssip) = ssip.as_mut() {
+		match odilia_tts::list_output_modules(ssip).await {
+			Ok(modules) => {
+				let selected = odilia_tts::select_output_module(
+					&config.speech.module,
+					&modules,
+				);
+				if selected.is_none() {
+					tracing::warn!(configured = %config.speech.module, ?modules, "Configured output module is not available; falling back to the speech-dispatcher default.");
+				}
+				config.speech.module = selected.unwrap_or_default();
+			}
+			Err(e) => {
+				tracing::warn!(error = %e, "Could not list speech-dispatcher output modules; using the configured module as-is.");
+			}
+		}
+	}
 	// Initialize state
 	let state = Arc::new(ScreenReaderState::new(ssip_req_tx, config).await?);
-	let ssip = odilia_tts::create_ssip_client().await?;
 
 	if state.say(Priority::Message, "Welcome to Odilia!".to_string()).await {
 		tracing::debug!("Welcome message spoken.");
@@ -129,33 +169,99 @@ async fn main() -> eyre::Result<()> {
 		state.register_event::<object::TextCaretMovedEvent>(),
 		state.register_event::<object::ChildrenChangedEvent>(),
 		state.register_event::<object::TextChangedEvent>(),
+		state.register_event::<object::ActiveDescendantChangedEvent>(),
+		state.register_event::<object::SelectionChangedEvent>(),
+		state.register_event::<object::PropertyChangeEvent>(),
+		state.register_event::<object::RowReorderedEvent>(),
+		state.register_event::<object::ColumnReorderedEvent>(),
+		state.register_event::<object::AttributesChangedEvent>(),
 		state.register_event::<document::LoadCompleteEvent>(),
 		state.add_cache_match_rule(),
 	)?;
+	// pointer movement is very chatty, so only listen for it when it will be read
+	if state.config.announcements.mouse_review {
+		state.register_event::<mouse::AbsEvent>().await?;
+	}
+	if state.config.announcements.reads_text_attributes() {
+		state.register_event::<object::TextAttributesChangedEvent>().await?;
+	}
 
-	let ssip_event_receiver =
-		odilia_tts::handle_ssip_commands(ssip, ssip_req_rx, token.clone())
-			.map(|r| r.wrap_err("Could no process SSIP request"));
+	// the watchdog only reads how full the event channel is, it never sends on it
+	let watchdog_events = atspi_event_tx.clone();
 	let atspi_event_receiver =
 		events::receive(Arc::clone(&state), atspi_event_tx, token.clone())
 			.map(|()| Ok::<_, eyre::Report>(()));
 	let atspi_event_processor =
 		events::process(Arc::clone(&state), atspi_event_rx, token.clone())
 			.map(|()| Ok::<_, eyre::Report>(()));
-	let odilia_event_receiver = sr_event_receiver(sr_event_tx, token.clone())
+	let input_sources: Vec<Box<dyn InputSource>> = vec![Box::new(SocketSource)];
+	let odilia_event_receiver = run_sources(input_sources, sr_event_tx, token.clone())
 		.map(|r| r.wrap_err("Could not process Odilia events"));
 	let odilia_event_processor =
 		events::sr_event(Arc::clone(&state), sr_event_rx, token.clone())
 			.map(|r| r.wrap_err("Could not process Odilia event"));
 	let notification_task = notifications_monitor(Arc::clone(&state), token.clone())
 		.map(|r| r.wrap_err("Could not process signal shutdown."));
+	let pruning_task = events::prune_dead_applications(Arc::clone(&state), token.clone())
+		.map(|r| r.wrap_err("Could not watch for closed applications"));
+	let warming_state = Arc::clone(&state);
+	let warming_task = async move {
+		let scope = warming_state.config.cache.warm_cache_on_start;
+		events::warm_cache(warming_state.as_ref(), scope).await
+	}
+	.map(|r| r.map(|_| ()).wrap_err("Could not warm the cache"));
 
 	tracker.spawn(atspi_event_receiver);
 	tracker.spawn(atspi_event_processor);
 	tracker.spawn(odilia_event_receiver);
 	tracker.spawn(odilia_event_processor);
-	tracker.spawn(ssip_event_receiver);
+	if let Some(ssip) = ssip {
+		let ssip_event_receiver = odilia_tts::handle_ssip_commands(
+			ssip,
+			ssip_req_rx,
+			priority_voices(&state.config.priorities.voices),
+			token.clone(),
+		)
+		.map(|r| r.wrap_err("Could no process SSIP request"));
+		tracker.spawn(ssip_event_receiver);
+	} else {
+		match state.config.speech.output {
+			SpeechOutput::Command => {
+				let backend =
+					CommandBackend::new(state.config.speech.command.clone());
+				let ssip_event_speaker = odilia_tts::run_backend(
+					Box::new(backend),
+					ssip_req_rx,
+					token.clone(),
+				)
+				.map(|r| r.wrap_err("Could not speak through the speech command"));
+				tracker.spawn(ssip_event_speaker);
+			}
+			SpeechOutput::Stdout => {
+				let ssip_event_printer =
+					odilia_tts::print_ssip_commands(ssip_req_rx, token.clone())
+						.map(|r| {
+							r.wrap_err("Could not print SSIP request")
+						});
+				tracker.spawn(ssip_event_printer);
+			}
+			SpeechOutput::SpeechDispatcher | SpeechOutput::Silent => {
+				tracker.spawn(odilia_tts::discard_ssip_commands(
+					ssip_req_rx,
+					token.clone(),
+				));
+			}
+		}
+	}
 	tracker.spawn(notification_task);
+	tracker.spawn(pruning_task);
+	tracker.spawn(warming_task);
+	if state.config.cache.children_changed_window_ms > 0 {
+		tracker.spawn(events::flush_children_changes(Arc::clone(&state), token.clone()));
+	}
+	if state.config.watchdog.enabled {
+		tracker.spawn(watchdog::watch(Arc::clone(&state), watchdog_events, token.clone()));
+	}
 	tracker.close();
 	let _ = sigterm_signal_watcher(token, tracker)
 		.await
@@ -187,6 +293,7 @@ fn load_configuration(cli_overide: Option<PathBuf>) -> Result<ApplicationConfig,
 		.admerge(Toml::file(&config_path));
 	//realise the configuration and freeze it into place
 	let config: ApplicationConfig = figment.extract()?;
+	config.validate()?;
 	if !config_path.exists() {
 		let toml = toml::to_string(&config)?;
 		fs::write(&config_path, toml).expect("Unable to create default config file.");
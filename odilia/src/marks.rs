@@ -0,0 +1,76 @@
+//! Named reading positions which the user can jump back to, like bookmarks in long content.
+//! Marks only last for the current session.
+
+use odilia_cache::AccessiblePrimitive;
+use std::collections::HashMap;
+
+/// A saved reading position: an accessible, and where the caret was inside it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Mark {
+	pub accessible: AccessiblePrimitive,
+	pub caret_offset: usize,
+}
+
+/// All marks the user has set, by name.
+#[derive(Debug, Default)]
+pub struct Marks {
+	by_name: HashMap<String, Mark>,
+}
+
+impl Marks {
+	/// Save a mark under `name`, returning the mark it replaced, if any.
+	pub fn set(&mut self, name: &str, mark: Mark) -> Option<Mark> {
+		self.by_name.insert(name.to_string(), mark)
+	}
+	/// Look up the mark saved under `name`.
+	pub fn get(&self, name: &str) -> Option<&Mark> {
+		self.by_name.get(name)
+	}
+	/// Forget every mark inside the given application, for example because it has closed.
+	pub fn remove_sender(&mut self, sender: &str) {
+		self.by_name
+			.retain(|_, mark| mark.accessible.sender.as_str() != sender);
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{Mark, Marks};
+	use odilia_cache::AccessiblePrimitive;
+
+	fn mark(sender: &str, id: &str, caret_offset: usize) -> Mark {
+		Mark {
+			accessible: AccessiblePrimitive {
+				id: id.to_string(),
+				sender: sender.into(),
+			},
+			caret_offset,
+		}
+	}
+
+	#[test]
+	fn marks_are_found_by_name() {
+		let mut marks = Marks::default();
+		assert_eq!(marks.set("a", mark(":1.2", "/org/a11y/atspi/accessible/5", 30)), None);
+		marks.set("b", mark(":1.2", "/org/a11y/atspi/accessible/9", 0));
+		assert_eq!(marks.get("a"), Some(&mark(":1.2", "/org/a11y/atspi/accessible/5", 30)));
+		assert_eq!(marks.get("c"), None);
+	}
+	#[test]
+	fn setting_a_mark_again_moves_it() {
+		let mut marks = Marks::default();
+		marks.set("a", mark(":1.2", "/org/a11y/atspi/accessible/5", 30));
+		let old = marks.set("a", mark(":1.2", "/org/a11y/atspi/accessible/5", 75));
+		assert_eq!(old, Some(mark(":1.2", "/org/a11y/atspi/accessible/5", 30)));
+		assert_eq!(marks.get("a").map(|mark| mark.caret_offset), Some(75));
+	}
+	#[test]
+	fn marks_of_closed_applications_are_removed() {
+		let mut marks = Marks::default();
+		marks.set("a", mark(":1.2", "/org/a11y/atspi/accessible/5", 30));
+		marks.set("b", mark(":1.7", "/org/a11y/atspi/accessible/5", 30));
+		marks.remove_sender(":1.2");
+		assert_eq!(marks.get("a"), None);
+		assert!(marks.get("b").is_some());
+	}
+}
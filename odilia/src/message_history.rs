@@ -0,0 +1,121 @@
+//! The most recent messages spoken by Odilia, so the user can go back over anything they missed.
+//! Replayed messages are not recorded again, otherwise reviewing the history would change it.
+
+use circular_queue::CircularQueue;
+
+/// How many spoken messages are kept for review.
+pub const MESSAGE_HISTORY_LENGTH: usize = 50;
+
+/// The result of moving through the message history.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Review<'a> {
+	/// The message now under the review cursor.
+	Message(&'a str),
+	/// There is no older message; the cursor stays on the oldest one.
+	StartOfHistory,
+	/// There is no newer message; the cursor stays on the newest one.
+	EndOfHistory,
+}
+
+/// Recently spoken messages, with a cursor which the user can move back and forth through them.
+#[derive(Debug)]
+pub struct MessageHistory {
+	messages: CircularQueue<String>,
+	/// How far back from the newest message the cursor is; `None` until the user starts reviewing.
+	cursor: Option<usize>,
+}
+
+impl Default for MessageHistory {
+	fn default() -> Self {
+		Self::with_capacity(MESSAGE_HISTORY_LENGTH)
+	}
+}
+
+impl MessageHistory {
+	#[must_use]
+	pub fn with_capacity(capacity: usize) -> Self {
+		Self { messages: CircularQueue::with_capacity(capacity), cursor: None }
+	}
+	/// Remember a spoken message, dropping the oldest one if the history is full.
+	/// Reviewing starts again from the newest message afterwards.
+	pub fn record(&mut self, message: String) {
+		self.messages.push(message);
+		self.cursor = None;
+	}
+	/// Move the cursor to the message before the current one.
+	pub fn review_previous(&mut self) -> Review<'_> {
+		let index = self.cursor.map_or(0, |cursor| cursor + 1);
+		if index >= self.messages.len() {
+			return Review::StartOfHistory;
+		}
+		self.cursor = Some(index);
+		self.current()
+	}
+	/// Move the cursor to the message after the current one.
+	pub fn review_next(&mut self) -> Review<'_> {
+		match self.cursor {
+			None | Some(0) => Review::EndOfHistory,
+			Some(cursor) => {
+				self.cursor = Some(cursor - 1);
+				self.current()
+			}
+		}
+	}
+	fn current(&self) -> Review<'_> {
+		self.cursor
+			.and_then(|cursor| self.messages.iter().nth(cursor))
+			.map(String::as_str)
+			.map_or(Review::EndOfHistory, Review::Message)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{MessageHistory, Review};
+
+	fn history(messages: &[&str]) -> MessageHistory {
+		let mut history = MessageHistory::with_capacity(3);
+		for message in messages {
+			history.record((*message).to_string());
+		}
+		history
+	}
+
+	#[test]
+	fn review_walks_back_and_forth_through_messages() {
+		let mut history = history(&["one", "two", "three"]);
+		assert_eq!(history.review_previous(), Review::Message("three"));
+		assert_eq!(history.review_previous(), Review::Message("two"));
+		assert_eq!(history.review_previous(), Review::Message("one"));
+		assert_eq!(history.review_next(), Review::Message("two"));
+		assert_eq!(history.review_next(), Review::Message("three"));
+	}
+	#[test]
+	fn boundaries_keep_the_cursor_in_place() {
+		let mut history = history(&["one", "two"]);
+		assert_eq!(history.review_next(), Review::EndOfHistory);
+		assert_eq!(history.review_previous(), Review::Message("two"));
+		assert_eq!(history.review_previous(), Review::Message("one"));
+		assert_eq!(history.review_previous(), Review::StartOfHistory);
+		assert_eq!(history.review_next(), Review::Message("two"));
+		assert_eq!(history.review_next(), Review::EndOfHistory);
+		assert_eq!(history.review_previous(), Review::Message("one"));
+	}
+	#[test]
+	fn oldest_messages_are_dropped_when_full() {
+		let mut history = history(&["one", "two", "three", "four"]);
+		assert_eq!(history.review_previous(), Review::Message("four"));
+		assert_eq!(history.review_previous(), Review::Message("three"));
+		assert_eq!(history.review_previous(), Review::Message("two"));
+		assert_eq!(history.review_previous(), Review::StartOfHistory);
+	}
+	#[test]
+	fn recording_restarts_review_from_the_newest_message() {
+		let mut history = history(&["one", "two"]);
+		history.review_previous();
+		history.review_previous();
+		history.record("three".to_string());
+		assert_eq!(history.review_previous(), Review::Message("three"));
+		assert_eq!(MessageHistory::default().review_previous(), Review::StartOfHistory);
+	}
+}
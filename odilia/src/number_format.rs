@@ -0,0 +1,95 @@
+//! Wording of numbers in announcements, like "3 of 7" or "5 fields", so that it can follow the user's language.
+//! Only English is implemented so far; every other language falls back to it until a formatter is added to [`FORMATS`].
+
+/// How numbers are put into words for one language.
+pub trait NumberFormat: Send + Sync {
+	/// A plain number, like "7".
+	fn cardinal(&self, n: usize) -> String;
+	/// A place in a sequence, like "3rd".
+	#[allow(dead_code)]
+	fn ordinal(&self, n: usize) -> String;
+	/// How many of something there are, like "1 field" or "5 fields".
+	fn count(&self, n: usize, singular: &str, plural: &str) -> String;
+	/// A position within a set, like "3 of 7".
+	fn position(&self, index: usize, total: usize) -> String;
+	/// A percentage, rounded to a whole number, like "42 percent".
+	fn percentage(&self, value: f64) -> String;
+}
+
+/// Numbers as they are written in English.
+#[derive(Debug)]
+pub struct English;
+
+impl NumberFormat for English {
+	fn cardinal(&self, n: usize) -> String {
+		n.to_string()
+	}
+	fn ordinal(&self, n: usize) -> String {
+		let suffix = match (n % 10, n % 100) {
+			(_, 11..=13) => "th",
+			(1, _) => "st",
+			(2, _) => "nd",
+			(3, _) => "rd",
+			_ => "th",
+		};
+		format!("{n}{suffix}")
+	}
+	fn count(&self, n: usize, singular: &str, plural: &str) -> String {
+		let noun = if n == 1 { singular } else { plural };
+		format!("{n} {noun}")
+	}
+	fn position(&self, index: usize, total: usize) -> String {
+		format!("{index} of {total}")
+	}
+	fn percentage(&self, value: f64) -> String {
+		format!("{} percent", value.round())
+	}
+}
+
+/// The formatter for each language, by the language part of its locale.
+const FORMATS: [(&str, &dyn NumberFormat); 1] = [("en", &English)];
+
+/// Find the formatter for a locale like `en-US` or `en_GB`; only the language part is used.
+/// Languages without their own formatter use English.
+#[must_use]
+pub fn for_locale(locale: &str) -> &'static dyn NumberFormat {
+	let fallback: &'static dyn NumberFormat = &English;
+	let language = locale.split(['-', '_', '.']).next().unwrap_or_default();
+	FORMATS.iter()
+		.find(|(code, _)| code.eq_ignore_ascii_case(language))
+		.map_or(fallback, |(_, format)| *format)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{for_locale, English, NumberFormat};
+
+	#[test]
+	fn english_ordinals() {
+		let ordinals: Vec<String> = [1, 2, 3, 4, 11, 12, 13, 21, 22, 101, 111]
+			.map(|n| English.ordinal(n))
+			.into();
+		assert_eq!(
+			ordinals,
+			[
+				"1st", "2nd", "3rd", "4th", "11th", "12th", "13th", "21st", "22nd",
+				"101st", "111th"
+			]
+		);
+	}
+	#[test]
+	fn english_counts_positions_and_percentages() {
+		assert_eq!(English.count(1, "field", "fields"), "1 field");
+		assert_eq!(English.count(0, "field", "fields"), "0 fields");
+		assert_eq!(English.count(5, "field", "fields"), "5 fields");
+		assert_eq!(English.position(3, 7), "3 of 7");
+		assert_eq!(English.percentage(41.6), "42 percent");
+		assert_eq!(English.percentage(100.0), "100 percent");
+	}
+	#[test]
+	fn locales_are_matched_by_language() {
+		for locale in ["en", "en-US", "en_GB.UTF-8", "EN", "fr-FR", ""] {
+			assert_eq!(for_locale(locale).position(1, 2), "1 of 2");
+		}
+	}
+}
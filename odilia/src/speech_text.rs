@@ -0,0 +1,154 @@
+//! Transformations applied to text right before it is sent to the speech server.
+//! Everything here is a pure function of the text and the user's configuration.
+
+use odilia_common::settings::speech::{NumberReading, SpeechSettings, WhitespaceMode};
+
+/// Apply all configured transformations to `text` before speaking it.
+pub fn prepare_for_speech(text: String, settings: &SpeechSettings) -> String {
+	let text = match settings.whitespace {
+		WhitespaceMode::Preserve => text,
+		WhitespaceMode::Collapse => normalize_whitespace(&text, false),
+		WhitespaceMode::Announce => normalize_whitespace(&text, true),
+	};
+	match settings.number_reading {
+		NumberReading::Words => text,
+		NumberReading::Digits => space_out_digits(&text),
+	}
+}
+
+/// Collapse every run of whitespace into a single space, and trim both ends.
+/// If `announce_newlines` is set, each line break is replaced with the words "new line" instead; `\r\n` counts as one line break.
+pub fn normalize_whitespace(text: &str, announce_newlines: bool) -> String {
+	if !announce_newlines {
+		return text.split_whitespace().collect::<Vec<_>>().join(" ");
+	}
+	text.replace("\r\n", "\n")
+		.split('\n')
+		.map(|line| line.split_whitespace().collect::<Vec<_>>().join(" "))
+		.collect::<Vec<_>>()
+		.join(" new line ")
+		.split_whitespace()
+		.collect::<Vec<_>>()
+		.join(" ")
+}
+
+/// Space out the digits of every number in `text`, so that "1024" is read "1 0 2 4" instead of "one thousand twenty-four".
+/// - Grouping separators are dropped: "1,024" is read the same as "1024".
+/// - Decimal points are read as "point": "3.14" becomes "3 point 1 4".
+/// - Words mixing letters and numbers, like "k8s" or "mp3", are left alone.
+pub fn space_out_digits(text: &str) -> String {
+	let chars: Vec<char> = text.chars().collect();
+	let mut out = String::with_capacity(text.len() * 2);
+	let mut idx = 0;
+	while idx < chars.len() {
+		let starts_number = chars[idx].is_ascii_digit()
+			&& (idx == 0 || !chars[idx - 1].is_alphanumeric());
+		if !starts_number {
+			out.push(chars[idx]);
+			idx += 1;
+			continue;
+		}
+		let (end, spoken) = read_number(&chars, idx);
+		// a number glued to letters is part of a word
+		if chars.get(end).is_some_and(|chr| chr.is_alphanumeric()) {
+			let word_end = chars[end..]
+				.iter()
+				.position(|chr| !chr.is_alphanumeric())
+				.map_or(chars.len(), |offset| end + offset);
+			out.extend(&chars[idx..word_end]);
+			idx = word_end;
+		} else {
+			out.push_str(&spoken);
+			idx = end;
+		}
+	}
+	out
+}
+
+/// Read the number starting at `start`, returning the index just past it and its digit-by-digit form.
+fn read_number(chars: &[char], start: usize) -> (usize, String) {
+	let digit_run =
+		|from: usize| chars[from..].iter().take_while(|chr| chr.is_ascii_digit()).count();
+	let mut digits: Vec<String> = Vec::new();
+	let mut idx = start;
+	let push_digits = |from: usize, len: usize, digits: &mut Vec<String>| {
+		digits.extend(chars[from..from + len].iter().map(char::to_string));
+	};
+	let len = digit_run(idx);
+	push_digits(idx, len, &mut digits);
+	idx += len;
+	// grouping separators are always followed by exactly three digits
+	while chars.get(idx) == Some(&',') && digit_run(idx + 1) == 3 {
+		push_digits(idx + 1, 3, &mut digits);
+		idx += 4;
+	}
+	if chars.get(idx) == Some(&'.') && digit_run(idx + 1) > 0 {
+		let len = digit_run(idx + 1);
+		digits.push("point".to_string());
+		push_digits(idx + 1, len, &mut digits);
+		idx += len + 1;
+	}
+	(idx, digits.join(" "))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{normalize_whitespace, space_out_digits};
+
+	#[test]
+	fn whitespace_is_collapsed_and_trimmed() {
+		assert_eq!(normalize_whitespace("  Save\t\tfile \n", false), "Save file");
+		assert_eq!(
+			normalize_whitespace("first line\n\n  second\r\nthird", false),
+			"first line second third"
+		);
+		assert_eq!(normalize_whitespace(" \n\t ", false), "");
+	}
+	#[test]
+	fn newlines_can_be_announced() {
+		assert_eq!(
+			normalize_whitespace("fn main() {\n\tprintln!();\n}", true),
+			"fn main() { new line println!(); new line }"
+		);
+		assert_eq!(normalize_whitespace("a\r\n\r\nb", true), "a new line new line b");
+		assert_eq!(normalize_whitespace("\n", true), "new line");
+	}
+
+	#[test]
+	fn plain_numbers_are_spaced() {
+		assert_eq!(space_out_digits("1024"), "1 0 2 4");
+		assert_eq!(space_out_digits("Your code is 482913."), "Your code is 4 8 2 9 1 3.");
+	}
+	#[test]
+	fn single_digits_are_unchanged() {
+		assert_eq!(space_out_digits("page 7 of 9"), "page 7 of 9");
+	}
+	#[test]
+	fn grouping_separators_are_dropped() {
+		assert_eq!(space_out_digits("1,024,000 bytes"), "1 0 2 4 0 0 0 bytes");
+	}
+	#[test]
+	fn commas_which_are_not_grouping_are_kept() {
+		assert_eq!(space_out_digits("1,2, 3"), "1,2, 3");
+		assert_eq!(space_out_digits("12,34"), "1 2,3 4");
+	}
+	#[test]
+	fn decimals_are_read_with_point() {
+		assert_eq!(space_out_digits("pi is 3.14"), "pi is 3 point 1 4");
+		assert_eq!(space_out_digits("1,234.5"), "1 2 3 4 point 5");
+	}
+	#[test]
+	fn trailing_period_is_not_a_decimal() {
+		assert_eq!(space_out_digits("It costs 20."), "It costs 2 0.");
+	}
+	#[test]
+	fn mixed_alphanumerics_are_left_alone() {
+		assert_eq!(space_out_digits("k8s and mp3"), "k8s and mp3");
+		assert_eq!(space_out_digits("123abc"), "123abc");
+		assert_eq!(space_out_digits("abc123"), "abc123");
+	}
+	#[test]
+	fn unicode_text_is_preserved() {
+		assert_eq!(space_out_digits("café 42 ünïcode"), "café 4 2 ünïcode");
+	}
+}
@@ -1,4 +1,4 @@
-use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::{AtomicBool, AtomicI8, AtomicUsize, Ordering};
 
 use circular_queue::CircularQueue;
 use eyre::WrapErr;
@@ -7,22 +7,36 @@ use tokio::sync::{mpsc::Sender, Mutex};
 use tracing::{debug, Instrument};
 use zbus::{fdo::DBusProxy, names::BusName, zvariant::ObjectPath, MatchRule, MessageType};
 
+use crate::children_batch::{ChildrenBatch, MAX_BATCH_SIZE};
+use crate::marks::Marks;
+use crate::message_history::MessageHistory;
+use crate::number_format::{self, NumberFormat};
+use crate::speech_text::prepare_for_speech;
+use crate::watchdog::InFlight;
 use atspi_common::{
 	events::{EventProperties, HasMatchRule, HasRegistryEventString},
-	Event,
+	Event, Granularity,
 };
 use atspi_connection::AccessibilityConnection;
 use atspi_proxies::{accessible::AccessibleProxy, cache::CacheProxy};
 use odilia_cache::Convertable;
 use odilia_cache::{AccessibleExt, AccessiblePrimitive, Cache, CacheItem};
 use odilia_common::{
-	errors::CacheError,
 	modes::ScreenReaderMode,
-	settings::{speech::PunctuationSpellingMode, ApplicationConfig},
-	types::TextSelectionArea,
+	settings::{
+		priority::{EventCategory, SpeechPriority, VoiceOverride},
+		speech::PunctuationSpellingMode,
+		ApplicationConfig,
+	},
+	types::{AriaLive, TextSelectionArea},
 	Result as OdiliaResult,
 };
-use std::sync::Arc;
+use odilia_tts::SpeechParams;
+use std::{
+	collections::HashMap,
+	sync::Arc,
+	time::{Duration, Instant},
+};
 
 #[allow(clippy::module_name_repetitions)]
 pub struct ScreenReaderState {
@@ -34,6 +48,78 @@ pub struct ScreenReaderState {
 	pub accessible_history: Mutex<CircularQueue<AccessiblePrimitive>>,
 	pub event_history: Mutex<CircularQueue<Event>>,
 	pub cache: Arc<Cache>,
+	pub config: ApplicationConfig,
+	pub last_busy_announcement: Mutex<Option<Instant>>,
+	pub last_value_announcement: Mutex<Option<Instant>>,
+	/// When the newest value change held back by the rate limit was, while it waits to be read.
+	pub pending_value: Mutex<Option<Instant>>,
+	pub pending_deletions: Mutex<HashMap<AccessiblePrimitive, PendingDeletion>>,
+	pub marks: Mutex<Marks>,
+	pub message_history: Mutex<MessageHistory>,
+	/// The modal dialog which is currently open, if any.
+	pub active_modal: Mutex<Option<AccessiblePrimitive>>,
+	/// The live region attributes of each accessible whose text has changed, so that every insertion does not ask the application for them again.
+	/// Kept up to date by `AttributesChanged` events.
+	pub live_attributes: Mutex<HashMap<AccessiblePrimitive, HashMap<String, String>>>,
+	/// The indentation, in columns, of the last line read in each accessible.
+	pub last_indentation: Mutex<HashMap<AccessiblePrimitive, usize>>,
+	/// Where tree navigation is, when it has been used since focus last moved.
+	pub nav_cursor: Mutex<Option<AccessiblePrimitive>>,
+	/// The text whose links are being moved through, and the index of the link last read in it.
+	pub link_cursor: Mutex<Option<(AccessiblePrimitive, usize)>>,
+	/// The newest focus announcement, held back briefly in case focus moves on again straight away.
+	pub pending_focus: Mutex<Option<PendingFocus>>,
+	/// When the newest change of selection inside a multi-select container was, while its count of selected items is held back.
+	pub pending_selection_count: Mutex<Option<Instant>>,
+	/// When the newest change to the options an editable combo box offers was, while their count is held back.
+	pub pending_autocomplete: Mutex<Option<Instant>>,
+	/// The list of options last counted for an editable combo box, and how many options it had, so only changes to the count are read.
+	pub autocomplete_count: Mutex<Option<(AccessiblePrimitive, usize)>>,
+	/// Set when the user asks for the next focused item to be read in full detail; cleared once it has been.
+	pub verbose_next: AtomicBool,
+	/// Set when the user asks for the next thing said to be spoken as important, so nothing interrupts it; cleared once it has been.
+	pub important_next: AtomicBool,
+	/// When the pointer was last looked under, and what was read there.
+	pub mouse_review: Mutex<MouseReview>,
+	/// The rate speech is currently spoken at, which starts out as the configured one and can be changed with commands.
+	pub speech_rate: AtomicI8,
+	/// The formatting last read at the caret, and the rich text field it was read in.
+	pub caret_formatting: Mutex<Option<(AccessiblePrimitive, Vec<&'static str>)>>,
+	/// Whether moving the caret by a character also reads the word it is in; toggled with a command.
+	pub speak_caret_context: AtomicBool,
+	/// Whether focused items are read by the text they show instead of their accessible name; toggled with a command.
+	pub read_display_text: AtomicBool,
+	/// Whether structural navigation commands are acted on; toggled with a command, so that they can be left to the application.
+	pub structural_navigation: AtomicBool,
+	/// How much text is read when the caret moves into a different text field; starts out as the configured granularity and can be changed with a command.
+	pub granularity: Mutex<Granularity>,
+	/// The accessibility event handlers which are running, checked by the watchdog.
+	pub handlers_in_flight: InFlight,
+	/// Added and removed children waiting to be handled together.
+	pub children_batch: Mutex<ChildrenBatch>,
+}
+
+/// Text which was just deleted from an accessible, kept around briefly in case it is immediately replaced.
+#[derive(Debug, Clone)]
+pub struct PendingDeletion {
+	pub text: String,
+	pub start_pos: usize,
+	pub at: Instant,
+}
+
+/// An item which just gained focus, waiting to be announced unless focus moves somewhere else first.
+#[derive(Debug, Clone)]
+pub struct PendingFocus {
+	pub object: AccessiblePrimitive,
+	pub at: Instant,
+}
+
+/// What was last read because the pointer moved over it.
+#[derive(Debug, Default)]
+pub struct MouseReview {
+	/// When the pointer last moved, while what is under it waits to be read.
+	pub moved_at: Option<Instant>,
+	pub item: Option<AccessiblePrimitive>,
 }
 
 impl ScreenReaderState {
@@ -53,14 +139,23 @@ impl ScreenReaderState {
 			.await
 			.wrap_err("Failed to create org.freedesktop.DBus proxy")?;
 
-		let mode = Mutex::new(ScreenReaderMode { name: "CommandMode".to_string() });
+		let mode = Mutex::new(ScreenReaderMode::default());
 
 		tracing::debug!("Reading configuration");
 
 		let previous_caret_position = AtomicUsize::new(0);
 		let accessible_history = Mutex::new(CircularQueue::with_capacity(16));
 		let event_history = Mutex::new(CircularQueue::with_capacity(16));
-		let cache = Arc::new(Cache::new(atspi.connection().clone()));
+		let cache = if config.cache.enabled {
+			Cache::new(atspi.connection().clone())
+		} else {
+			tracing::warn!("Caching is disabled; every accessible will be fetched from its application each time it is needed.");
+			Cache::uncached(atspi.connection().clone())
+		};
+		let cache =
+			Arc::new(cache.with_query_timeout(Duration::from_millis(
+				config.cache.query_timeout_ms,
+			)));
 		ssip.send(SSIPRequest::SetPitch(
 			ssip_client_async::ClientScope::Current,
 			config.speech.pitch,
@@ -71,11 +166,14 @@ impl ScreenReaderState {
 			config.speech.volume,
 		))
 		.await?;
-		ssip.send(SSIPRequest::SetOutputModule(
-			ssip_client_async::ClientScope::Current,
-			config.speech.module.clone(),
-		))
-		.await?;
+		// an empty module means the configured one was not available, so keep speech-dispatcher's default
+		if !config.speech.module.is_empty() {
+			ssip.send(SSIPRequest::SetOutputModule(
+				ssip_client_async::ClientScope::Current,
+				config.speech.module.clone(),
+			))
+			.await?;
+		}
 		ssip.send(SSIPRequest::SetLanguage(
 			ssip_client_async::ClientScope::Current,
 			config.speech.language.clone(),
@@ -103,6 +201,12 @@ impl ScreenReaderState {
 			config.speech.rate,
 		))
 		.await?;
+		let speech_rate = AtomicI8::new(config.speech.rate);
+		let granularity = Mutex::new(config.navigation.default_granularity.into());
+		let children_batch = Mutex::new(ChildrenBatch::new(
+			Duration::from_millis(config.cache.children_changed_window_ms),
+			MAX_BATCH_SIZE,
+		));
 		Ok(Self {
 			atspi,
 			dbus,
@@ -112,6 +216,33 @@ impl ScreenReaderState {
 			accessible_history,
 			event_history,
 			cache,
+			config,
+			last_busy_announcement: Mutex::new(None),
+			last_value_announcement: Mutex::new(None),
+			pending_value: Mutex::new(None),
+			pending_deletions: Mutex::new(HashMap::new()),
+			marks: Mutex::new(Marks::default()),
+			message_history: Mutex::new(MessageHistory::default()),
+			active_modal: Mutex::new(None),
+			live_attributes: Mutex::new(HashMap::new()),
+			last_indentation: Mutex::new(HashMap::new()),
+			nav_cursor: Mutex::new(None),
+			link_cursor: Mutex::new(None),
+			pending_focus: Mutex::new(None),
+			pending_selection_count: Mutex::new(None),
+			pending_autocomplete: Mutex::new(None),
+			autocomplete_count: Mutex::new(None),
+			verbose_next: AtomicBool::new(false),
+			important_next: AtomicBool::new(false),
+			mouse_review: Mutex::new(MouseReview::default()),
+			speech_rate,
+			caret_formatting: Mutex::new(None),
+			speak_caret_context: AtomicBool::new(false),
+			read_display_text: AtomicBool::new(false),
+			structural_navigation: AtomicBool::new(true),
+			granularity,
+			handlers_in_flight: InFlight::default(),
+			children_batch,
 		})
 	}
 	#[tracing::instrument(level = "debug", skip(self), err)]
@@ -120,15 +251,17 @@ impl ScreenReaderState {
 		atspi_cache_item: atspi_common::CacheItem,
 	) -> OdiliaResult<CacheItem> {
 		let prim = atspi_cache_item.object.clone().into();
-		if self.cache.get(&prim).is_none() {
-			self.cache.add(CacheItem::from_atspi_cache_item(
+		if let Some(cache_item) = self.cache.get(&prim) {
+			return Ok(cache_item);
+		}
+		self.cache.add_and_get(
+			CacheItem::from_atspi_cache_item(
 				atspi_cache_item,
 				Arc::downgrade(&Arc::clone(&self.cache)),
 				self.atspi.connection(),
 			)
-			.await?)?;
-		}
-		self.cache.get(&prim).ok_or(CacheError::NoItem.into())
+			.await?,
+		)
 	}
 	#[tracing::instrument(level = "debug", skip(self), err)]
 	pub async fn get_or_create_atspi_legacy_cache_item_to_cache(
@@ -136,15 +269,17 @@ impl ScreenReaderState {
 		atspi_cache_item: atspi_common::LegacyCacheItem,
 	) -> OdiliaResult<CacheItem> {
 		let prim = atspi_cache_item.object.clone().into();
-		if self.cache.get(&prim).is_none() {
-			self.cache.add(CacheItem::from_atspi_legacy_cache_item(
+		if let Some(cache_item) = self.cache.get(&prim) {
+			return Ok(cache_item);
+		}
+		self.cache.add_and_get(
+			CacheItem::from_atspi_legacy_cache_item(
 				atspi_cache_item,
 				Arc::downgrade(&Arc::clone(&self.cache)),
 				self.atspi.connection(),
 			)
-			.await?)?;
-		}
-		self.cache.get(&prim).ok_or(CacheError::NoItem.into())
+			.await?,
+		)
 	}
 	#[tracing::instrument(skip_all, level = "debug", ret, err)]
 	pub async fn get_or_create_event_object_to_cache<T: EventProperties>(
@@ -152,15 +287,17 @@ impl ScreenReaderState {
 		event: &T,
 	) -> OdiliaResult<CacheItem> {
 		let prim = AccessiblePrimitive::from_event(event)?;
-		if self.cache.get(&prim).is_none() {
-			self.cache.add(CacheItem::from_atspi_event(
+		if let Some(cache_item) = self.cache.get(&prim) {
+			return Ok(cache_item);
+		}
+		self.cache.add_and_get(
+			CacheItem::from_atspi_event(
 				event,
 				Arc::downgrade(&Arc::clone(&self.cache)),
 				self.atspi.connection(),
 			)
-			.await?)?;
-		}
-		self.cache.get(&prim).ok_or(CacheError::NoItem.into())
+			.await?,
+		)
 	}
 
 	// TODO: use cache; this will uplift performance MASSIVELY, also TODO: use this function instad of manually generating speech every time.
@@ -233,18 +370,46 @@ impl ScreenReaderState {
 	pub async fn stop_speech(&self) -> bool {
 		self.ssip.send(SSIPRequest::Cancel(MessageScope::All)).await.is_ok()
 	}
+	pub async fn set_rate(&self, rate: i8) -> bool {
+		self.ssip
+			.send(SSIPRequest::SetRate(ssip_client_async::ClientScope::Current, rate))
+			.await
+			.is_ok()
+	}
 	#[tracing::instrument(name = "closing speech dispatcher connection", skip(self))]
 	pub async fn close_speech(&self) -> bool {
 		self.ssip.send(SSIPRequest::Quit).await.is_ok()
 	}
+	/// The priority to speak an event of the given category with, as configured by the user.
+	#[must_use]
+	pub fn priority(&self, category: EventCategory, live: Option<&AriaLive>) -> Priority {
+		ssip_priority(self.config.priorities.resolve(category, live))
+	}
+	/// How numbers should be worded, following the configured speech language.
+	#[must_use]
+	pub fn number_format(&self) -> &'static dyn NumberFormat {
+		number_format::for_locale(&self.config.speech.language)
+	}
+	/// Speak `text`, and remember it so the user can review it later.
 	#[tracing::instrument(skip(self))]
 	pub async fn say(&self, priority: Priority, text: String) -> bool {
+		let priority = take_priority(&self.important_next, priority);
+		if !self.speak(priority, text.clone()).await {
+			return false;
+		}
+		self.message_history.lock().await.record(text);
+		true
+	}
+	/// Speak `text` without adding it to the message history, for example when replaying the history itself.
+	#[tracing::instrument(skip(self))]
+	pub async fn speak(&self, priority: Priority, text: String) -> bool {
 		if self.ssip.send(SSIPRequest::SetPriority(priority)).await.is_err() {
 			return false;
 		}
 		if self.ssip.send(SSIPRequest::Speak).await.is_err() {
 			return false;
 		}
+		let text = prepare_for_speech(text, &self.config.speech);
 		// this crashed ssip-client because the connection is automatically stopped when invalid text is sent; since the period character on a line by itself is the stop character, there's not much we can do except filter it out explicitly.
 		if text == *"." {
 			return false;
@@ -276,9 +441,10 @@ impl ScreenReaderState {
 	}
 
 	/// Adds a new accessible to the history. We only store 16 previous accessibles, but theoretically, it should be lower.
+	/// Tree navigation starts again from the new accessible.
 	pub async fn update_accessible(&self, new_a11y: AccessiblePrimitive) {
-		let mut history = self.accessible_history.lock().await;
-		history.push(new_a11y);
+		self.accessible_history.lock().await.push(new_a11y);
+		*self.nav_cursor.lock().await = None;
 	}
 	pub async fn build_cache<'a, T>(&self, dest: T) -> OdiliaResult<CacheProxy<'a>>
 	where
@@ -331,3 +497,63 @@ impl ScreenReaderState {
 		Ok(())
 	}
 }
+
+/// Convert a configured priority into the one speech-dispatcher understands.
+/// Like the punctuation mode, this lives here so that `odilia_common` does not need to depend on `ssip_client_async`.
+#[must_use]
+pub fn ssip_priority(priority: SpeechPriority) -> Priority {
+	match priority {
+		SpeechPriority::Important => Priority::Important,
+		SpeechPriority::Message => Priority::Message,
+		SpeechPriority::Text => Priority::Text,
+		SpeechPriority::Notification => Priority::Notification,
+		SpeechPriority::Progress => Priority::Progress,
+	}
+}
+
+/// The priority to say something with: `requested`, unless the user asked for the next thing said to be important.
+/// That request is used up by this one message, so the one after keeps its own priority again.
+#[must_use]
+pub fn take_priority(important_next: &AtomicBool, requested: Priority) -> Priority {
+	if important_next.swap(false, Ordering::Relaxed) {
+		Priority::Important
+	} else {
+		requested
+	}
+}
+
+/// The rate and voice to speak each priority with, from the configured overrides.
+#[must_use]
+pub fn priority_voices(
+	voices: &HashMap<SpeechPriority, VoiceOverride>,
+) -> HashMap<Priority, SpeechParams> {
+	voices.iter()
+		.map(|(priority, voice)| {
+			let params = SpeechParams { rate: voice.rate, voice: voice.person.clone() };
+			(ssip_priority(*priority), params)
+		})
+		.collect()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::take_priority;
+	use ssip_client_async::Priority;
+	use std::sync::atomic::{AtomicBool, Ordering};
+
+	#[test]
+	fn important_next_is_used_up_by_one_message() {
+		let important_next = AtomicBool::new(false);
+		assert_eq!(take_priority(&important_next, Priority::Text), Priority::Text);
+		important_next.store(true, Ordering::Relaxed);
+		assert_eq!(
+			take_priority(&important_next, Priority::Notification),
+			Priority::Important
+		);
+		assert_eq!(
+			take_priority(&important_next, Priority::Notification),
+			Priority::Notification
+		);
+		assert_eq!(take_priority(&important_next, Priority::Message), Priority::Message);
+	}
+}
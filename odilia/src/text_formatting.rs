@@ -0,0 +1,248 @@
+//! Describing the formatting of text at the caret, like "bold, italic", from its text attributes.
+//! Formatting is read in full when focus enters a rich text field, and after that only when it changes as the caret moves.
+//! Spelling and grammar mistakes marked by the editor are read the same way, but only when the caret moves onto them.
+
+use crate::state::ScreenReaderState;
+use atspi_common::{Role, State, StateSet};
+use odilia_cache::CacheItem;
+use odilia_common::{errors::OdiliaError, settings::priority::EventCategory};
+use std::collections::HashMap;
+
+/// Roles which hold formatted text when they can be edited, unlike plain text entries.
+const RICH_TEXT_ROLES: [Role; 6] = [
+	Role::DocumentText,
+	Role::DocumentFrame,
+	Role::DocumentWeb,
+	Role::Paragraph,
+	Role::Section,
+	Role::Heading,
+];
+
+/// Whether an item is a rich text editor, whose formatting is worth reading.
+pub fn is_rich_text(role: Role, states: StateSet) -> bool {
+	RICH_TEXT_ROLES.contains(&role) && states.contains(State::Editable)
+}
+
+/// The formatting described by a set of text attributes, in a fixed order.
+/// Attributes which are at their plain value (like a `normal` style) are left out.
+pub fn formatting_names(attributes: &HashMap<String, String>) -> Vec<&'static str> {
+	let value = |name: &str| attributes.get(name).map(String::as_str);
+	let mut names = Vec::new();
+	let bold = match value("weight") {
+		Some("bold") => true,
+		Some(weight) => weight.parse::<u32>().is_ok_and(|weight| weight >= 600),
+		None => false,
+	};
+	if bold {
+		names.push("bold");
+	}
+	if matches!(value("style"), Some("italic" | "oblique")) {
+		names.push("italic");
+	}
+	// an underline of "error" marks a spelling mistake, not formatting
+	if value("underline").is_some_and(|underline| !matches!(underline, "none" | "error")) {
+		names.push("underlined");
+	}
+	if value("strikethrough").is_some_and(|strike| !matches!(strike, "false" | "none")) {
+		names.push("strikethrough");
+	}
+	names
+}
+
+/// What is said for text the editor has marked as a mistake.
+const ERROR_MARKERS: [&str; 2] = ["misspelled word", "grammar error"];
+
+/// The mistakes an editor has marked in a set of text attributes, from the `invalid` attribute.
+/// Some toolkits only draw an `error` underline under misspelled words, so that is read as a spelling mistake too.
+pub fn error_names(attributes: &HashMap<String, String>) -> Vec<&'static str> {
+	let invalid = attributes.get("invalid").map(String::as_str);
+	let error_underline = attributes
+		.get("underline")
+		.is_some_and(|underline| underline == "error");
+	match (invalid, error_underline) {
+		(Some("grammar"), _) => vec![ERROR_MARKERS[1]],
+		(Some("spelling"), _) | (_, true) => vec![ERROR_MARKERS[0]],
+		_ => Vec::new(),
+	}
+}
+
+/// Why the formatting at the caret is being looked at.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CaretContext {
+	/// Focus has just entered the field.
+	FocusEntered,
+	/// The caret has moved within the field.
+	CaretMoved,
+	/// The formatting at the caret changed without the caret moving, like when bold is turned on while typing.
+	AttributesChanged,
+}
+
+/// What to say about the formatting at the caret, if anything.
+/// On entry all of the formatting is read; after that only what changed since `previous`, with formatting which ended said as "not bold".
+/// Leaving a mistake is not mentioned, since only the text which is wrong is worth knowing about.
+pub fn formatting_announcement(
+	context: CaretContext,
+	previous: Option<&[&str]>,
+	current: &[&str],
+) -> Option<String> {
+	let parts: Vec<String> = match (context, previous) {
+		(CaretContext::FocusEntered, _) => {
+			current.iter().map(ToString::to_string).collect()
+		}
+		// the formatting was never read on entry, so there is nothing to compare it to
+		(CaretContext::CaretMoved | CaretContext::AttributesChanged, None) => Vec::new(),
+		(CaretContext::CaretMoved | CaretContext::AttributesChanged, Some(previous)) => {
+			let started = current
+				.iter()
+				.filter(|name| !previous.contains(name))
+				.map(ToString::to_string);
+			let ended = previous
+				.iter()
+				.filter(|name| {
+					!current.contains(name) && !ERROR_MARKERS.contains(name)
+				})
+				.map(|name| format!("not {name}"));
+			started.chain(ended).collect()
+		}
+	};
+	(!parts.is_empty()).then(|| parts.join(", "))
+}
+
+/// Say the formatting and marked mistakes at the caret in `item`, if it is a rich text field and the user wants either read.
+/// The formatting is remembered, so the next caret move only mentions what changed.
+#[tracing::instrument(level = "debug", skip(state, item), err)]
+pub async fn announce_formatting(
+	state: &ScreenReaderState,
+	item: &CacheItem,
+	context: CaretContext,
+) -> Result<(), OdiliaError> {
+	let announcements = &state.config.announcements;
+	if !announcements.reads_text_attributes() || !is_rich_text(item.role, item.states) {
+		*state.caret_formatting.lock().await = None;
+		return Ok(());
+	}
+	let text = item.object.clone().into_text(state.atspi.connection()).await?;
+	let caret = text.caret_offset().await?;
+	// the defaults are included, since a field can be bold throughout
+	let (attributes, _, _) = text.get_attribute_run(caret, true).await?;
+	let mut current = Vec::new();
+	if announcements.text_formatting {
+		current.extend(formatting_names(&attributes));
+	}
+	if announcements.spelling_errors {
+		current.extend(error_names(&attributes));
+	}
+	let announcement = {
+		let mut last = state.caret_formatting.lock().await;
+		let previous = last
+			.as_ref()
+			.filter(|(object, _)| *object == item.object)
+			.map(|(_, names)| names.as_slice());
+		let announcement = formatting_announcement(context, previous, &current);
+		*last = Some((item.object.clone(), current));
+		announcement
+	};
+	if let Some(announcement) = announcement {
+		state.say(state.priority(EventCategory::Caret, None), announcement)
+			.await;
+	}
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{
+		error_names, formatting_announcement, formatting_names, is_rich_text, CaretContext,
+	};
+	use atspi_common::{Role, State, StateSet};
+	use std::collections::HashMap;
+
+	fn attributes(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+		pairs.iter()
+			.map(|(name, value)| ((*name).to_string(), (*value).to_string()))
+			.collect()
+	}
+
+	#[test]
+	fn attributes_are_named() {
+		let bold_italic = attributes(&[("weight", "700"), ("style", "italic")]);
+		assert_eq!(formatting_names(&bold_italic), ["bold", "italic"]);
+		let plain = attributes(&[
+			("weight", "400"),
+			("style", "normal"),
+			("underline", "none"),
+		]);
+		assert!(formatting_names(&plain).is_empty());
+		let marked = attributes(&[("underline", "single"), ("strikethrough", "true")]);
+		assert_eq!(formatting_names(&marked), ["underlined", "strikethrough"]);
+		assert!(formatting_names(&attributes(&[("underline", "error")])).is_empty());
+	}
+	#[test]
+	fn entering_a_field_reads_all_of_its_formatting() {
+		let announcement = formatting_announcement(
+			CaretContext::FocusEntered,
+			None,
+			&["bold", "italic"],
+		);
+		assert_eq!(announcement.as_deref(), Some("bold, italic"));
+		let previous = ["bold"];
+		assert_eq!(
+			formatting_announcement(
+				CaretContext::FocusEntered,
+				Some(&previous[..]),
+				&[]
+			),
+			None
+		);
+	}
+	#[test]
+	fn moving_the_caret_reads_only_changes() {
+		let moved = |previous: &[&str], current: &[&str]| {
+			formatting_announcement(CaretContext::CaretMoved, Some(previous), current)
+		};
+		assert_eq!(moved(&["bold"], &["bold"]), None);
+		assert_eq!(moved(&["bold"], &["bold", "italic"]).as_deref(), Some("italic"));
+		assert_eq!(moved(&["bold"], &["italic"]).as_deref(), Some("italic, not bold"));
+		assert_eq!(
+			formatting_announcement(CaretContext::CaretMoved, None, &["bold"]),
+			None
+		);
+		let toggled = formatting_announcement(
+			CaretContext::AttributesChanged,
+			Some(&["italic"][..]),
+			&["bold", "italic"],
+		);
+		assert_eq!(toggled.as_deref(), Some("bold"));
+	}
+	#[test]
+	fn marked_mistakes_are_named() {
+		let spelling = attributes(&[("invalid", "spelling"), ("underline", "error")]);
+		assert_eq!(error_names(&spelling), ["misspelled word"]);
+		assert_eq!(error_names(&attributes(&[("invalid", "grammar")])), ["grammar error"]);
+		assert_eq!(
+			error_names(&attributes(&[("underline", "error")])),
+			["misspelled word"]
+		);
+		assert!(error_names(&attributes(&[("invalid", "false")])).is_empty());
+		assert!(error_names(&attributes(&[("weight", "700")])).is_empty());
+	}
+	#[test]
+	fn leaving_a_mistake_is_not_read() {
+		let moved = |previous: &[&str], current: &[&str]| {
+			formatting_announcement(CaretContext::CaretMoved, Some(previous), current)
+		};
+		assert_eq!(
+			moved(&["bold"], &["bold", "misspelled word"]).as_deref(),
+			Some("misspelled word")
+		);
+		assert_eq!(moved(&["misspelled word"], &["misspelled word"]), None);
+		assert_eq!(moved(&["bold", "grammar error"], &[]).as_deref(), Some("not bold"));
+	}
+	#[test]
+	fn only_editable_documents_are_rich_text() {
+		let editable = StateSet::new(State::Editable | State::Focusable);
+		assert!(is_rich_text(Role::DocumentText, editable));
+		assert!(!is_rich_text(Role::DocumentText, StateSet::new(State::Focusable)));
+		assert!(!is_rich_text(Role::Entry, editable));
+	}
+}
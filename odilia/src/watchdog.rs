@@ -0,0 +1,158 @@
+//! A watchdog which notices when accessibility events stop being handled.
+//! Without it, a stuck event handler (like the deadlock zbus runs into when the event channel fills up) leaves Odilia silent with no sign of why.
+
+use crate::state::ScreenReaderState;
+use atspi_common::Event;
+use odilia_common::settings::watchdog::StallAction;
+use std::{
+	collections::HashMap,
+	sync::{
+		atomic::{AtomicU64, Ordering},
+		Arc, Mutex, PoisonError,
+	},
+	time::{Duration, Instant},
+};
+use tokio::sync::mpsc::Sender;
+use tokio_util::sync::CancellationToken;
+
+/// How often the watchdog checks on event handling.
+pub const CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// The event handlers which are running, by when each one started.
+/// Handlers are spawned as their own tasks, so events keep being picked up even when every handler is stuck; only how long the handlers take shows a stall.
+#[derive(Debug, Default)]
+pub struct InFlight {
+	next_id: AtomicU64,
+	started: Mutex<HashMap<u64, Instant>>,
+}
+
+impl InFlight {
+	/// Record that a handler has started; it counts as running until the returned guard is dropped, even if the handler panics.
+	pub fn start(&self) -> InFlightGuard<'_> {
+		let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+		self.started
+			.lock()
+			.unwrap_or_else(PoisonError::into_inner)
+			.insert(id, Instant::now());
+		InFlightGuard { in_flight: self, id }
+	}
+	/// When the handler which has been running the longest started, or `None` when none are running.
+	#[must_use]
+	pub fn oldest(&self) -> Option<Instant> {
+		self.started
+			.lock()
+			.unwrap_or_else(PoisonError::into_inner)
+			.values()
+			.min()
+			.copied()
+	}
+}
+
+/// A running handler, which is forgotten once this is dropped.
+#[derive(Debug)]
+#[must_use = "the handler only counts as running while this is held"]
+pub struct InFlightGuard<'a> {
+	in_flight: &'a InFlight,
+	id: u64,
+}
+
+impl Drop for InFlightGuard<'_> {
+	fn drop(&mut self) {
+		self.in_flight
+			.started
+			.lock()
+			.unwrap_or_else(PoisonError::into_inner)
+			.remove(&self.id);
+	}
+}
+
+/// Whether more than `threshold` has gone by since `since`.
+#[must_use]
+pub fn is_stalled(now: Instant, since: Instant, threshold: Duration) -> bool {
+	now.saturating_duration_since(since) > threshold
+}
+
+/// Check on event handling every [`CHECK_INTERVAL`], and report when a handler has been running for too long.
+/// Each stall is reported once; the watchdog reports again only after every stuck handler has finished and another gets stuck.
+#[tracing::instrument(level = "debug", skip_all)]
+pub async fn watch(
+	state: Arc<ScreenReaderState>,
+	events: Sender<Event>,
+	shutdown: CancellationToken,
+) {
+	let settings = &state.config.watchdog;
+	let threshold = Duration::from_secs(settings.stall_seconds);
+	let mut interval = tokio::time::interval(CHECK_INTERVAL);
+	let mut reported = false;
+	loop {
+		tokio::select! {
+			_ = interval.tick() => {
+				let stalled = state
+					.handlers_in_flight
+					.oldest()
+					.filter(|started| is_stalled(Instant::now(), *started, threshold));
+				let Some(started) = stalled else {
+					reported = false;
+					continue;
+				};
+				if reported {
+					continue;
+				}
+				reported = true;
+				let waiting = events.max_capacity() - events.capacity();
+				tracing::error!(waiting, stalled_for = ?started.elapsed(), "Accessibility events are no longer being handled; Odilia may be stuck.");
+				if settings.on_stall == StallAction::Shutdown {
+					tracing::error!("Shutting down because event handling is stuck.");
+					shutdown.cancel();
+				}
+			}
+			() = shutdown.cancelled() => {
+				tracing::debug!("Shutting down the watchdog.");
+				break;
+			}
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{is_stalled, InFlight};
+	use std::time::{Duration, Instant};
+
+	#[test]
+	fn stalls_only_after_the_threshold() {
+		let threshold = Duration::from_secs(10);
+		let last_progress = Instant::now();
+		assert!(!is_stalled(last_progress, last_progress, threshold));
+		assert!(!is_stalled(
+			last_progress + Duration::from_secs(10),
+			last_progress,
+			threshold
+		));
+		assert!(is_stalled(
+			last_progress + Duration::from_secs(11),
+			last_progress,
+			threshold
+		));
+		// a handler which started after the check is not stalled
+		assert!(!is_stalled(
+			last_progress,
+			last_progress + Duration::from_secs(1),
+			threshold
+		));
+	}
+	#[test]
+	fn the_oldest_running_handler_is_tracked() {
+		let in_flight = InFlight::default();
+		assert_eq!(in_flight.oldest(), None);
+		let first = in_flight.start();
+		let oldest = in_flight.oldest().expect("a handler is running");
+		std::thread::sleep(Duration::from_millis(5));
+		let second = in_flight.start();
+		assert_eq!(in_flight.oldest(), Some(oldest));
+		drop(first);
+		assert!(in_flight.oldest().is_some_and(|started| started > oldest));
+		drop(second);
+		assert_eq!(in_flight.oldest(), None);
+	}
+}
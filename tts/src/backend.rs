@@ -0,0 +1,71 @@
+//! Speech engines Odilia can speak through, behind a common interface.
+//! Odilia itself only ever sends SSIP requests down a channel; [`run_backend`] turns those into calls on whichever backend was configured.
+
+use ssip_client_async::{Priority, Request};
+use std::{future::Future, pin::Pin};
+use tokio::sync::mpsc::Receiver;
+use tokio_util::sync::CancellationToken;
+
+/// The result of a [`TtsBackend`] operation, boxed so the trait can be used as `dyn TtsBackend`.
+pub type BackendFuture<'a> = Pin<Box<dyn Future<Output = eyre::Result<()>> + Send + 'a>>;
+
+/// A speech engine: something which can speak text, stop speaking, and change how it speaks.
+pub trait TtsBackend: Send {
+	/// Speak `text`; whether it interrupts, waits for, or is dropped in favour of other speech depends on `priority`.
+	fn speak(&mut self, priority: Priority, text: String) -> BackendFuture<'_>;
+	/// Stop speaking, dropping anything which is queued.
+	fn stop(&mut self) -> BackendFuture<'_>;
+	/// Change the rate of speech, from -100 (slowest) to 100 (fastest).
+	fn set_rate(&mut self, rate: i8) -> BackendFuture<'_>;
+	/// Change the voice speech is spoken in.
+	fn set_voice(&mut self, voice: String) -> BackendFuture<'_>;
+	/// Apply any other SSIP request, like a change of pitch or language.
+	/// Backends which can not do anything with it ignore it, which is the default.
+	fn configure(&mut self, request: Request) -> BackendFuture<'_> {
+		tracing::trace!(?request, "Request is not supported by this backend; ignoring it");
+		Box::pin(async { Ok(()) })
+	}
+	/// Say goodbye and release the engine, once Odilia is shutting down.
+	fn close(&mut self) -> BackendFuture<'_> {
+		Box::pin(async { Ok(()) })
+	}
+}
+
+/// A handler task which speaks incoming SSIP requests through `backend`.
+/// This function will run untill it gets canceled via the cancellation token, or every sender is dropped.
+///
+/// # Errors
+///
+/// If the backend fails to carry out any request, or to close.
+#[tracing::instrument(level = "debug", skip_all, err)]
+pub async fn run_backend(
+	mut backend: Box<dyn TtsBackend>,
+	mut requests: Receiver<Request>,
+	shutdown: CancellationToken,
+) -> eyre::Result<()> {
+	// odilia sets the priority right before every message
+	let mut priority = Priority::Text;
+	loop {
+		tokio::select! {
+			request_option = requests.recv() => {
+				let Some(request) = request_option else { break };
+				tracing::debug!(?request, "SSIP command received");
+				match request {
+					Request::SetPriority(new_priority) => priority = new_priority,
+					// the text follows in its own request
+					Request::Speak => {}
+					Request::SendLine(line) => backend.speak(priority.clone(), line).await?,
+					Request::SendLines(lines) => backend.speak(priority.clone(), lines.join("\n")).await?,
+					Request::Cancel(_) | Request::Stop(_) => backend.stop().await?,
+					Request::SetRate(_, rate) => backend.set_rate(rate).await?,
+					Request::SetSynthesisVoice(_, voice) => backend.set_voice(voice).await?,
+					request => backend.configure(request).await?,
+				}
+			}
+			() = shutdown.cancelled() => break,
+		}
+	}
+	backend.close().await?;
+	tracing::debug!("Speech backend shutdown completed");
+	Ok(())
+}
@@ -0,0 +1,169 @@
+//! A backend which speaks by running a text-to-speech program, like `espeak-ng`, for systems without speech-dispatcher.
+
+use crate::backend::{BackendFuture, TtsBackend};
+use eyre::Context;
+use ssip_client_async::Priority;
+use std::process::Stdio;
+use tokio::{
+	io::AsyncWriteExt,
+	process::{Child, Command},
+};
+
+/// Replaced in each argument of the command by the current rate, from -100 to 100.
+pub const RATE_PLACEHOLDER: &str = "{rate}";
+/// Replaced in each argument of the command by the current voice.
+pub const VOICE_PLACEHOLDER: &str = "{voice}";
+/// The command spoken through when none is configured.
+pub const DEFAULT_COMMAND: [&str; 2] = ["espeak-ng", "--stdin"];
+
+/// The arguments to run the command `template` with, after filling in the placeholders.
+/// The first item of `template` is the program itself, and is left as it is.
+#[must_use]
+pub fn command_arguments(template: &[String], rate: i8, voice: &str) -> Vec<String> {
+	template.iter()
+		.skip(1)
+		.map(|argument| {
+			argument.replace(RATE_PLACEHOLDER, &rate.to_string())
+				.replace(VOICE_PLACEHOLDER, voice)
+		})
+		.collect()
+}
+
+/// Speaks each message by running a command which reads the text to speak from its standard input.
+/// A new message interrupts the one being spoken, except for notifications and progress updates, which are dropped while anything is still speaking, like speech-dispatcher does.
+/// Important messages are never interrupted; a new message waits for them to finish instead.
+#[derive(Debug)]
+pub struct CommandBackend {
+	template: Vec<String>,
+	rate: i8,
+	voice: String,
+	speaking: Option<(Priority, Child)>,
+}
+
+impl CommandBackend {
+	/// A backend running `template`, a program followed by its arguments, which may contain [`RATE_PLACEHOLDER`] and [`VOICE_PLACEHOLDER`].
+	/// An empty `template` runs [`DEFAULT_COMMAND`].
+	#[must_use]
+	pub fn new(template: Vec<String>) -> Self {
+		let template = if template.is_empty() {
+			DEFAULT_COMMAND.iter().map(ToString::to_string).collect()
+		} else {
+			template
+		};
+		Self { template, rate: 0, voice: String::new(), speaking: None }
+	}
+	/// Whether the last message is still being spoken.
+	fn is_speaking(&mut self) -> bool {
+		self.speaking
+			.as_mut()
+			.is_some_and(|(_, child)| matches!(child.try_wait(), Ok(None)))
+	}
+	async fn stop_now(&mut self) -> eyre::Result<()> {
+		if let Some((_, mut child)) = self.speaking.take() {
+			// the command may have finished on its own in the meantime, which is fine
+			let _ = child.start_kill();
+			child.wait()
+				.await
+				.wrap_err("Could not wait for the speech command to stop")?;
+		}
+		Ok(())
+	}
+	/// Let an important message finish, or stop whatever else is being spoken.
+	async fn finish_or_stop(&mut self) -> eyre::Result<()> {
+		match self.speaking.take() {
+			Some((Priority::Important, mut child)) => {
+				child.wait().await.wrap_err(
+					"Could not wait for the speech command to finish",
+				)?;
+				Ok(())
+			}
+			speaking => {
+				self.speaking = speaking;
+				self.stop_now().await
+			}
+		}
+	}
+	async fn speak_now(&mut self, priority: Priority, text: &str) -> eyre::Result<()> {
+		if matches!(priority, Priority::Notification | Priority::Progress)
+			&& self.is_speaking()
+		{
+			tracing::trace!(
+				?priority,
+				"Dropping message; something else is being spoken"
+			);
+			return Ok(());
+		}
+		self.finish_or_stop().await?;
+		let Some(program) = self.template.first() else {
+			eyre::bail!("The speech command is empty");
+		};
+		let mut child = Command::new(program)
+			.args(command_arguments(&self.template, self.rate, &self.voice))
+			.stdin(Stdio::piped())
+			.stdout(Stdio::null())
+			.stderr(Stdio::null())
+			.spawn()
+			.wrap_err_with(|| {
+				format!("Could not run the speech command `{program}`")
+			})?;
+		if let Some(mut stdin) = child.stdin.take() {
+			// dropping stdin closes it, which tells the command there is nothing more to read
+			stdin.write_all(format!("{text}\n").as_bytes())
+				.await
+				.wrap_err("Could not send text to the speech command")?;
+		}
+		self.speaking = Some((priority, child));
+		Ok(())
+	}
+}
+
+impl TtsBackend for CommandBackend {
+	fn speak(&mut self, priority: Priority, text: String) -> BackendFuture<'_> {
+		Box::pin(async move { self.speak_now(priority, &text).await })
+	}
+	fn stop(&mut self) -> BackendFuture<'_> {
+		Box::pin(self.stop_now())
+	}
+	fn set_rate(&mut self, rate: i8) -> BackendFuture<'_> {
+		self.rate = rate;
+		Box::pin(async { Ok(()) })
+	}
+	fn set_voice(&mut self, voice: String) -> BackendFuture<'_> {
+		self.voice = voice;
+		Box::pin(async { Ok(()) })
+	}
+	fn close(&mut self) -> BackendFuture<'_> {
+		Box::pin(self.stop_now())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{command_arguments, CommandBackend, DEFAULT_COMMAND};
+
+	fn template(parts: &[&str]) -> Vec<String> {
+		parts.iter().map(ToString::to_string).collect()
+	}
+
+	#[test]
+	fn placeholders_are_filled_in() {
+		let espeak = template(&["espeak-ng", "--stdin", "-v", "{voice}", "--rate={rate}"]);
+		assert_eq!(
+			command_arguments(&espeak, -20, "en-us"),
+			["--stdin", "-v", "en-us", "--rate=-20"]
+		);
+	}
+	#[test]
+	fn arguments_without_placeholders_are_kept() {
+		let custom = template(&["/usr/local/bin/say", "--quiet", "{rate}{rate}"]);
+		assert_eq!(command_arguments(&custom, 5, "ignored"), ["--quiet", "55"]);
+		assert!(command_arguments(&template(&["espeak-ng"]), 0, "").is_empty());
+		assert!(command_arguments(&[], 0, "").is_empty());
+	}
+	#[test]
+	fn empty_command_falls_back_to_espeak() {
+		assert_eq!(CommandBackend::new(Vec::new()).template, DEFAULT_COMMAND);
+		let custom = template(&["say"]);
+		assert_eq!(CommandBackend::new(custom.clone()).template, custom);
+	}
+}
@@ -10,9 +10,11 @@
 
 use eyre::Context;
 use ssip_client_async::{
-	fifo::asynchronous_tokio::Builder, tokio::AsyncClient, ClientName, Request,
+	fifo::asynchronous_tokio::Builder, tokio::AsyncClient, ClientName, ClientScope,
+	MessageScope, Priority, Request, Response,
 };
 use std::{
+	collections::HashMap,
 	io::ErrorKind,
 	process::{exit, Command, Stdio},
 	time,
@@ -24,6 +26,15 @@ use tokio::{
 };
 use tokio_util::sync::CancellationToken;
 
+mod backend;
+pub use backend::{run_backend, BackendFuture, TtsBackend};
+mod command;
+pub use command::{
+	command_arguments, CommandBackend, DEFAULT_COMMAND, RATE_PLACEHOLDER, VOICE_PLACEHOLDER,
+};
+mod stdout;
+pub use stdout::{print_ssip_commands, StdoutSpeaker};
+
 /// Creates a new async SSIP client which can be sent commends, and can await responses to.
 /// # Errors
 /// There may be errors when trying to send the initial registration command, or when parsing the response.
@@ -66,49 +77,295 @@ pub async fn create_ssip_client(
 	Ok(ssip_core)
 }
 
-/// A handler task for incoming SSIP requests
+/// Asks speech-dispatcher for the list of output modules it has available (espeak-ng, pico, etc.).
+/// This must be called before the client is handed off to [`handle_ssip_commands`].
+/// # Errors
+/// Fails if the request can not be sent, or if the server answers with anything other than a list of modules.
+#[tracing::instrument(level = "debug", skip(client), ret, err)]
+pub async fn list_output_modules(
+	client: &mut AsyncClient<BufReader<OwnedReadHalf>, BufWriter<OwnedWriteHalf>>,
+) -> eyre::Result<Vec<String>> {
+	match client.send(Request::ListOutputModules).await?.receive().await? {
+		Response::OutputModulesListSent(modules) => Ok(modules),
+		other => Err(eyre::eyre!("Unexpected response to LIST OUTPUT_MODULES: {other:?}")),
+	}
+}
+
+/// Picks the output module to use out of the ones speech-dispatcher reports as `available`.
+/// The comparison ignores case, and the name is returned exactly as speech-dispatcher spelled it.
+/// `None` means the configured module can not be used, and speech-dispatcher's own default module should be left in place.
+#[must_use]
+pub fn select_output_module(configured: &str, available: &[String]) -> Option<String> {
+	if configured.is_empty() {
+		return None;
+	}
+	available
+		.iter()
+		.find(|module| module.eq_ignore_ascii_case(configured))
+		.cloned()
+}
+
+/// The rate and voice speech-dispatcher speaks with.
+/// `None` means the value has not been set, so speech-dispatcher's own default is in use.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SpeechParams {
+	pub rate: Option<i8>,
+	pub voice: Option<String>,
+}
+
+/// The params to speak a message of `priority` with: those its override sets, and `base` for the rest.
+#[must_use]
+pub fn effective_params(
+	base: &SpeechParams,
+	overrides: &HashMap<Priority, SpeechParams>,
+	priority: &Priority,
+) -> SpeechParams {
+	let Some(priority_params) = overrides.get(priority) else {
+		return base.clone();
+	};
+	SpeechParams {
+		rate: priority_params.rate.or(base.rate),
+		voice: priority_params.voice.clone().or_else(|| base.voice.clone()),
+	}
+}
+
+/// The requests which change speech-dispatcher's params from `current` to `desired`.
+/// Params which are already right, or which `desired` leaves unset, are not sent.
+#[must_use]
+pub fn param_requests(current: &SpeechParams, desired: &SpeechParams) -> Vec<Request> {
+	let mut requests = Vec::new();
+	if let Some(rate) = desired.rate.filter(|rate| current.rate != Some(*rate)) {
+		requests.push(Request::SetRate(ClientScope::Current, rate));
+	}
+	if let Some(voice) = desired
+		.voice
+		.as_ref()
+		.filter(|voice| current.voice.as_ref() != Some(*voice))
+	{
+		requests.push(Request::SetSynthesisVoice(ClientScope::Current, voice.clone()));
+	}
+	requests
+}
+
+/// Keeps track of the params speech-dispatcher is using, so the per-priority overrides can be applied to a single message and undone afterwards.
+#[derive(Debug, Default)]
+struct ParamTracker {
+	/// The params odilia asked for; what speech goes back to after an overridden message.
+	base: SpeechParams,
+	/// The params last sent to speech-dispatcher.
+	current: SpeechParams,
+	overrides: HashMap<Priority, SpeechParams>,
+}
+
+impl ParamTracker {
+	/// The requests to actually send for `request`, with any param changes needed around it.
+	fn requests_for(&mut self, request: Request) -> Vec<Request> {
+		match &request {
+			Request::SetRate(_, rate) => {
+				self.base.rate = Some(*rate);
+				self.current.rate = Some(*rate);
+				vec![request]
+			}
+			Request::SetSynthesisVoice(_, voice) => {
+				self.base.voice = Some(voice.clone());
+				self.current.voice = Some(voice.clone());
+				vec![request]
+			}
+			// the priority is always set right before a message is spoken, so this is where its params are applied
+			Request::SetPriority(priority) => {
+				let desired =
+					effective_params(&self.base, &self.overrides, priority);
+				let mut requests = param_requests(&self.current, &desired);
+				self.current = desired;
+				requests.push(request);
+				requests
+			}
+			// once the message has been sent, go back to the normal params
+			Request::SendLines(_) => {
+				let mut requests = vec![request];
+				requests.extend(param_requests(&self.current, &self.base));
+				self.current = self.base.clone();
+				requests
+			}
+			_ => vec![request],
+		}
+	}
+}
+
+/// The speech-dispatcher backend, speaking through an SSIP connection.
+/// Messages spoken with a priority in its overrides use the rate and voice given there, and the normal ones are restored once the message is sent.
+pub struct SpeechDispatcherBackend {
+	client: AsyncClient<BufReader<OwnedReadHalf>, BufWriter<OwnedWriteHalf>>,
+	params: ParamTracker,
+}
+
+impl SpeechDispatcherBackend {
+	#[must_use]
+	pub fn new(
+		client: AsyncClient<BufReader<OwnedReadHalf>, BufWriter<OwnedWriteHalf>>,
+		overrides: HashMap<Priority, SpeechParams>,
+	) -> Self {
+		Self { client, params: ParamTracker { overrides, ..ParamTracker::default() } }
+	}
+	/// Send `request`, along with any param changes needed around it, waiting for each response.
+	async fn send(&mut self, request: Request) -> eyre::Result<()> {
+		for request in self.params.requests_for(request) {
+			let response = self.client.send(request).await?.receive().await?;
+			tracing::debug!(?response, "Recieved response from server");
+		}
+		Ok(())
+	}
+}
+
+impl TtsBackend for SpeechDispatcherBackend {
+	fn speak(&mut self, priority: Priority, text: String) -> BackendFuture<'_> {
+		Box::pin(async move {
+			self.send(Request::SetPriority(priority)).await?;
+			self.send(Request::Speak).await?;
+			self.send(Request::SendLines(Vec::from([text]))).await
+		})
+	}
+	fn stop(&mut self) -> BackendFuture<'_> {
+		Box::pin(self.send(Request::Cancel(MessageScope::All)))
+	}
+	fn set_rate(&mut self, rate: i8) -> BackendFuture<'_> {
+		Box::pin(self.send(Request::SetRate(ClientScope::Current, rate)))
+	}
+	fn set_voice(&mut self, voice: String) -> BackendFuture<'_> {
+		Box::pin(self.send(Request::SetSynthesisVoice(ClientScope::Current, voice)))
+	}
+	fn configure(&mut self, request: Request) -> BackendFuture<'_> {
+		Box::pin(self.send(request))
+	}
+	fn close(&mut self) -> BackendFuture<'_> {
+		Box::pin(async move {
+			tracing::debug!("Saying goodbye message.");
+			self.client.send(Request::Speak).await?.receive().await?;
+			self.client
+				.send(Request::SendLines(Vec::from(
+					["Quitting Odilia".to_string()],
+				)))
+				.await?
+				.receive()
+				.await?;
+			tracing::debug!("Attempting to quit SSIP.");
+			let response = self.client.send(Request::Quit).await?.receive().await?;
+			tracing::debug!(?response, "Recieved response from server");
+			Ok(())
+		})
+	}
+}
+
+/// A handler task for incoming SSIP requests, speaking them through speech-dispatcher.
 /// This function will run untill it gets canceled via the cancellation token
 ///
 /// # Errors
 ///
-/// This function will return an error if anything within it fails. It may fail to read a value from the channel, it may fail to run an SSIP command, or fail to parse the response.
+/// This function will return an error if anything within it fails. It may fail to run an SSIP command, or fail to parse the response.
 /// Errors may also be returned during cleanup via the `cancellation_token` parameter, since shutting down the connection to speech dispatcher can also potentially error.
 /// Any of these failures will result in this function exiting with an `Err(_)` variant.
-#[tracing::instrument(level = "debug", skip_all, err)]
+///
+/// Messages spoken with a priority in `overrides` use the rate and voice given there, and the normal ones are restored once the message is sent.
 pub async fn handle_ssip_commands(
-	mut client: AsyncClient<BufReader<OwnedReadHalf>, BufWriter<OwnedWriteHalf>>,
+	client: AsyncClient<BufReader<OwnedReadHalf>, BufWriter<OwnedWriteHalf>>,
 	requests: Receiver<Request>,
+	overrides: HashMap<Priority, SpeechParams>,
 	shutdown: CancellationToken,
 ) -> eyre::Result<()> {
-	tokio::pin!(requests);
+	let backend = SpeechDispatcherBackend::new(client, overrides);
+	run_backend(Box::new(backend), requests, shutdown).await
+}
+
+/// A stand-in for [`handle_ssip_commands`] which accepts and drops every request without connecting to speech dispatcher.
+/// Useful for running Odilia silently, when debugging the cache or event handling.
+/// This function will run untill it gets canceled via the cancellation token, or every sender is dropped.
+#[tracing::instrument(level = "debug", skip_all)]
+pub async fn discard_ssip_commands(mut requests: Receiver<Request>, shutdown: CancellationToken) {
 	loop {
 		tokio::select! {
-				      request_option = requests.recv() => {
-					      if let Some(request) = request_option {
-		  tracing::debug!(?request, "SSIP command received");
-		  let response = client
-		    .send(request).await?
-		    .receive().await?;
-		  tracing::debug!(?response, "Recieved response from server");
+			request_option = requests.recv() => {
+				let Some(request) = request_option else { break };
+				tracing::trace!(?request, "SSIP command discarded");
+			}
+			() = shutdown.cancelled() => break,
 		}
-				      }
-				      () = shutdown.cancelled() => {
-		      tracing::debug!("Saying goodbye message.");
-		      client
-			      .send(Request::Speak).await?
-			      .receive().await?;
-		      client
-			      .send(Request::SendLines(Vec::from(["Quitting Odilia".to_string()]))).await?
-			      .receive().await?;
-		      tracing::debug!("Attempting to quit SSIP.");
-		      let response = client
-			.send(Request::Quit).await?
-			.receive().await?;
-		      tracing::debug!(?response, "Recieved response from server");
-					      tracing::debug!("SSIP command interpreter shutdown completed");
-					      break;
-				      }
-			      }
-	}
-	Ok(())
+	}
+	tracing::debug!("SSIP command discarder shutdown completed");
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{effective_params, param_requests, select_output_module, SpeechParams};
+	use ssip_client_async::{ClientScope, Priority, Request};
+	use std::collections::HashMap;
+
+	fn modules() -> Vec<String> {
+		vec!["espeak-ng".to_string(), "pico".to_string(), "dummy".to_string()]
+	}
+
+	#[test]
+	fn configured_module_is_selected_when_available() {
+		assert_eq!(select_output_module("pico", &modules()), Some("pico".to_string()));
+	}
+	#[test]
+	fn module_selection_ignores_case() {
+		assert_eq!(
+			select_output_module("eSpeak-NG", &modules()),
+			Some("espeak-ng".to_string())
+		);
+	}
+	#[test]
+	fn missing_module_falls_back_to_default() {
+		assert_eq!(select_output_module("festival", &modules()), None);
+		assert_eq!(select_output_module("", &modules()), None);
+		assert_eq!(select_output_module("pico", &[]), None);
+	}
+
+	fn base() -> SpeechParams {
+		SpeechParams { rate: Some(50), voice: Some("English (America)+Max".to_string()) }
+	}
+	fn overrides() -> HashMap<Priority, SpeechParams> {
+		[
+			(Priority::Important, SpeechParams { rate: Some(70), voice: None }),
+			(
+				Priority::Message,
+				SpeechParams {
+					rate: None,
+					voice: Some("English (Great Britain)".to_string()),
+				},
+			),
+		]
+		.into_iter()
+		.collect()
+	}
+
+	#[test]
+	fn overrides_only_replace_the_params_they_set() {
+		let overrides = overrides();
+		let important = effective_params(&base(), &overrides, &Priority::Important);
+		assert_eq!(important, SpeechParams { rate: Some(70), ..base() });
+		let message = effective_params(&base(), &overrides, &Priority::Message);
+		assert_eq!(
+			message,
+			SpeechParams {
+				voice: Some("English (Great Britain)".to_string()),
+				..base()
+			}
+		);
+		assert_eq!(effective_params(&base(), &overrides, &Priority::Text), base());
+	}
+	#[test]
+	fn only_changed_params_are_sent() {
+		let fast = SpeechParams { rate: Some(70), ..base() };
+		assert!(param_requests(&base(), &base()).is_empty());
+		assert_eq!(
+			param_requests(&base(), &fast),
+			[Request::SetRate(ClientScope::Current, 70)]
+		);
+		assert_eq!(
+			param_requests(&fast, &base()),
+			[Request::SetRate(ClientScope::Current, 50)]
+		);
+		assert!(param_requests(&base(), &SpeechParams::default()).is_empty());
+	}
 }
@@ -0,0 +1,101 @@
+//! A speaker which writes what would have been spoken to standard output, for running Odilia headless, like in CI or over SSH.
+
+use ssip_client_async::{Priority, Request};
+use std::io::{self, Write};
+use tokio::sync::mpsc::Receiver;
+use tokio_util::sync::CancellationToken;
+
+/// Turns SSIP requests into lines of text, keeping track of the priority set for the next message.
+#[derive(Debug)]
+pub struct StdoutSpeaker {
+	priority: Priority,
+}
+
+impl Default for StdoutSpeaker {
+	fn default() -> Self {
+		Self { priority: Priority::Text }
+	}
+}
+
+impl StdoutSpeaker {
+	/// The line to print for `request`, if it is one which would be heard.
+	/// Text is prefixed with its priority, like `[important] text`, and cancelling speech is shown as `[CANCEL]`, so interruptions stay visible.
+	pub fn render(&mut self, request: Request) -> Option<String> {
+		match request {
+			Request::SetPriority(priority) => {
+				self.priority = priority;
+				None
+			}
+			Request::SendLine(line) => Some(self.line(&line)),
+			Request::SendLines(lines) => Some(self.line(&lines.join(" "))),
+			Request::Cancel(_) | Request::Stop(_) => Some("[CANCEL]".to_string()),
+			_ => None,
+		}
+	}
+	/// Write the line for `request` to `out`, if there is one.
+	/// # Errors
+	/// If writing to `out` fails.
+	pub fn write(&mut self, request: Request, out: &mut impl Write) -> io::Result<()> {
+		match self.render(request) {
+			Some(line) => writeln!(out, "{line}"),
+			None => Ok(()),
+		}
+	}
+	fn line(&self, text: &str) -> String {
+		format!("[{}] {text}", self.priority)
+	}
+}
+
+/// A stand-in for [`crate::handle_ssip_commands`] which prints every message to standard output instead of speaking it.
+/// This function will run untill it gets canceled via the cancellation token, or every sender is dropped.
+///
+/// # Errors
+///
+/// If standard output can not be written to.
+#[tracing::instrument(level = "debug", skip_all, err)]
+pub async fn print_ssip_commands(
+	mut requests: Receiver<Request>,
+	shutdown: CancellationToken,
+) -> eyre::Result<()> {
+	let mut speaker = StdoutSpeaker::default();
+	loop {
+		tokio::select! {
+			request_option = requests.recv() => {
+				let Some(request) = request_option else { break };
+				speaker.write(request, &mut io::stdout().lock())?;
+			}
+			() = shutdown.cancelled() => break,
+		}
+	}
+	tracing::debug!("SSIP command printer shutdown completed");
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::StdoutSpeaker;
+	use ssip_client_async::{ClientScope, MessageScope, Priority, Request};
+
+	#[test]
+	fn messages_are_printed_with_their_priority() {
+		let mut speaker = StdoutSpeaker::default();
+		let mut out = Vec::new();
+		for request in [
+			Request::SendLines(vec!["Welcome to".to_string(), "Odilia!".to_string()]),
+			Request::SetPriority(Priority::Important),
+			Request::Speak,
+			Request::SendLines(vec!["Battery low".to_string()]),
+			Request::Cancel(MessageScope::All),
+			Request::SetRate(ClientScope::Current, 20),
+			Request::SetPriority(Priority::Progress),
+			Request::SendLine("Loading".to_string()),
+		] {
+			speaker.write(request, &mut out)
+				.expect("writing to a Vec can not fail");
+		}
+		assert_eq!(
+			String::from_utf8(out).expect("output should be UTF-8"),
+			"[text] Welcome to Odilia!\n[important] Battery low\n[CANCEL]\n[progress] Loading\n"
+		);
+	}
+}